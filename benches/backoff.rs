@@ -0,0 +1,88 @@
+#![cfg(not(feature = "poison"))]
+#![feature(test)]
+
+extern crate test;
+
+use std::cell::UnsafeCell;
+use std::hint;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use spinlock::Mutex;
+use test::Bencher;
+
+const NUM_INCREMENTS: usize = 1_000;
+
+/// Spinlock without backoff used as a baseline.
+struct TightMutex {
+    data: UnsafeCell<usize>,
+    lock: AtomicBool,
+}
+
+unsafe impl Sync for TightMutex {}
+
+impl TightMutex {
+    const fn new() -> Self {
+        Self {
+            data: UnsafeCell::new(0),
+            lock: AtomicBool::new(false),
+        }
+    }
+
+    fn increment(&self) {
+        while self.lock.swap(true, Ordering::Acquire) {
+            while self.lock.load(Ordering::Relaxed) {
+                hint::spin_loop();
+            }
+        }
+
+        unsafe { *self.data.get() += 1 };
+        self.lock.store(false, Ordering::Release);
+    }
+}
+
+fn contend<L, F>(num_threads: usize, lock: &Arc<L>, increment: F)
+where
+    L: Send + Sync + 'static,
+    F: Fn(&L) + Copy + Send + 'static,
+{
+    let threads: Vec<_> = (0..num_threads)
+        .map(|_| {
+            let lock = Arc::clone(lock);
+            thread::spawn(move || {
+                for _ in 0..NUM_INCREMENTS {
+                    increment(&lock);
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}
+
+#[bench]
+fn tight_spin_8_threads(b: &mut Bencher) {
+    let lock = Arc::new(TightMutex::new());
+    b.iter(|| contend(8, &lock, TightMutex::increment));
+}
+
+#[bench]
+fn backoff_8_threads(b: &mut Bencher) {
+    let lock = Arc::new(Mutex::new(0_usize));
+    b.iter(|| contend(8, &lock, |m| *m.lock() += 1));
+}
+
+#[bench]
+fn tight_spin_16_threads(b: &mut Bencher) {
+    let lock = Arc::new(TightMutex::new());
+    b.iter(|| contend(16, &lock, TightMutex::increment));
+}
+
+#[bench]
+fn backoff_16_threads(b: &mut Bencher) {
+    let lock = Arc::new(Mutex::new(0_usize));
+    b.iter(|| contend(16, &lock, |m| *m.lock() += 1));
+}
@@ -0,0 +1,76 @@
+#![feature(test)]
+
+extern crate test;
+
+use std::sync::Arc;
+use std::thread;
+
+use test::{black_box, Bencher};
+
+const NUM_INCREMENTS: usize = 10_000;
+const NUM_THREADS: usize = 4;
+
+/// Runs `NUM_THREADS` threads which each increment the counter behind the lock
+/// `NUM_INCREMENTS` times.
+fn contend<L, F>(lock: &Arc<L>, increment: F)
+where
+    L: Send + Sync + 'static,
+    F: Fn(&L) + Copy + Send + 'static,
+{
+    let threads: Vec<_> = (0..NUM_THREADS)
+        .map(|_| {
+            let lock = Arc::clone(lock);
+            thread::spawn(move || {
+                for _ in 0..NUM_INCREMENTS {
+                    increment(&lock);
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}
+
+#[bench]
+fn contended_spinlock(b: &mut Bencher) {
+    let lock = Arc::new(spinlock::Mutex::new(0_usize));
+    b.iter(|| contend(&lock, |m| m.with_lock(|count| *count += 1)));
+}
+
+#[bench]
+fn contended_std(b: &mut Bencher) {
+    let lock = Arc::new(std::sync::Mutex::new(0_usize));
+    b.iter(|| contend(&lock, |m| *m.lock().unwrap() += 1));
+}
+
+#[bench]
+fn contended_parking_lot(b: &mut Bencher) {
+    let lock = Arc::new(parking_lot::Mutex::new(0_usize));
+    b.iter(|| contend(&lock, |m| *m.lock() += 1));
+}
+
+#[bench]
+fn uncontended_spinlock(b: &mut Bencher) {
+    let lock = spinlock::Mutex::new(0_usize);
+    b.iter(|| lock.with_lock(|count| *count = black_box(*count + 1)));
+}
+
+#[bench]
+fn uncontended_std(b: &mut Bencher) {
+    let lock = std::sync::Mutex::new(0_usize);
+    b.iter(|| {
+        let mut count = lock.lock().unwrap();
+        *count = black_box(*count + 1);
+    });
+}
+
+#[bench]
+fn uncontended_parking_lot(b: &mut Bencher) {
+    let lock = parking_lot::Mutex::new(0_usize);
+    b.iter(|| {
+        let mut count = lock.lock();
+        *count = black_box(*count + 1);
+    });
+}
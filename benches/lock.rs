@@ -0,0 +1,48 @@
+#![feature(test)]
+
+extern crate test;
+
+use std::hint;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use spinlock::RawMutex;
+use test::{black_box, Bencher};
+
+const NUM_LOCKS: usize = 1_000;
+
+/// Previous `lock` spinning in the same function as the fast path, used as a
+/// baseline.
+fn fused_lock(lock: &AtomicBool) {
+    loop {
+        if !lock.swap(true, Ordering::Acquire) {
+            return;
+        }
+
+        while lock.load(Ordering::Relaxed) {
+            hint::spin_loop();
+        }
+    }
+}
+
+#[bench]
+fn fused_uncontended(b: &mut Bencher) {
+    let lock = AtomicBool::new(false);
+    b.iter(|| {
+        for _ in 0..NUM_LOCKS {
+            fused_lock(black_box(&lock));
+            lock.store(false, Ordering::Release);
+        }
+    });
+}
+
+#[bench]
+fn split_uncontended(b: &mut Bencher) {
+    let raw = RawMutex::new();
+    b.iter(|| {
+        for _ in 0..NUM_LOCKS {
+            black_box(&raw).lock();
+            // SAFETY: The lock was acquired above.
+            unsafe { raw.unlock() };
+        }
+    });
+}
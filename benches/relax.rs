@@ -0,0 +1,45 @@
+#![cfg(not(feature = "poison"))]
+#![feature(test)]
+
+extern crate test;
+
+use std::sync::Arc;
+use std::thread;
+
+use spinlock::{Mutex, PoliteSpin, Relax, Spin};
+use test::Bencher;
+
+const NUM_INCREMENTS: usize = 1_000;
+
+/// Runs twice as many threads as there are cores, each incrementing the
+/// counter behind the mutex `NUM_INCREMENTS` times.
+fn oversubscribed<R: Relax + 'static>(mutex: &Arc<Mutex<usize, R>>) {
+    let num_threads = 2 * thread::available_parallelism().map_or(1, usize::from);
+
+    let threads: Vec<_> = (0..num_threads)
+        .map(|_| {
+            let mutex = Arc::clone(mutex);
+            thread::spawn(move || {
+                for _ in 0..NUM_INCREMENTS {
+                    *mutex.lock() += 1;
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}
+
+#[bench]
+fn spin_oversubscribed(b: &mut Bencher) {
+    let mutex = Arc::new(Mutex::<_, Spin>::with_relax(0));
+    b.iter(|| oversubscribed(&mutex));
+}
+
+#[bench]
+fn polite_spin_oversubscribed(b: &mut Bencher) {
+    let mutex = Arc::new(Mutex::<_, PoliteSpin>::with_relax(0));
+    b.iter(|| oversubscribed(&mutex));
+}
@@ -0,0 +1,59 @@
+#![feature(test)]
+
+extern crate test;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use spinlock::RawMutex;
+use test::{black_box, Bencher};
+
+const NUM_ATTEMPTS: usize = 1_000;
+
+/// Previous `try_lock` loading the lock before swapping it, used as a baseline.
+fn load_swap_try_lock(lock: &AtomicBool) -> bool {
+    !lock.load(Ordering::Relaxed) && !lock.swap(true, Ordering::Acquire)
+}
+
+#[bench]
+fn load_swap_uncontended(b: &mut Bencher) {
+    let lock = AtomicBool::new(false);
+    b.iter(|| {
+        for _ in 0..NUM_ATTEMPTS {
+            black_box(load_swap_try_lock(&lock));
+            lock.store(false, Ordering::Release);
+        }
+    });
+}
+
+#[bench]
+fn compare_exchange_uncontended(b: &mut Bencher) {
+    let raw = RawMutex::new();
+    b.iter(|| {
+        for _ in 0..NUM_ATTEMPTS {
+            black_box(raw.try_lock());
+            // SAFETY: The lock was acquired by `try_lock`.
+            unsafe { raw.unlock() };
+        }
+    });
+}
+
+#[bench]
+fn load_swap_locked(b: &mut Bencher) {
+    let lock = AtomicBool::new(true);
+    b.iter(|| {
+        for _ in 0..NUM_ATTEMPTS {
+            black_box(load_swap_try_lock(&lock));
+        }
+    });
+}
+
+#[bench]
+fn compare_exchange_locked(b: &mut Bencher) {
+    let raw = RawMutex::new();
+    raw.lock();
+    b.iter(|| {
+        for _ in 0..NUM_ATTEMPTS {
+            black_box(raw.try_lock());
+        }
+    });
+}
@@ -0,0 +1,77 @@
+#![feature(test)]
+
+extern crate test;
+
+use std::hint;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use spinlock::RwLock;
+use test::Bencher;
+
+// On x86 both compare and swap variants compile to the same `lock cmpxchg`
+// so no difference is expected. On LL/SC architectures (ARM, RISC-V) the
+// strong variant is an inner retry loop around `ldxr`/`stxr` while the weak
+// one is a single attempt, which shrinks the spin loop of the write lock.
+
+const NUM_INCREMENTS: usize = 1_000;
+const NUM_THREADS: usize = 4;
+
+/// Previous write lock retrying with a strong compare and swap, used as a
+/// baseline.
+fn strong_write_increment(lock: &AtomicUsize, count: &AtomicUsize) {
+    while lock
+        .compare_exchange(0, 1, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        while lock.load(Ordering::Relaxed) != 0 {
+            hint::spin_loop();
+        }
+    }
+
+    count.fetch_add(1, Ordering::Relaxed);
+    lock.store(0, Ordering::Release);
+}
+
+#[bench]
+fn strong_cas_write(b: &mut Bencher) {
+    let lock = Arc::new((AtomicUsize::new(0), AtomicUsize::new(0)));
+    b.iter(|| {
+        let threads: Vec<_> = (0..NUM_THREADS)
+            .map(|_| {
+                let lock = Arc::clone(&lock);
+                thread::spawn(move || {
+                    for _ in 0..NUM_INCREMENTS {
+                        strong_write_increment(&lock.0, &lock.1);
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    });
+}
+
+#[bench]
+fn weak_cas_write(b: &mut Bencher) {
+    let rwlock = Arc::new(RwLock::new(0_usize));
+    b.iter(|| {
+        let threads: Vec<_> = (0..NUM_THREADS)
+            .map(|_| {
+                let rwlock = Arc::clone(&rwlock);
+                thread::spawn(move || {
+                    for _ in 0..NUM_INCREMENTS {
+                        *rwlock.write() += 1;
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    });
+}
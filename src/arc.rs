@@ -0,0 +1,572 @@
+use alloc::sync::Arc;
+use core::ops::{Deref, DerefMut};
+
+use crate::mutex::{Mutex, MutexGuard};
+#[cfg(feature = "poison")]
+use crate::poison::LockResult;
+use crate::relax::{Relax, Spin};
+use crate::rwlock::{RwLock, RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard};
+
+impl<T: 'static, R: Relax + 'static> Mutex<T, R> {
+    /// Acquires the lock through an `Arc`, blocking the current thread until the
+    /// lock is available.
+    ///
+    /// The returned guard owns a reference to the mutex so it is not bound to
+    /// the lifetime of a borrow and can be moved into a `'static` closure or
+    /// another thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    /// use std::sync::Arc;
+    /// use std::thread;
+    ///
+    /// let mutex = Arc::new(Mutex::new(1));
+    /// let mut guard = mutex.lock_arc();
+    ///
+    /// thread::spawn(move || {
+    ///     *guard = 42;
+    /// }).join().expect("thread::spawn failed");
+    /// assert_eq!(*mutex.lock_arc(), 42);
+    /// ```
+    #[cfg(not(feature = "poison"))]
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn lock_arc(self: &Arc<Self>) -> ArcMutexGuard<T, R> {
+        ArcMutexGuard::acquire(Arc::clone(self))
+    }
+
+    /// Acquires the lock through an `Arc`, blocking the current thread until the
+    /// lock is available.
+    ///
+    /// The returned guard owns a reference to the mutex so it is not bound to
+    /// the lifetime of a borrow and can be moved into a `'static` closure or
+    /// another thread.
+    ///
+    /// # Errors
+    /// If another thread panicked while holding the lock, the mutex is poisoned
+    /// and the guard is returned within a [`PoisonError`](crate::PoisonError).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    /// use std::sync::Arc;
+    /// use std::thread;
+    ///
+    /// let mutex = Arc::new(Mutex::new(1));
+    /// let mut guard = mutex.lock_arc().unwrap();
+    ///
+    /// thread::spawn(move || {
+    ///     *guard = 42;
+    /// }).join().expect("thread::spawn failed");
+    /// assert_eq!(*mutex.lock_arc().unwrap(), 42);
+    /// ```
+    #[cfg(feature = "poison")]
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn lock_arc(self: &Arc<Self>) -> LockResult<ArcMutexGuard<T, R>> {
+        self.check_poison(ArcMutexGuard::acquire(Arc::clone(self)))
+    }
+
+    /// Tries to acquire the lock through an `Arc`. If the lock is not available
+    /// returns `None`.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(Mutex::new(1));
+    ///
+    /// let guard = mutex.try_lock_arc().unwrap();
+    /// assert!(mutex.try_lock_arc().is_none());
+    /// ```
+    #[cfg(not(feature = "poison"))]
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_lock_arc(self: &Arc<Self>) -> Option<ArcMutexGuard<T, R>> {
+        ArcMutexGuard::try_acquire(Arc::clone(self))
+    }
+
+    /// Tries to acquire the lock through an `Arc`. If the lock is not available
+    /// returns `None`.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// If another thread panicked while holding the lock, the mutex is poisoned
+    /// and the guard is returned within a [`PoisonError`](crate::PoisonError).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(Mutex::new(1));
+    ///
+    /// let guard = mutex.try_lock_arc().unwrap().unwrap();
+    /// assert!(mutex.try_lock_arc().is_none());
+    /// ```
+    #[cfg(feature = "poison")]
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_lock_arc(self: &Arc<Self>) -> Option<LockResult<ArcMutexGuard<T, R>>> {
+        ArcMutexGuard::try_acquire(Arc::clone(self)).map(|guard| self.check_poison(guard))
+    }
+
+    /// Acquires the lock through an `Arc`, an alias of
+    /// [`lock_arc`](self::Mutex::lock_arc) named after `tokio::sync::Mutex`
+    /// to ease migrations.
+    ///
+    /// Unlike `tokio` this function is synchronous, it blocks the current
+    /// thread by spinning until the lock is available.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(Mutex::new(1));
+    ///
+    /// *mutex.lock_owned() = 42;
+    /// assert_eq!(*mutex.lock(), 42);
+    /// ```
+    #[cfg(not(feature = "poison"))]
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn lock_owned(self: &Arc<Self>) -> ArcMutexGuard<T, R> {
+        self.lock_arc()
+    }
+
+    /// Acquires the lock through an `Arc`, an alias of
+    /// [`lock_arc`](self::Mutex::lock_arc) named after `tokio::sync::Mutex`
+    /// to ease migrations.
+    ///
+    /// Unlike `tokio` this function is synchronous, it blocks the current
+    /// thread by spinning until the lock is available.
+    ///
+    /// # Errors
+    /// If another thread panicked while holding the lock, the mutex is poisoned
+    /// and the guard is returned within a [`PoisonError`](crate::PoisonError).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(Mutex::new(1));
+    ///
+    /// *mutex.lock_owned().unwrap() = 42;
+    /// assert_eq!(*mutex.lock().unwrap(), 42);
+    /// ```
+    #[cfg(feature = "poison")]
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn lock_owned(self: &Arc<Self>) -> LockResult<ArcMutexGuard<T, R>> {
+        self.lock_arc()
+    }
+
+    /// Tries to acquire the lock through an `Arc`, an alias of
+    /// [`try_lock_arc`](self::Mutex::try_lock_arc) named after
+    /// `tokio::sync::Mutex` to ease migrations.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(Mutex::new(1));
+    ///
+    /// let guard = mutex.try_lock_owned().unwrap();
+    /// assert!(mutex.try_lock_owned().is_none());
+    /// ```
+    #[cfg(not(feature = "poison"))]
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_lock_owned(self: &Arc<Self>) -> Option<ArcMutexGuard<T, R>> {
+        self.try_lock_arc()
+    }
+
+    /// Tries to acquire the lock through an `Arc`, an alias of
+    /// [`try_lock_arc`](self::Mutex::try_lock_arc) named after
+    /// `tokio::sync::Mutex` to ease migrations.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// If another thread panicked while holding the lock, the mutex is poisoned
+    /// and the guard is returned within a [`PoisonError`](crate::PoisonError).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(Mutex::new(1));
+    ///
+    /// let guard = mutex.try_lock_owned().unwrap().unwrap();
+    /// assert!(mutex.try_lock_owned().is_none());
+    /// ```
+    #[cfg(feature = "poison")]
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_lock_owned(self: &Arc<Self>) -> Option<LockResult<ArcMutexGuard<T, R>>> {
+        self.try_lock_arc()
+    }
+}
+
+/// Guard structure owning a reference to a locked [`Mutex`](crate::Mutex)
+/// through an `Arc`, used to release the lock when dropped.
+///
+/// This structure is created by [`lock_arc`](crate::Mutex::lock_arc) and
+/// [`try_lock_arc`](crate::Mutex::try_lock_arc) on [`Mutex`](crate::Mutex).
+///
+/// Unlike [`MutexGuard`](crate::MutexGuard) it is `'static` and it can be sent
+/// to another thread, unless the `critical-section` feature is enabled since
+/// the critical section must be exited where it was entered.
+#[derive(Debug)]
+#[must_use = "if unused the lock is immediately released"]
+pub struct ArcMutexGuard<T: 'static, R: Relax + 'static = Spin> {
+    // Guard of the mutex kept alive by the `Arc` below, it is declared first
+    // so that it is dropped before it.
+    guard: MutexGuard<'static, T, R>,
+    mutex: Arc<Mutex<T, R>>,
+}
+
+impl<T: 'static, R: Relax + 'static> ArcMutexGuard<T, R> {
+    /// Acquires the lock of the given mutex regardless of the poisoning.
+    fn acquire(mutex: Arc<Mutex<T, R>>) -> Self {
+        // SAFETY: The mutex is kept alive by the `Arc` stored along the guard,
+        // which is only dropped after it.
+        let guard = unsafe { &*Arc::as_ptr(&mutex) }.acquire();
+        Self { guard, mutex }
+    }
+
+    /// Tries to acquire the lock of the given mutex regardless of the poisoning.
+    fn try_acquire(mutex: Arc<Mutex<T, R>>) -> Option<Self> {
+        // SAFETY: The mutex is kept alive by the `Arc` stored along the guard,
+        // which is only dropped after it.
+        let guard = unsafe { &*Arc::as_ptr(&mutex) }.try_acquire()?;
+        Some(Self { guard, mutex })
+    }
+
+    /// Returns the mutex locked by the guard.
+    ///
+    /// This is an associated function that needs to be used as `ArcMutexGuard::mutex(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{ArcMutexGuard, Mutex};
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(Mutex::new(1));
+    ///
+    /// # #[cfg(feature = "poison")]
+    /// # let guard = mutex.try_lock_arc().unwrap().unwrap();
+    /// # #[cfg(not(feature = "poison"))]
+    /// let guard = mutex.try_lock_arc().unwrap();
+    /// assert!(Arc::ptr_eq(ArcMutexGuard::mutex(&guard), &mutex));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn mutex(guard: &Self) -> &Arc<Mutex<T, R>> {
+        &guard.mutex
+    }
+}
+
+impl<T: 'static, R: Relax + 'static> Deref for ArcMutexGuard<T, R> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<T: 'static, R: Relax + 'static> DerefMut for ArcMutexGuard<T, R> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+// SAFETY: The guard only gives access to the data, which can be sent to another
+// thread, and the lock can be released from any thread. The critical section
+// however must be exited on the thread which entered it.
+#[cfg(not(feature = "critical-section"))]
+#[allow(clippy::non_send_fields_in_send_ty)]
+unsafe impl<T: Send + Sync + 'static, R: Relax + 'static> Send for ArcMutexGuard<T, R> {}
+
+impl<T: 'static, R: Relax + 'static> RwLock<T, R> {
+    /// Acquires the rwlock with shared read access through an `Arc`, blocking
+    /// the thread until it is available.
+    ///
+    /// The returned guard owns a reference to the rwlock so it is not bound to
+    /// the lifetime of a borrow and can be moved into a `'static` closure or
+    /// another thread.
+    ///
+    /// It also waits while the maximum number of shared read accesses, given
+    /// by [`max_readers`](self::RwLock::max_readers), is held.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    /// use std::sync::Arc;
+    /// use std::thread;
+    ///
+    /// let rwlock = Arc::new(RwLock::new(1));
+    /// let guard = rwlock.read_arc();
+    ///
+    /// thread::spawn(move || {
+    ///     assert_eq!(*guard, 1);
+    /// }).join().expect("thread::spawn failed");
+    /// assert_eq!(rwlock.reader_count(), 0);
+    /// ```
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn read_arc(self: &Arc<Self>) -> ArcRwLockReadGuard<T, R> {
+        let rwlock = Arc::clone(self);
+        // SAFETY: The rwlock is kept alive by the `Arc` stored along the guard,
+        // which is only dropped after it.
+        let guard = unsafe { &*Arc::as_ptr(&rwlock) }.read();
+        ArcRwLockReadGuard { guard, rwlock }
+    }
+
+    /// Acquires the rwlock with shared read access through an `Arc`, blocking
+    /// the thread until it is available, even if the current thread already
+    /// holds a shared read access.
+    ///
+    /// This is the owned counterpart of
+    /// [`read_recursive`](self::RwLock::read_recursive): it does not wait for
+    /// the writers waiting in [`write_biased`](self::RwLock::write_biased), so
+    /// it can't deadlock when called while holding another read guard on the
+    /// same rwlock.
+    ///
+    /// The returned guard owns a reference to the rwlock, the shared read
+    /// access is released before that reference when the guard is dropped,
+    /// even if it is the last one.
+    ///
+    /// It also waits while the maximum number of shared read accesses, given
+    /// by [`max_readers`](self::RwLock::max_readers), is held.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    /// use std::sync::Arc;
+    ///
+    /// let rwlock = Arc::new(RwLock::new(1));
+    ///
+    /// let r1 = rwlock.read_arc();
+    /// let r2 = rwlock.read_arc_recursive();
+    /// assert_eq!(*r1 + *r2, 2);
+    /// drop(rwlock);
+    /// drop((r1, r2));
+    /// ```
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn read_arc_recursive(self: &Arc<Self>) -> ArcRwLockReadGuard<T, R> {
+        let rwlock = Arc::clone(self);
+        // SAFETY: The rwlock is kept alive by the `Arc` stored along the guard,
+        // which is only dropped after it.
+        let guard = unsafe { &*Arc::as_ptr(&rwlock) }.read_recursive();
+        ArcRwLockReadGuard { guard, rwlock }
+    }
+
+    /// Acquires the rwlock with exclusive write access through an `Arc`,
+    /// blocking the thread until it is available.
+    ///
+    /// The returned guard owns a reference to the rwlock so it is not bound to
+    /// the lifetime of a borrow and can be moved into a `'static` closure or
+    /// another thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    /// use std::sync::Arc;
+    /// use std::thread;
+    ///
+    /// let rwlock = Arc::new(RwLock::new(1));
+    /// let mut guard = rwlock.write_arc();
+    ///
+    /// thread::spawn(move || {
+    ///     *guard = 42;
+    /// }).join().expect("thread::spawn failed");
+    /// assert_eq!(*rwlock.read(), 42);
+    /// ```
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn write_arc(self: &Arc<Self>) -> ArcRwLockWriteGuard<T, R> {
+        let rwlock = Arc::clone(self);
+        // SAFETY: The rwlock is kept alive by the `Arc` stored along the guard,
+        // which is only dropped after it.
+        let guard = unsafe { &*Arc::as_ptr(&rwlock) }.write();
+        ArcRwLockWriteGuard { guard, rwlock }
+    }
+
+    /// Acquires the rwlock with upgradeable read access through an `Arc`,
+    /// blocking the thread until it is available.
+    ///
+    /// The returned guard owns a reference to the rwlock so it is not bound to
+    /// the lifetime of a borrow and can be moved into a `'static` closure or
+    /// another thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    /// use std::sync::Arc;
+    ///
+    /// let rwlock = Arc::new(RwLock::new(1));
+    ///
+    /// let upgradeable = rwlock.upgradeable_read_arc();
+    /// assert_eq!(*upgradeable, 1);
+    /// assert!(rwlock.try_upgradeable_read().is_none());
+    /// ```
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn upgradeable_read_arc(self: &Arc<Self>) -> ArcRwLockUpgradableReadGuard<T, R> {
+        let rwlock = Arc::clone(self);
+        // SAFETY: The rwlock is kept alive by the `Arc` stored along the guard,
+        // which is only dropped after it.
+        let guard = unsafe { &*Arc::as_ptr(&rwlock) }.upgradeable_read();
+        ArcRwLockUpgradableReadGuard { guard, rwlock }
+    }
+}
+
+/// Guard structure owning a reference to a [`RwLock`](crate::RwLock) through
+/// an `Arc`, used to release the shared read access when dropped.
+///
+/// This structure is created by [`read_arc`](crate::RwLock::read_arc) and
+/// [`read_arc_recursive`](crate::RwLock::read_arc_recursive) on
+/// [`RwLock`](crate::RwLock). Unlike [`RwLockReadGuard`](crate::RwLockReadGuard)
+/// it is `'static` and it can be sent to another thread.
+#[derive(Debug)]
+#[must_use = "if unused the lock is immediately released"]
+pub struct ArcRwLockReadGuard<T: 'static, R: Relax + 'static = Spin> {
+    // Guard of the rwlock kept alive by the `Arc` below, it is declared first
+    // so that it is dropped before it.
+    guard: RwLockReadGuard<'static, T, R>,
+    rwlock: Arc<RwLock<T, R>>,
+}
+
+impl<T: 'static, R: Relax + 'static> ArcRwLockReadGuard<T, R> {
+    /// Returns the rwlock locked by the guard.
+    ///
+    /// This is an associated function that needs to be used as `ArcRwLockReadGuard::rwlock(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    #[inline]
+    #[must_use]
+    pub const fn rwlock(guard: &Self) -> &Arc<RwLock<T, R>> {
+        &guard.rwlock
+    }
+}
+
+impl<T: 'static, R: Relax + 'static> Deref for ArcRwLockReadGuard<T, R> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+// SAFETY: The guard only gives a shared access to the data, which can be
+// shared with another thread, and the read access can be released from any
+// thread.
+#[allow(clippy::non_send_fields_in_send_ty)]
+unsafe impl<T: Send + Sync + 'static, R: Relax + 'static> Send for ArcRwLockReadGuard<T, R> {}
+
+/// Guard structure owning a reference to a [`RwLock`](crate::RwLock) through
+/// an `Arc`, used to release the exclusive write access when dropped.
+///
+/// This structure is created by [`write_arc`](crate::RwLock::write_arc) on
+/// [`RwLock`](crate::RwLock). Unlike [`RwLockWriteGuard`](crate::RwLockWriteGuard)
+/// it is `'static` and it can be sent to another thread.
+#[derive(Debug)]
+#[must_use = "if unused the lock is immediately released"]
+pub struct ArcRwLockWriteGuard<T: 'static, R: Relax + 'static = Spin> {
+    // Guard of the rwlock kept alive by the `Arc` below, it is declared first
+    // so that it is dropped before it.
+    guard: RwLockWriteGuard<'static, T, R>,
+    rwlock: Arc<RwLock<T, R>>,
+}
+
+impl<T: 'static, R: Relax + 'static> ArcRwLockWriteGuard<T, R> {
+    /// Returns the rwlock locked by the guard.
+    ///
+    /// This is an associated function that needs to be used as `ArcRwLockWriteGuard::rwlock(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    #[inline]
+    #[must_use]
+    pub const fn rwlock(guard: &Self) -> &Arc<RwLock<T, R>> {
+        &guard.rwlock
+    }
+}
+
+impl<T: 'static, R: Relax + 'static> Deref for ArcRwLockWriteGuard<T, R> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<T: 'static, R: Relax + 'static> DerefMut for ArcRwLockWriteGuard<T, R> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+// SAFETY: The guard only gives access to the data, which can be sent to another
+// thread, and the write access can be released from any thread.
+#[allow(clippy::non_send_fields_in_send_ty)]
+unsafe impl<T: Send + Sync + 'static, R: Relax + 'static> Send for ArcRwLockWriteGuard<T, R> {}
+
+/// Guard structure owning a reference to a [`RwLock`](crate::RwLock) through
+/// an `Arc`, used to release the upgradeable read access when dropped.
+///
+/// This structure is created by [`upgradeable_read_arc`](crate::RwLock::upgradeable_read_arc)
+/// on [`RwLock`](crate::RwLock). Unlike [`RwLockUpgradableReadGuard`](crate::RwLockUpgradableReadGuard)
+/// it is `'static` and it can be sent to another thread.
+#[derive(Debug)]
+#[must_use = "if unused the lock is immediately released"]
+pub struct ArcRwLockUpgradableReadGuard<T: 'static, R: Relax + 'static = Spin> {
+    // Guard of the rwlock kept alive by the `Arc` below, it is declared first
+    // so that it is dropped before it.
+    guard: RwLockUpgradableReadGuard<'static, T, R>,
+    rwlock: Arc<RwLock<T, R>>,
+}
+
+impl<T: 'static, R: Relax + 'static> ArcRwLockUpgradableReadGuard<T, R> {
+    /// Returns the rwlock locked by the guard.
+    ///
+    /// This is an associated function that needs to be used as `ArcRwLockUpgradableReadGuard::rwlock(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    #[inline]
+    #[must_use]
+    pub const fn rwlock(guard: &Self) -> &Arc<RwLock<T, R>> {
+        &guard.rwlock
+    }
+}
+
+impl<T: 'static, R: Relax + 'static> Deref for ArcRwLockUpgradableReadGuard<T, R> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+// SAFETY: The guard only gives a shared access to the data, which can be
+// shared with another thread, and the upgradeable read access can be released
+// from any thread.
+#[allow(clippy::non_send_fields_in_send_ty)]
+unsafe impl<T: Send + Sync + 'static, R: Relax + 'static> Send
+    for ArcRwLockUpgradableReadGuard<T, R>
+{
+}
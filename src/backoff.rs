@@ -0,0 +1,196 @@
+use crate::relax::{Relax, Spin};
+
+// Maximum step of the backoff, it spins at most 2^SPIN_LIMIT times per call.
+const SPIN_LIMIT: u32 = 6;
+
+/// Spins the CPU for a given number of iterations, relaxing with `R` on each one.
+#[inline]
+fn spin_for<R: Relax>(iterations: u32) {
+    // Every spin is a scheduling point for loom so spinning once is enough
+    // to let the other threads make progress without exploding the model.
+    #[cfg(feature = "loom")]
+    let iterations = iterations.min(1);
+
+    for _ in 0..iterations {
+        R::relax();
+    }
+}
+
+/// A helper for busy-wait loops with exponential backoff.
+///
+/// This is the same backoff strategy as the one used by the locks of this
+/// crate when waiting for a lock to be released. Each call to
+/// [`spin`](self::SpinWait::spin) spins twice as long as the previous one
+/// until the spin budget is exhausted, at which point the caller can decide
+/// to do something else such as yielding the thread.
+///
+/// It only relies on [`core::hint::spin_loop`] so it is `no_std` and does
+/// not allocate.
+///
+/// # Examples
+/// ```
+/// use spinlock::SpinWait;
+/// use std::sync::atomic::{AtomicBool, Ordering};
+///
+/// let flag = AtomicBool::new(true);
+/// let mut spin_wait = SpinWait::new();
+///
+/// while !flag.load(Ordering::Acquire) {
+///     if !spin_wait.spin() {
+///         std::thread::yield_now();
+///     }
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct SpinWait {
+    counter: u32,
+}
+
+impl SpinWait {
+    /// Creates a new `SpinWait` with a full spin budget.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { counter: 0 }
+    }
+
+    /// Spins an exponentially increasing number of times.
+    ///
+    /// Returns `false` without spinning once the spin budget is exhausted.
+    #[inline]
+    pub fn spin(&mut self) -> bool {
+        self.spin_with::<Spin>()
+    }
+
+    /// Spins an exponentially increasing number of times, relaxing with `R`.
+    #[inline]
+    pub(crate) fn spin_with<R: Relax>(&mut self) -> bool {
+        if self.counter > SPIN_LIMIT {
+            return false;
+        }
+
+        spin_for::<R>(1 << self.counter);
+        self.counter += 1;
+
+        true
+    }
+
+    /// Resets the spin budget.
+    #[inline]
+    pub const fn reset(&mut self) {
+        self.counter = 0;
+    }
+}
+
+/// Exponential backoff used when waiting for a lock to be released.
+///
+/// Spinning on the lock in a tight loop hammers the cache line under heavy
+/// contention, backing off exponentially leaves more room to the thread
+/// holding the lock.
+///
+/// A spin budget delays the backoff: the first `budget` calls spin a single
+/// time each, so the lock is checked as often as possible, before backing off
+/// exponentially.
+#[derive(Debug)]
+pub struct Backoff {
+    spin_wait: SpinWait,
+    // Remaining calls spinning a single time before backing off.
+    budget: u32,
+}
+
+impl Backoff {
+    /// Creates a new `Backoff` starting with a single spin.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::with_budget(0)
+    }
+
+    /// Creates a new `Backoff` spinning a single time on each of the first
+    /// `budget` calls before backing off.
+    #[inline]
+    #[must_use]
+    pub const fn with_budget(budget: u32) -> Self {
+        Self {
+            spin_wait: SpinWait::new(),
+            budget,
+        }
+    }
+
+    /// Spins an exponentially increasing number of times up to 2^`SPIN_LIMIT`.
+    #[inline]
+    pub fn spin(&mut self) {
+        self.spin_with::<Spin>();
+    }
+
+    /// Spins an exponentially increasing number of times up to 2^`SPIN_LIMIT`,
+    /// relaxing with `R`.
+    #[inline]
+    pub fn spin_with<R: Relax>(&mut self) {
+        if self.budget > 0 {
+            self.budget -= 1;
+            spin_for::<R>(1);
+        } else if !self.spin_wait.spin_with::<R>() {
+            spin_for::<R>(1 << SPIN_LIMIT);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    use super::{Backoff, SpinWait, SPIN_LIMIT};
+    use crate::relax::Relax;
+
+    // Number of relaxations of `Counting`, only used by `backoff_budget`.
+    static RELAXES: AtomicU32 = AtomicU32::new(0);
+
+    struct Counting;
+
+    impl Relax for Counting {
+        fn relax() {
+            RELAXES.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn relaxes_per_spin<const N: usize>(backoff: &mut Backoff) -> [u32; N] {
+        core::array::from_fn(|_| {
+            let before = RELAXES.load(Ordering::Relaxed);
+            backoff.spin_with::<Counting>();
+            RELAXES.load(Ordering::Relaxed) - before
+        })
+    }
+
+    #[test]
+    fn spin_wait_step_growth() {
+        let mut spin_wait = SpinWait::new();
+
+        for counter in 0..=SPIN_LIMIT {
+            assert_eq!(spin_wait.counter, counter);
+            assert!(spin_wait.spin());
+        }
+        assert_eq!(spin_wait.counter, SPIN_LIMIT + 1);
+    }
+
+    #[test]
+    fn spin_wait_exhausted() {
+        let mut spin_wait = SpinWait::new();
+
+        while spin_wait.spin() {}
+        assert!(!spin_wait.spin());
+
+        spin_wait.reset();
+        assert_eq!(spin_wait.counter, 0);
+        assert!(spin_wait.spin());
+    }
+
+    #[test]
+    fn backoff_budget() {
+        let mut backoff = Backoff::new();
+        assert_eq!(relaxes_per_spin(&mut backoff), [1, 2, 4, 8]);
+
+        let mut backoff = Backoff::with_budget(3);
+        assert_eq!(relaxes_per_spin(&mut backoff), [1, 1, 1, 1, 2, 4]);
+    }
+}
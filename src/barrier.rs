@@ -0,0 +1,144 @@
+use crate::backoff::Backoff;
+use crate::sync::{AtomicUsize, Ordering};
+
+// Number of low bits of the state counting the threads which reached the
+// barrier, the upper bits hold the generation.
+const COUNT_BITS: u32 = usize::BITS / 2;
+// Mask of the count of the state, it is also the maximum number of threads.
+const COUNT: usize = (1 << COUNT_BITS) - 1;
+// Increment of the state for a new generation.
+const GENERATION: usize = 1 << COUNT_BITS;
+
+/// A barrier enabling multiple threads to synchronize the beginning of some
+/// computation.
+///
+/// The waiting threads busy-wait until all the threads reached the barrier.
+/// The barrier can be reused once all the threads have been released.
+#[derive(Debug)]
+pub struct Barrier {
+    // The state of the barrier, from the upper to the lower bits:
+    // - the generation, incremented each time all the threads reached the
+    //   barrier, the waiting threads spin until it changes
+    // - the number of threads which reached the barrier in the current
+    //   generation, reset along with the increment of the generation
+    //
+    // Both are updated at once so that a thread is always counted in a
+    // single generation, even if more than `n` threads call `wait`.
+    state: AtomicUsize,
+    // Number of threads to wait for.
+    n: usize,
+}
+
+impl Barrier {
+    const_fn! {
+        /// Creates a new `Barrier` that blocks `n` threads until they all
+        /// called [`wait`](self::Barrier::wait).
+        ///
+        /// A barrier created with `n` equal to 0 behaves like one created
+        /// with `n` equal to 1, [`wait`](self::Barrier::wait) never blocks.
+        ///
+        /// # Panics
+        /// Panics if `n` is greater than `2^(usize::BITS / 2) - 1`.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::Barrier;
+        ///
+        /// let barrier = Barrier::new(4);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn new(n: usize) -> Self {
+            assert!(n <= COUNT, "too many threads for a barrier");
+            Self {
+                state: AtomicUsize::new(0),
+                n,
+            }
+        }
+    }
+
+    /// Blocks the current thread until all the threads reached the barrier.
+    ///
+    /// This function blocks the current thread by spinning until `n` threads
+    /// called it. A single thread of each generation is the leader, its
+    /// [`BarrierWaitResult`] returns `true` from
+    /// [`is_leader`](self::BarrierWaitResult::is_leader).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Barrier;
+    /// use std::sync::Arc;
+    /// use std::thread;
+    ///
+    /// let barrier = Arc::new(Barrier::new(4));
+    ///
+    /// let threads: Vec<_> = (0..4)
+    ///     .map(|_| {
+    ///         let barrier = Arc::clone(&barrier);
+    ///         thread::spawn(move || barrier.wait().is_leader())
+    ///     })
+    ///     .collect();
+    ///
+    /// let leaders = threads
+    ///     .into_iter()
+    ///     .map(|thread| thread.join().expect("thread::spawn failed"))
+    ///     .filter(|&is_leader| is_leader)
+    ///     .count();
+    /// assert_eq!(leaders, 1);
+    /// ```
+    #[must_use]
+    pub fn wait(&self) -> BarrierWaitResult {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            // The last thread of the generation resets the count and bumps
+            // the generation with the same compare and swap.
+            let leader = (state & COUNT) + 1 >= self.n;
+            let next = if leader {
+                (state & !COUNT).wrapping_add(GENERATION)
+            } else {
+                state + 1
+            };
+
+            match self
+                .state
+                .compare_exchange_weak(state, next, Ordering::AcqRel, Ordering::Relaxed)
+            {
+                Ok(_) if leader => return BarrierWaitResult(true),
+                Ok(_) => break,
+                Err(current) => state = current,
+            }
+        }
+
+        let generation = state & !COUNT;
+        let mut backoff = Backoff::new();
+        while self.state.load(Ordering::Acquire) & !COUNT == generation {
+            backoff.spin();
+        }
+
+        BarrierWaitResult(false)
+    }
+}
+
+/// This structure is returned by [`wait`](self::Barrier::wait) on
+/// [`Barrier`](self::Barrier).
+#[derive(Debug, Clone, Copy)]
+pub struct BarrierWaitResult(bool);
+
+impl BarrierWaitResult {
+    /// Returns whether this thread is the leader of its generation.
+    ///
+    /// Exactly one thread of each generation is the leader.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Barrier;
+    ///
+    /// let barrier = Barrier::new(1);
+    /// assert!(barrier.wait().is_leader());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn is_leader(&self) -> bool {
+        self.0
+    }
+}
@@ -0,0 +1,124 @@
+use crate::backoff::Backoff;
+use crate::relax::Relax;
+use crate::sync::{AtomicUsize, Ordering};
+use crate::MutexGuard;
+
+/// A condition variable to block a thread until notified, coordinating with a
+/// [`Mutex`](crate::Mutex).
+///
+/// Since this crate is `no_std` there is no way to park a thread, the waiting
+/// threads busy-wait until notified which burns CPU time. It is only suited for
+/// conditions that are expected to become true shortly.
+///
+/// As with any condition variable, a waiting thread may be woken up while the
+/// condition is not met so it should be checked in a loop.
+#[derive(Debug)]
+pub struct Condvar {
+    // Incremented on each notification, the waiting threads spin until it changes.
+    generation: AtomicUsize,
+}
+
+impl Condvar {
+    const_fn! {
+        /// Creates a new `Condvar`.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::Condvar;
+        ///
+        /// let condvar = Condvar::new();
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn new() -> Self {
+            Self {
+                generation: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    /// Releases the lock of the guard and blocks the current thread until
+    /// notified, then acquires the lock again.
+    ///
+    /// This function blocks the current thread by spinning until
+    /// [`notify_one`](self::Condvar::notify_one) or
+    /// [`notify_all`](self::Condvar::notify_all) is called.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{Condvar, Mutex};
+    /// use std::sync::Arc;
+    /// use std::thread;
+    ///
+    /// let pair = Arc::new((Mutex::new(false), Condvar::new()));
+    /// let p = Arc::clone(&pair);
+    ///
+    /// thread::spawn(move || {
+    ///     let (mutex, condvar) = &*p;
+    ///     mutex.with_lock(|ready| *ready = true);
+    ///     condvar.notify_one();
+    /// });
+    ///
+    /// let (mutex, condvar) = &*pair;
+    /// # #[cfg(feature = "poison")]
+    /// # let mut ready = mutex.lock().unwrap();
+    /// # #[cfg(not(feature = "poison"))]
+    /// let mut ready = mutex.lock();
+    /// while !*ready {
+    ///     ready = condvar.wait(ready);
+    /// }
+    /// ```
+    pub fn wait<'a, T, R: Relax>(&self, guard: MutexGuard<'a, T, R>) -> MutexGuard<'a, T, R> {
+        // The generation is read while the lock is still held so that a
+        // notification happening after the release is not missed.
+        let generation = self.generation.load(Ordering::Acquire);
+        let mutex = MutexGuard::mutex(&guard);
+        drop(guard);
+
+        let mut backoff = Backoff::new();
+        while self.generation.load(Ordering::Acquire) == generation {
+            backoff.spin_with::<R>();
+        }
+
+        mutex.acquire()
+    }
+
+    /// Wakes up a thread blocked on this condition variable.
+    ///
+    /// Since waiting threads are not queued, all of them may observe the
+    /// notification.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Condvar;
+    ///
+    /// let condvar = Condvar::new();
+    /// condvar.notify_one();
+    /// ```
+    #[inline]
+    pub fn notify_one(&self) {
+        self.generation.fetch_add(1, Ordering::Release);
+    }
+
+    /// Wakes up all threads blocked on this condition variable.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Condvar;
+    ///
+    /// let condvar = Condvar::new();
+    /// condvar.notify_all();
+    /// ```
+    #[inline]
+    pub fn notify_all(&self) {
+        self.generation.fetch_add(1, Ordering::Release);
+    }
+}
+
+impl Default for Condvar {
+    /// Creates a new `Condvar`.
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
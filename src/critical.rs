@@ -0,0 +1,42 @@
+/// A critical section entered while a [`Mutex`](crate::Mutex) is held.
+///
+/// With the `critical-section` feature it disables the interrupts, or whatever
+/// the [`critical_section`](https://docs.rs/critical-section) implementation
+/// does, so that an interrupt handler can't spin forever on a lock held by the
+/// code it interrupted. Otherwise it does nothing.
+#[derive(Debug, Clone, Copy)]
+pub struct CriticalSection {
+    #[cfg(feature = "critical-section")]
+    restore: ::critical_section::RestoreState,
+}
+
+// Without the `critical-section` feature the functions do nothing.
+#[cfg_attr(
+    not(feature = "critical-section"),
+    allow(clippy::missing_const_for_fn, clippy::unused_self)
+)]
+impl CriticalSection {
+    /// Enters a critical section.
+    ///
+    /// The critical section must be exited with [`exit`](self::CriticalSection::exit).
+    #[inline]
+    #[must_use]
+    pub fn enter() -> Self {
+        Self {
+            // SAFETY: The guards holding the critical section exit it when dropped.
+            #[cfg(feature = "critical-section")]
+            restore: unsafe { ::critical_section::acquire() },
+        }
+    }
+
+    /// Exits the critical section.
+    ///
+    /// # Safety
+    /// The critical section must be exited only once, and the nested critical
+    /// sections must be exited in the reverse order they were entered.
+    #[inline]
+    pub unsafe fn exit(self) {
+        #[cfg(feature = "critical-section")]
+        ::critical_section::release(self.restore);
+    }
+}
@@ -0,0 +1,367 @@
+use core::ops::{Deref, DerefMut};
+
+use crate::backoff::Backoff;
+use crate::sync::{AtomicUsize, Ordering, UnsafeCell};
+
+/// A fair reader-writer lock.
+///
+/// This primitive allows multiple readers or one unique writer, like
+/// [`RwLock`](crate::RwLock), but the readers and the writers are served in the
+/// order they asked for the lock so neither of them can starve. It is using a
+/// ticket lock as locking mechanism: every reader and writer takes a ticket
+/// from a single counter, then waits for its ticket to be served.
+///
+/// The state is encoded in three counters:
+/// - the next ticket, handed to each thread trying to acquire the lock
+/// - the read ticket being served, a reader holding it enters and immediately
+///   serves the next read ticket so that the consecutive readers in line
+///   hold the lock together
+/// - the write ticket being served, incremented by each reader and writer
+///   releasing the lock so that a writer enters once everyone ahead of it
+///   in line has left
+///
+/// A writer releasing the lock serves both the next read and write tickets.
+/// The readers queued behind a writer thus wait for it and then enter as a
+/// batch, while the writers enter one at a time in order.
+///
+/// Compared to [`RwLock`](crate::RwLock) the throughput is lower: each
+/// acquisition updates the shared ticket counter, a reader arriving after a
+/// waiting writer can't share the lock with the readers ahead of it, and the
+/// lock can only be handed to the next thread in line even if another one is
+/// ready to take it. Since a reader waits for the writers ahead of it, a
+/// thread already holding a shared read access deadlocks if it asks for
+/// another one while a writer is waiting.
+///
+/// # Examples
+/// ```
+/// use spinlock::FairRwLock;
+///
+/// let rwlock = FairRwLock::new(1);
+///
+/// let r1 = rwlock.read();
+/// let r2 = rwlock.read();
+/// assert_eq!(*r1 + *r2, 2);
+/// drop((r1, r2));
+///
+/// *rwlock.write() += 1;
+/// assert_eq!(*rwlock.read(), 2);
+/// ```
+#[derive(Debug)]
+pub struct FairRwLock<T> {
+    // Inner data contained in the rwlock.
+    data: UnsafeCell<T>,
+    // Ticket handed to the next thread trying to acquire the lock.
+    next_ticket: AtomicUsize,
+    // Ticket of the next reader allowed to hold the lock.
+    read_serving: AtomicUsize,
+    // Ticket of the next writer allowed to hold the lock.
+    write_serving: AtomicUsize,
+}
+
+impl<T> FairRwLock<T> {
+    const_fn! {
+        /// Creates a new `FairRwLock<T>` which is unlocked.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::FairRwLock;
+        ///
+        /// let rwlock = FairRwLock::new(1);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn new(data: T) -> Self {
+            Self {
+                data: UnsafeCell::new(data),
+                next_ticket: AtomicUsize::new(0),
+                read_serving: AtomicUsize::new(0),
+                write_serving: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    /// Acquires the rwlock with shared read access,
+    /// blocking the thread until it is available.
+    ///
+    /// This function blocks the current thread until all the writers that
+    /// asked for the lock before have released it.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::FairRwLock;
+    /// use std::thread;
+    /// use std::sync::Arc;
+    ///
+    /// let rwlock = Arc::new(FairRwLock::new(1));
+    /// let r = Arc::clone(&rwlock);
+    ///
+    /// thread::spawn(move || {
+    ///     assert_eq!(*r.read(), 1);
+    /// }).join().expect("thread::spawn failed");
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn read(&self) -> FairRwLockReadGuard<'_, T> {
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+
+        let mut backoff = Backoff::new();
+        // Memory order acquire is used to make sure no reordering happens after it.
+        while self.read_serving.load(Ordering::Acquire) != ticket {
+            backoff.spin();
+        }
+
+        // Lets the next reader in line share the lock.
+        self.read_serving.fetch_add(1, Ordering::Release);
+
+        FairRwLockReadGuard { rwlock: self }
+    }
+
+    /// Tries to acquire the rwlock with shared read access. If the lock is not available returns `None`.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::FairRwLock;
+    ///
+    /// let rwlock = FairRwLock::new(1);
+    ///
+    /// assert_eq!(*rwlock.try_read().unwrap(), 1);
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_read(&self) -> Option<FairRwLockReadGuard<'_, T>> {
+        // The read access is available only if no writer is in line
+        // ie the next ticket is the read ticket being served.
+        let ticket = self.read_serving.load(Ordering::Acquire);
+        self.next_ticket
+            .compare_exchange(
+                ticket,
+                ticket.wrapping_add(1),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .ok()?;
+
+        self.read_serving.fetch_add(1, Ordering::Release);
+
+        Some(FairRwLockReadGuard { rwlock: self })
+    }
+
+    /// Acquires the rwlock with exclusive write access,
+    /// blocking the thread until it is available.
+    ///
+    /// This function blocks the current thread until all the readers and
+    /// writers that asked for the lock before have released it.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::FairRwLock;
+    /// use std::thread;
+    /// use std::sync::Arc;
+    ///
+    /// let rwlock = Arc::new(FairRwLock::new(1));
+    /// let r = Arc::clone(&rwlock);
+    ///
+    /// thread::spawn(move || {
+    ///     *r.write() = 42;
+    /// }).join().expect("thread::spawn failed");
+    /// assert_eq!(*rwlock.read(), 42);
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn write(&self) -> FairRwLockWriteGuard<'_, T> {
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+
+        let mut backoff = Backoff::new();
+        // Memory order acquire is used to make sure no reordering happens after it.
+        while self.write_serving.load(Ordering::Acquire) != ticket {
+            backoff.spin();
+        }
+
+        FairRwLockWriteGuard { rwlock: self }
+    }
+
+    /// Tries to acquire the rwlock with exclusive write access. If the lock is not available returns `None`.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::FairRwLock;
+    ///
+    /// let rwlock = FairRwLock::new(1);
+    ///
+    /// *rwlock.try_write().unwrap() = 2;
+    /// assert_eq!(*rwlock.read(), 2);
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_write(&self) -> Option<FairRwLockWriteGuard<'_, T>> {
+        // The write access is available only if no one holds the lock nor is
+        // in line ie the next ticket is the write ticket being served.
+        let ticket = self.write_serving.load(Ordering::Acquire);
+        self.next_ticket
+            .compare_exchange(
+                ticket,
+                ticket.wrapping_add(1),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .map_or(None, |_| Some(FairRwLockWriteGuard { rwlock: self }))
+    }
+}
+
+impl<T: Default> Default for FairRwLock<T> {
+    /// Creates a `FairRwLock<T>` which is unlocked containing the default of `T`.
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+// SAFETY: The locking mechanism ensures that only one write access or multiple
+// read accesses are possible, so the data is shared between the readers and
+// may be modified by a writer of another thread.
+unsafe impl<T: Send + Sync> Sync for FairRwLock<T> {}
+
+/// Guard structure used to release the shared read access when dropped.
+///
+/// This structure is created by [`read`](self::FairRwLock::read) and
+/// [`try_read`](self::FairRwLock::try_read) on [`FairRwLock`](self::FairRwLock).
+#[derive(Debug)]
+#[must_use = "if unused the lock is immediately released"]
+pub struct FairRwLockReadGuard<'rwlock, T> {
+    rwlock: &'rwlock FairRwLock<T>,
+}
+
+impl<T> Deref for FairRwLockReadGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: A read guard is only created once the writers ahead in line
+        // released the lock and the writers behind wait for it to be dropped,
+        // so the data can't be modified while it is held.
+        self.rwlock.data.with(|data| unsafe { &*data })
+    }
+}
+
+impl<T> Drop for FairRwLockReadGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // Lets the next writer in line enter once every reader ahead of it left.
+        self.rwlock.write_serving.fetch_add(1, Ordering::Release);
+    }
+}
+
+// Prevents the read guard from being moved to an other thread.
+impl<T> !Send for FairRwLockReadGuard<'_, T> {}
+
+// SAFETY: The guard only gives shared access to the data.
+unsafe impl<T: Sync> Sync for FairRwLockReadGuard<'_, T> {}
+
+/// Guard structure used to release the exclusive write access when dropped.
+///
+/// This structure is created by [`write`](self::FairRwLock::write) and
+/// [`try_write`](self::FairRwLock::try_write) on [`FairRwLock`](self::FairRwLock).
+#[derive(Debug)]
+#[must_use = "if unused the lock is immediately released"]
+pub struct FairRwLockWriteGuard<'rwlock, T> {
+    rwlock: &'rwlock FairRwLock<T>,
+}
+
+impl<T> Deref for FairRwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: A write guard is only created once everyone ahead in line
+        // released the lock and everyone behind waits for it to be dropped.
+        self.rwlock.data.with(|data| unsafe { &*data })
+    }
+}
+
+impl<T> DerefMut for FairRwLockWriteGuard<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: A write guard is only created once everyone ahead in line
+        // released the lock and everyone behind waits for it to be dropped.
+        self.rwlock.data.with_mut(|data| unsafe { &mut *data })
+    }
+}
+
+impl<T> Drop for FairRwLockWriteGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // Serves both the next writer and the next reader in line, only one of
+        // them holds the next ticket. Memory order release is used to make sure
+        // no reordering happens before it.
+        self.rwlock.write_serving.fetch_add(1, Ordering::Release);
+        self.rwlock.read_serving.fetch_add(1, Ordering::Release);
+    }
+}
+
+// Prevents the write guard from being moved to an other thread.
+impl<T> !Send for FairRwLockWriteGuard<'_, T> {}
+
+// SAFETY: A shared reference to the guard only gives shared access to the data.
+unsafe impl<T: Sync> Sync for FairRwLockWriteGuard<'_, T> {}
+
+#[cfg(all(test, not(feature = "loom")))]
+mod tests {
+    extern crate std;
+
+    use core::sync::atomic::Ordering;
+    use std::thread;
+    use std::vec::Vec;
+
+    use super::FairRwLock;
+
+    fn at_last_ticket<T>(data: T) -> FairRwLock<T> {
+        let rwlock = FairRwLock::new(data);
+        rwlock.next_ticket.store(usize::MAX, Ordering::Relaxed);
+        rwlock.read_serving.store(usize::MAX, Ordering::Relaxed);
+        rwlock.write_serving.store(usize::MAX, Ordering::Relaxed);
+        rwlock
+    }
+
+    #[test]
+    fn try_read_wraps_tickets() {
+        let rwlock = at_last_ticket(1);
+
+        let r = rwlock.try_read().unwrap();
+        assert_eq!(rwlock.next_ticket.load(Ordering::Relaxed), 0);
+        assert_eq!(*r, 1);
+        assert!(rwlock.try_write().is_none());
+        drop(r);
+        assert!(rwlock.try_write().is_some());
+    }
+
+    #[test]
+    fn try_write_wraps_tickets() {
+        let rwlock = at_last_ticket(1);
+
+        *rwlock.try_write().unwrap() = 2;
+        assert_eq!(rwlock.next_ticket.load(Ordering::Relaxed), 0);
+        assert_eq!(*rwlock.try_read().unwrap(), 2);
+    }
+
+    #[test]
+    fn fifo_order() {
+        let order = FairRwLock::new(Vec::new());
+
+        let guard = order.write();
+        thread::scope(|s| {
+            for i in 0..4 {
+                let order = &order;
+                s.spawn(move || order.write().push(i));
+
+                // Waits for the thread to take its ticket before spawning the
+                // next one, the guard holds the first ticket.
+                while order.next_ticket.load(Ordering::Relaxed) != i + 2 {
+                    thread::yield_now();
+                }
+            }
+            drop(guard);
+        });
+
+        assert_eq!(*order.read(), [0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,91 @@
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+
+use crate::sync::UnsafeCell;
+use crate::Once;
+
+/// A value initialized on its first access.
+///
+/// The initialization is driven by a [`Once`](crate::Once), threads
+/// accessing the value while it is being initialized spin until it completes.
+///
+/// # Panics
+/// If the initialization function panics, the panic is propagated and any
+/// later access panics too.
+///
+/// # Examples
+/// ```
+/// use spinlock::Lazy;
+///
+/// static VALUES: Lazy<Vec<u32>> = Lazy::new(|| (0..4).collect());
+///
+/// assert_eq!(VALUES.len(), 4);
+/// ```
+pub struct Lazy<T, F = fn() -> T> {
+    once: Once,
+    // Initialized once `once` is completed.
+    data: UnsafeCell<MaybeUninit<T>>,
+    // Taken by the thread running the initialization.
+    init: UnsafeCell<Option<F>>,
+}
+
+impl<T, F> Lazy<T, F> {
+    const_fn! {
+        /// Creates a new `Lazy<T, F>` which is initialized by the given function
+        /// on its first access.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::Lazy;
+        ///
+        /// let lazy = Lazy::new(|| 42);
+        /// assert_eq!(*lazy, 42);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn new(init: F) -> Self {
+            Self {
+                once: Once::new(),
+                data: UnsafeCell::new(MaybeUninit::uninit()),
+                init: UnsafeCell::new(Some(init)),
+            }
+        }
+    }
+}
+
+impl<T, F: FnOnce() -> T> Deref for Lazy<T, F> {
+    type Target = T;
+
+    /// Returns the value, initializing it if this is the first access.
+    #[inline]
+    fn deref(&self) -> &T {
+        self.once.call_once(|| {
+            // SAFETY: Only the thread running the initialization accesses
+            // the function and the data.
+            let init = self.init.with_mut(|init| unsafe { &mut *init }).take();
+            let init = init.expect("Lazy instance has previously been poisoned");
+            let data = init();
+            self.data.with_mut(|cell| unsafe { &mut *cell }).write(data);
+        });
+
+        // SAFETY: The initialization is completed so the data is initialized
+        // and no longer mutated.
+        self.data.with(|data| unsafe { (*data).assume_init_ref() })
+    }
+}
+
+impl<T, F> Drop for Lazy<T, F> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.once.is_completed() {
+            // SAFETY: The initialization is completed so the data is initialized,
+            // and the lazy is borrowed mutably so it can no longer be accessed.
+            self.data
+                .with_mut(|data| unsafe { (*data).assume_init_drop() });
+        }
+    }
+}
+
+// SAFETY: It is safe to impl Sync since the `Once` ensures that a single thread
+// runs the initialization, the value is then only accessed immutably.
+unsafe impl<T: Sync + Send, F: Send> Sync for Lazy<T, F> {}
@@ -11,8 +11,79 @@
 #![feature(negative_impls)]
 #![no_std]
 
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(all(feature = "lock_api", feature = "loom"))]
+compile_error!("the `lock_api` feature requires const constructors which `loom` does not provide");
+#[cfg(all(feature = "send_guard", feature = "critical-section"))]
+compile_error!("the `send_guard` feature can't send the critical section held by a `MutexGuard`");
+
+#[macro_use]
+mod sync;
+
+// `Arc` is only provided on the targets with atomic compare and swap.
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+mod arc;
+mod backoff;
+mod barrier;
+mod condvar;
+mod critical;
+mod fair_rwlock;
+mod lazy;
+#[cfg(feature = "lock_api")]
+pub mod lock_api;
 mod mutex;
+mod non_blocking;
+mod once;
+#[cfg(feature = "poison")]
+mod poison;
+mod raw_mutex;
+mod reentrant;
+mod relax;
 mod rwlock;
+mod semaphore;
+mod seqlock;
+// The state of the lock needs a 64-bit atomic.
+#[cfg(any(target_has_atomic = "64", feature = "portable-atomic"))]
+mod stamped_lock;
+#[cfg(feature = "stats")]
+mod stats;
+mod ticket;
+mod would_block;
 
-pub use mutex::Mutex;
-pub use rwlock::RwLock;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub use arc::{
+    ArcMutexGuard, ArcRwLockReadGuard, ArcRwLockUpgradableReadGuard, ArcRwLockWriteGuard,
+};
+pub use backoff::SpinWait;
+pub use barrier::{Barrier, BarrierWaitResult};
+pub use condvar::Condvar;
+pub use fair_rwlock::{FairRwLock, FairRwLockReadGuard, FairRwLockWriteGuard};
+pub use lazy::Lazy;
+pub use mutex::{MappedMutexGuard, Mutex, MutexGuard};
+pub use non_blocking::NonBlockingMutex;
+pub use once::Once;
+#[cfg(feature = "poison")]
+pub use poison::{LockResult, PoisonError};
+pub use raw_mutex::RawMutex;
+#[cfg(feature = "std")]
+pub use reentrant::StdThreadId;
+pub use reentrant::{GetThreadId, ReentrantMutex, ReentrantMutexGuard};
+#[cfg(feature = "std")]
+pub use relax::Yield;
+pub use relax::{Contention, PoliteSpin, Relax, Spin, WaitForEvent};
+pub use rwlock::{
+    MappedRwLockReadGuard, MappedRwLockWriteGuard, OptimisticReadGuard, Policy, RwLock,
+    RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard,
+};
+pub use semaphore::{Semaphore, SemaphorePermit};
+pub use seqlock::SeqLock;
+#[cfg(any(target_has_atomic = "64", feature = "portable-atomic"))]
+pub use stamped_lock::{ReadStamp, Stamp, StampedLock, WriteStamp};
+#[cfg(feature = "stats")]
+pub use stats::LockStats;
+pub use ticket::{TicketMutex, TicketMutexGuard};
+pub use would_block::{Timeout, WouldBlock};
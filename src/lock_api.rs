@@ -0,0 +1,119 @@
+//! Integration with the [`lock_api`](https://docs.rs/lock_api) crate.
+//!
+//! This module provides raw locks implementing the `lock_api` traits so that
+//! the spinlocks of this crate can be used by APIs generic over `lock_api`,
+//! along with type aliases of the `lock_api` types using them.
+
+use core::mem;
+
+use crate::{RawMutex as SpinRawMutex, RwLock as SpinRwLock};
+
+/// A raw mutex implementing [`lock_api::RawMutex`] using a spinlock.
+#[derive(Debug)]
+pub struct RawSpinMutex {
+    raw: SpinRawMutex,
+}
+
+// SAFETY: The lock is held until `unlock` is called.
+unsafe impl ::lock_api::RawMutex for RawSpinMutex {
+    const INIT: Self = Self {
+        raw: SpinRawMutex::new(),
+    };
+
+    type GuardMarker = ::lock_api::GuardNoSend;
+
+    #[inline]
+    fn lock(&self) {
+        self.raw.lock();
+    }
+
+    #[inline]
+    fn try_lock(&self) -> bool {
+        self.raw.try_lock()
+    }
+
+    #[inline]
+    unsafe fn unlock(&self) {
+        self.raw.unlock();
+    }
+
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.raw.is_locked()
+    }
+}
+
+/// A raw reader-writer lock implementing [`lock_api::RawRwLock`] using a spinlock.
+#[derive(Debug)]
+pub struct RawSpinRwLock {
+    rwlock: SpinRwLock<()>,
+}
+
+// SAFETY: The accesses are held until they are unlocked since the guards
+// of the inner rwlock are forgotten.
+unsafe impl ::lock_api::RawRwLock for RawSpinRwLock {
+    const INIT: Self = Self {
+        rwlock: SpinRwLock::new(()),
+    };
+
+    type GuardMarker = ::lock_api::GuardNoSend;
+
+    #[inline]
+    fn lock_shared(&self) {
+        // The shared read access is released by `unlock_shared`.
+        mem::forget(self.rwlock.read());
+    }
+
+    #[inline]
+    fn try_lock_shared(&self) -> bool {
+        // The shared read access is released by `unlock_shared`.
+        self.rwlock.try_read().map(mem::forget).is_some()
+    }
+
+    #[inline]
+    unsafe fn unlock_shared(&self) {
+        self.rwlock.force_read_unlock();
+    }
+
+    #[inline]
+    fn lock_exclusive(&self) {
+        // The exclusive write access is released by `unlock_exclusive`.
+        mem::forget(self.rwlock.write());
+    }
+
+    #[inline]
+    fn try_lock_exclusive(&self) -> bool {
+        // The exclusive write access is released by `unlock_exclusive`.
+        self.rwlock.try_write().map(mem::forget).is_some()
+    }
+
+    #[inline]
+    unsafe fn unlock_exclusive(&self) {
+        self.rwlock.force_write_unlock();
+    }
+
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.rwlock.is_write_locked() || self.rwlock.reader_count() > 0
+    }
+
+    #[inline]
+    fn is_locked_exclusive(&self) -> bool {
+        self.rwlock.is_write_locked()
+    }
+}
+
+/// A `lock_api` mutex using [`RawSpinMutex`](self::RawSpinMutex).
+pub type Mutex<T> = ::lock_api::Mutex<RawSpinMutex, T>;
+
+/// A `lock_api` mutex guard using [`RawSpinMutex`](self::RawSpinMutex).
+pub type MutexGuard<'a, T> = ::lock_api::MutexGuard<'a, RawSpinMutex, T>;
+
+/// A `lock_api` reader-writer lock using [`RawSpinRwLock`](self::RawSpinRwLock).
+pub type RwLock<T> = ::lock_api::RwLock<RawSpinRwLock, T>;
+
+/// A `lock_api` read guard using [`RawSpinRwLock`](self::RawSpinRwLock).
+pub type RwLockReadGuard<'a, T> = ::lock_api::RwLockReadGuard<'a, RawSpinRwLock, T>;
+
+/// A `lock_api` write guard using [`RawSpinRwLock`](self::RawSpinRwLock).
+pub type RwLockWriteGuard<'a, T> = ::lock_api::RwLockWriteGuard<'a, RawSpinRwLock, T>;
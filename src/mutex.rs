@@ -1,7 +1,21 @@
-use core::cell::UnsafeCell;
-use core::hint;
+use core::borrow::{Borrow, BorrowMut};
+use core::cmp;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
 use core::ops::{Deref, DerefMut};
-use core::sync::atomic::{AtomicBool, Ordering};
+use core::{mem, ptr};
+
+use crate::critical::CriticalSection;
+#[cfg(feature = "poison")]
+use crate::poison::{LockResult, PoisonError, PoisonOnPanic};
+use crate::raw_mutex::RawMutex;
+use crate::relax::{Relax, Spin};
+#[cfg(any(feature = "poison", feature = "stats"))]
+use crate::sync::Ordering;
+use crate::sync::{hint, AtomicBool, UnsafeCell};
+use crate::would_block::{Timeout, WouldBlock};
 
 /// A mutual exclusion synchronization primitive.
 ///
@@ -11,30 +25,299 @@ use core::sync::atomic::{AtomicBool, Ordering};
 ///
 /// This structure provides interior mutability and prevents multiple
 /// threads to access the data at the same time.
-#[derive(Debug)]
-pub struct Mutex<T> {
-    // Inner data contained in the mutex.
+///
+/// The way the CPU is relaxed while spinning is given by the [`Relax`]
+/// strategy `R`, which defaults to [`Spin`].
+///
+/// With the `critical-section` feature, acquiring the lock also enters a
+/// critical section which is exited when the guard is dropped, so the lock
+/// can be shared with interrupt handlers on a single core. This makes locking
+/// more expensive, and the guards of nested locks must be dropped in the
+/// reverse order they were acquired.
+///
+/// The data may be unsized, for instance a `&Mutex<[T; N]>` coerces to a
+/// `&Mutex<[T]>`.
+///
+/// A `Mutex<T>` is `Send` and `Sync` when `T` is `Send`: only one thread
+/// accesses the data at a time, so it can be shared between threads even if
+/// `T` is not `Sync`, such as a `Cell`. A mutex of a value which can't be sent
+/// to another thread can't be either:
+/// ```compile_fail
+/// use spinlock::Mutex;
+/// use std::rc::Rc;
+///
+/// fn assert_send<T: Send>() {}
+///
+/// assert_send::<Mutex<Rc<i32>>>();
+/// ```
+pub struct Mutex<T: ?Sized, R: Relax = Spin> {
+    // The lock protecting the data.
+    raw: RawMutex<R>,
+    // Did a thread panic while holding the lock.
+    #[cfg(feature = "poison")]
+    poisoned: AtomicBool,
+    // Inner data contained in the mutex, last so that it can be unsized.
     data: UnsafeCell<T>,
-    // Is the lock held by a thread.
-    lock: AtomicBool,
 }
 
+// Number of reloads of the lock by `try_lock_hot` before giving up.
+const HOT_SPINS: usize = 4;
+
 impl<T> Mutex<T> {
-    /// Creates a new `Mutex<T>` which is unlocked.
+    const_fn! {
+        /// Creates a new `Mutex<T>` which is unlocked.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::Mutex;
+        ///
+        /// let mutex = Mutex::new(1);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn new(data: T) -> Self {
+            Self::with_relax(data)
+        }
+    }
+
+    const_fn! {
+        /// Creates an array of `N` unlocked mutexes each containing a copy of
+        /// the given value.
+        ///
+        /// Since mutexes are not `Copy` they can't be used in an array repeat
+        /// expression, this function allows to initialize a table of locks in
+        /// a `static`.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::Mutex;
+        ///
+        /// static TABLE: [Mutex<u8>; 256] = Mutex::new_array(0);
+        ///
+        /// TABLE[42].with_lock(|entry| *entry += 1);
+        /// assert_eq!(TABLE[42].with_lock(|entry| *entry), 1);
+        /// ```
+        #[must_use]
+        pub const fn new_array<const N: usize>(value: T) -> [Self; N]
+        where
+            T: Copy,
+        {
+            let mut array = [const { MaybeUninit::<Self>::uninit() }; N];
+            let mut i = 0;
+            while i < N {
+                array[i] = MaybeUninit::new(Self::new(value));
+                i += 1;
+            }
+
+            // SAFETY: All the elements have been initialized and
+            // `MaybeUninit<Self>` has the same layout as `Self`.
+            unsafe { ptr::read(ptr::from_ref(&array).cast::<[Self; N]>()) }
+        }
+    }
+}
+
+impl<T, R: Relax> Mutex<T, R> {
+    const_fn! {
+        /// Creates a new `Mutex<T, R>` which is unlocked, relaxing the CPU with `R`
+        /// while spinning.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::{Mutex, Spin};
+        ///
+        /// let mutex = Mutex::<_, Spin>::with_relax(1);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn with_relax(data: T) -> Self {
+            Self::with_spin_budget(data, 0)
+        }
+    }
+
+    const_fn! {
+        /// Creates a new `Mutex<T, R>` which is unlocked, checking the lock after
+        /// each single spin the first `budget` times it waits for the lock before
+        /// backing off exponentially.
+        ///
+        /// A large budget lowers the latency of rarely contended locks, while
+        /// a budget of 0, the default of [`new`](self::Mutex::new), makes
+        /// heavily contended locks back off immediately.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::Mutex;
+        ///
+        /// static CONFIG: Mutex<u32> = Mutex::with_spin_budget(0, 64);
+        ///
+        /// CONFIG.with_lock(|config| *config = 42);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn with_spin_budget(data: T, budget: u32) -> Self {
+            Self {
+                data: UnsafeCell::new(data),
+                raw: RawMutex::with_spin_budget(budget),
+                #[cfg(feature = "poison")]
+                poisoned: AtomicBool::new(false),
+            }
+        }
+    }
+
+    /// Consumes this mutex, returning the underlying data.
+    ///
+    /// Since this call borrows the mutex by value, no other reference to it
+    /// can exist so no locking is needed.
     ///
     /// # Examples
     /// ```
     /// use spinlock::Mutex;
     ///
     /// let mutex = Mutex::new(1);
+    /// assert_eq!(mutex.into_inner(), 1);
     /// ```
     #[inline]
-    #[must_use]
-    pub const fn new(data: T) -> Self {
-        Self {
-            data: UnsafeCell::new(data),
-            lock: AtomicBool::new(false),
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+
+    /// Replaces the data of the mutex with the given value, returning the old value.
+    ///
+    /// The lock is acquired only once for the whole operation.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let mutex = Mutex::new(1);
+    /// assert_eq!(mutex.replace(42), 1);
+    /// assert_eq!(mutex.into_inner(), 42);
+    /// ```
+    #[inline]
+    pub fn replace(&self, value: T) -> T {
+        mem::replace(&mut *self.acquire(), value)
+    }
+
+    /// Swaps the data of two mutexes.
+    ///
+    /// To prevent deadlocks when two threads swap the same mutexes in opposite
+    /// order, the locks are always acquired in the order of the mutexes addresses.
+    /// Swapping a mutex with itself does nothing.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let a = Mutex::new(1);
+    /// let b = Mutex::new(2);
+    ///
+    /// a.swap(&b);
+    /// assert_eq!(a.into_inner(), 2);
+    /// assert_eq!(b.into_inner(), 1);
+    /// ```
+    #[inline]
+    pub fn swap(&self, other: &Self) {
+        if ptr::eq(self, other) {
+            return;
         }
+
+        self.with_pair(other, |ours, theirs| mem::swap(ours, theirs));
+    }
+
+    /// Takes the data of the mutex, leaving `T::default()` in its place.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let mutex = Mutex::new(vec![1, 2, 3]);
+    /// assert_eq!(mutex.take(), [1, 2, 3]);
+    /// assert!(mutex.into_inner().is_empty());
+    /// ```
+    #[inline]
+    pub fn take(&self) -> T
+    where
+        T: Default,
+    {
+        mem::take(&mut *self.acquire())
+    }
+}
+
+impl<T: ?Sized, R: Relax> Mutex<T, R> {
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the mutex mutably, no other reference to it
+    /// can exist so no locking is needed.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let mut mutex = Mutex::new(1);
+    /// *mutex.get_mut() = 42;
+    /// assert_eq!(mutex.into_inner(), 42);
+    /// ```
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        // SAFETY: The mutex is borrowed mutably so no guard can be held
+        // and it is safe to get a mutable reference to the data.
+        self.data.with_mut(|data| unsafe { &mut *data })
+    }
+
+    /// Calls the closure with a mutable reference to the underlying data,
+    /// returning the value it computed.
+    ///
+    /// This is the exclusive-reference counterpart of
+    /// [`with_lock`](self::Mutex::with_lock): it goes through
+    /// [`get_mut`](self::Mutex::get_mut) so no atomic operation is performed,
+    /// which makes it the zero-cost path for generic code holding a
+    /// `&mut Mutex<T>` rather than a `&Mutex<T>`.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let mut mutex = Mutex::new(vec![1, 2]);
+    ///
+    /// let len = mutex.with_mut(|data| {
+    ///     data.push(3);
+    ///     data.len()
+    /// });
+    /// assert_eq!(len, 3);
+    /// ```
+    #[inline]
+    pub fn with_mut<U, F>(&mut self, f: F) -> U
+    where
+        F: FnOnce(&mut T) -> U,
+    {
+        f(self.get_mut())
+    }
+
+    /// Returns a raw pointer to the underlying data.
+    ///
+    /// This is an escape hatch for protocols which guarantee the exclusive
+    /// access to the data by other means than the lock. The lock is not
+    /// acquired, the pointer is valid as long as the mutex is alive.
+    ///
+    /// Dereferencing the pointer is unsafe: no reference created from it may
+    /// alias a reference obtained through a guard, nor be used while another
+    /// thread accesses the data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let mutex = Mutex::new(1);
+    ///
+    /// let data = mutex.data_ptr();
+    /// // SAFETY: No guard is held and the mutex is not shared with another
+    /// // thread so this is the only access to the data.
+    /// unsafe { *data = 42 };
+    /// assert_eq!(mutex.into_inner(), 42);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn data_ptr(&self) -> *mut T {
+        self.data.with_mut(|data| data)
     }
 
     /// Acquires the lock, blocking the current thread until the lock is available.
@@ -55,21 +338,40 @@ impl<T> Mutex<T> {
     /// }).join().expect("thread::spawn failed");
     /// assert_eq!(*mutex.lock(), 42);
     /// ```
-    pub fn lock(&self) -> MutexGuard<'_, T> {
-        // To reduce the cache coherency traffic we spin on an atomic load which does
-        // not requires write access to the cache line (as opposed to compare_and_swap).
-        loop {
-            // Memory order acquire is used to make sure no reordering happens after it.
-            if !self.lock.swap(true, Ordering::Acquire) {
-                return MutexGuard::new(self);
-            }
+    #[cfg(not(feature = "poison"))]
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn lock(&self) -> MutexGuard<'_, T, R> {
+        self.acquire()
+    }
 
-            while self.lock.load(Ordering::Relaxed) {
-                // Hints the CPU that we are in a busy-wait spin loop, so the CPU can
-                // optimized its behavior.
-                hint::spin_loop();
-            }
-        }
+    /// Acquires the lock, blocking the current thread until the lock is available.
+    ///
+    /// This functions block the current thread until the lock is available.
+    ///
+    /// # Errors
+    /// If another thread panicked while holding the lock, the mutex is poisoned
+    /// and the guard is returned within a [`PoisonError`](crate::PoisonError).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    /// use std::thread;
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(Mutex::new(1));
+    /// let m = Arc::clone(&mutex);
+    ///
+    /// thread::spawn(move || {
+    ///     *m.lock().unwrap() = 42;
+    /// }).join().expect("thread::spawn failed");
+    /// assert_eq!(*mutex.lock().unwrap(), 42);
+    /// ```
+    #[cfg(feature = "poison")]
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, T, R>> {
+        self.check_poison(self.acquire())
     }
 
     /// Tries to acquire the lock. If the lock is not available returns `None`.
@@ -83,83 +385,1261 @@ impl<T> Mutex<T> {
     /// let mutex = Mutex::new(1);
     /// assert_eq!(*mutex.try_lock().unwrap(), 1);
     /// ```
-    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
-        if !self.lock.load(Ordering::Relaxed) && !self.lock.swap(true, Ordering::Acquire) {
-            Some(MutexGuard::new(self))
-        } else {
-            None
-        }
+    #[cfg(not(feature = "poison"))]
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T, R>> {
+        self.try_acquire()
     }
 
-    /// UNSAFE: forcing to unlock while a guard is still held may allow to have mutliple guards.
-    #[allow(clippy::inline_always)]
-    #[inline(always)]
-    unsafe fn unlock(&self) {
-        // Memory order acquire is used to make sure no reordering happens before it.
-        self.lock.store(false, Ordering::Release);
+    /// Tries to acquire the lock. If the lock is not available returns `None`.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// If another thread panicked while holding the lock, the mutex is poisoned
+    /// and the guard is returned within a [`PoisonError`](crate::PoisonError).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let mutex = Mutex::new(1);
+    /// assert_eq!(*mutex.try_lock().unwrap().unwrap(), 1);
+    /// ```
+    #[cfg(feature = "poison")]
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_lock(&self) -> Option<LockResult<MutexGuard<'_, T, R>>> {
+        self.try_acquire().map(|guard| self.check_poison(guard))
     }
-}
 
-impl<T: Default> Default for Mutex<T> {
-    /// Creates a `Mutex<T>` which is unlocked containing the default of `T`.
+    /// Tries to acquire the lock, like [`try_lock`](self::Mutex::try_lock) but
+    /// returning a `Result`.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// # Errors
+    /// If the lock is not available returns [`WouldBlock`](crate::WouldBlock).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{Mutex, WouldBlock};
+    ///
+    /// let mutex = Mutex::new(1);
+    ///
+    /// let guard = mutex.try_lock_result().unwrap();
+    /// assert_eq!(*guard, 1);
+    /// assert_eq!(mutex.try_lock_result().unwrap_err(), WouldBlock);
+    /// ```
+    #[cfg(not(feature = "poison"))]
     #[inline]
-    fn default() -> Self {
-        Self::new(T::default())
+    pub fn try_lock_result(&self) -> Result<MutexGuard<'_, T, R>, WouldBlock> {
+        self.try_lock().ok_or(WouldBlock)
     }
-}
 
-// SAFETY: It is safe to impl Sync since the locking mechanism ensures the synchronization.
-unsafe impl<T: Sync> Sync for Mutex<T> {}
+    /// Tries to acquire the lock, like [`try_lock`](self::Mutex::try_lock) but
+    /// returning a `Result`.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// If another thread panicked while holding the lock, the mutex is poisoned
+    /// and the guard is returned within a [`PoisonError`](crate::PoisonError).
+    ///
+    /// # Errors
+    /// If the lock is not available returns [`WouldBlock`](crate::WouldBlock).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{Mutex, WouldBlock};
+    ///
+    /// let mutex = Mutex::new(1);
+    ///
+    /// let guard = mutex.try_lock_result().unwrap().unwrap();
+    /// assert_eq!(*guard, 1);
+    /// assert_eq!(mutex.try_lock_result().unwrap_err(), WouldBlock);
+    /// ```
+    #[cfg(feature = "poison")]
+    #[inline]
+    pub fn try_lock_result(&self) -> Result<LockResult<MutexGuard<'_, T, R>>, WouldBlock> {
+        self.try_lock().ok_or(WouldBlock)
+    }
 
-/// This structure is created by calling [`lock`](self::Mutex::lock)
-/// or [`try_lock`](self::Mutex::try_lock) on [`Mutex`](self::Mutex).
-#[derive(Debug)]
-pub struct MutexGuard<'mutex, T> {
-    mutex: &'mutex Mutex<T>,
-}
+    /// Tries to acquire the lock up to `max_spins` times, returns `None` if the lock
+    /// never became available.
+    ///
+    /// This function spins for a bounded amount of time, in between an unbounded
+    /// [`lock`](self::Mutex::lock) and a single attempt [`try_lock`](self::Mutex::try_lock).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let mutex = Mutex::new(1);
+    /// let guard = mutex.try_lock_spin(100).unwrap();
+    /// assert!(mutex.try_lock_spin(100).is_none());
+    /// ```
+    #[cfg(not(feature = "poison"))]
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_lock_spin(&self, max_spins: usize) -> Option<MutexGuard<'_, T, R>> {
+        self.try_acquire_spin(max_spins)
+    }
 
-impl<'mutex, T> MutexGuard<'mutex, T> {
-    /// Creates a `MutexGuard<'mutex, T>` of a given Mutex.
+    /// Tries to acquire the lock up to `max_spins` times, returns `None` if the lock
+    /// never became available.
+    ///
+    /// This function spins for a bounded amount of time, in between an unbounded
+    /// [`lock`](self::Mutex::lock) and a single attempt [`try_lock`](self::Mutex::try_lock).
+    ///
+    /// If another thread panicked while holding the lock, the mutex is poisoned
+    /// and the guard is returned within a [`PoisonError`](crate::PoisonError).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let mutex = Mutex::new(1);
+    /// let guard = mutex.try_lock_spin(100).unwrap().unwrap();
+    /// assert!(mutex.try_lock_spin(100).is_none());
+    /// ```
+    #[cfg(feature = "poison")]
     #[inline]
-    #[must_use]
-    const fn new(mutex: &'mutex Mutex<T>) -> Self {
-        Self { mutex }
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_lock_spin(&self, max_spins: usize) -> Option<LockResult<MutexGuard<'_, T, R>>> {
+        self.try_acquire_spin(max_spins)
+            .map(|guard| self.check_poison(guard))
     }
-}
 
-impl<T> Deref for MutexGuard<'_, T> {
-    type Target = T;
+    /// Acquires the lock, spinning at most `max_spins` iterations in total,
+    /// returns [`Timeout`](crate::Timeout) if the lock never became available.
+    ///
+    /// The deadline is counted in spin iterations rather than in time so that
+    /// the acquisition is bounded without a clock, for instance in the loop of
+    /// a hard real-time `no_std` application. Each iteration relaxes the CPU
+    /// once with the relax strategy of the mutex.
+    ///
+    /// # Errors
+    /// If the lock is not available within `max_spins` iterations returns
+    /// [`Timeout`](crate::Timeout).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{Mutex, Timeout};
+    ///
+    /// let mutex = Mutex::new(1);
+    ///
+    /// let guard = mutex.lock_deadline(1_000).unwrap();
+    /// assert_eq!(mutex.lock_deadline(1_000).unwrap_err(), Timeout);
+    /// ```
+    #[cfg(not(feature = "poison"))]
+    #[inline]
+    pub fn lock_deadline(&self, max_spins: u64) -> Result<MutexGuard<'_, T, R>, Timeout> {
+        self.acquire_deadline(max_spins)
+    }
 
+    /// Acquires the lock, spinning at most `max_spins` iterations in total,
+    /// returns [`Timeout`](crate::Timeout) if the lock never became available.
+    ///
+    /// The deadline is counted in spin iterations rather than in time so that
+    /// the acquisition is bounded without a clock, for instance in the loop of
+    /// a hard real-time `no_std` application. Each iteration relaxes the CPU
+    /// once with the relax strategy of the mutex.
+    ///
+    /// If another thread panicked while holding the lock, the mutex is poisoned
+    /// and the guard is returned within a [`PoisonError`](crate::PoisonError).
+    ///
+    /// # Errors
+    /// If the lock is not available within `max_spins` iterations returns
+    /// [`Timeout`](crate::Timeout).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{Mutex, Timeout};
+    ///
+    /// let mutex = Mutex::new(1);
+    ///
+    /// let guard = mutex.lock_deadline(1_000).unwrap().unwrap();
+    /// assert_eq!(mutex.lock_deadline(1_000).unwrap_err(), Timeout);
+    /// ```
+    #[cfg(feature = "poison")]
     #[inline]
-    fn deref(&self) -> &Self::Target {
-        // SAFETY: A guard is only created if no one holds the lock meaning that
-        // no one else can modify the data so it is safe to get reference to the
-        // data for the lifetime of the guard.
-        unsafe { &*self.mutex.data.get() }
+    pub fn lock_deadline(
+        &self,
+        max_spins: u64,
+    ) -> Result<LockResult<MutexGuard<'_, T, R>>, Timeout> {
+        self.acquire_deadline(max_spins)
+            .map(|guard| self.check_poison(guard))
+    }
+
+    /// Tries to acquire the lock, reloading it for a few spin loop iterations
+    /// before giving up if it is held. If the lock is not available returns
+    /// `None`.
+    ///
+    /// This is a slightly more optimistic [`try_lock`](self::Mutex::try_lock)
+    /// for a lock which is about to be released, without committing to the
+    /// spinning of [`lock`](self::Mutex::lock). The lock is reloaded at most
+    /// 4 times with a [`spin_loop`](core::hint::spin_loop) hint in between,
+    /// regardless of the relax strategy, so it returns within a few hundred
+    /// cycles at most.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let mutex = Mutex::new(1);
+    /// let guard = mutex.try_lock_hot().unwrap();
+    /// assert!(mutex.try_lock_hot().is_none());
+    /// ```
+    #[cfg(not(feature = "poison"))]
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_lock_hot(&self) -> Option<MutexGuard<'_, T, R>> {
+        self.try_acquire_hot()
     }
-}
 
-impl<T> DerefMut for MutexGuard<'_, T> {
+    /// Tries to acquire the lock, reloading it for a few spin loop iterations
+    /// before giving up if it is held. If the lock is not available returns
+    /// `None`.
+    ///
+    /// This is a slightly more optimistic [`try_lock`](self::Mutex::try_lock)
+    /// for a lock which is about to be released, without committing to the
+    /// spinning of [`lock`](self::Mutex::lock). The lock is reloaded at most
+    /// 4 times with a [`spin_loop`](core::hint::spin_loop) hint in between,
+    /// regardless of the relax strategy, so it returns within a few hundred
+    /// cycles at most.
+    ///
+    /// If another thread panicked while holding the lock, the mutex is poisoned
+    /// and the guard is returned within a [`PoisonError`](crate::PoisonError).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let mutex = Mutex::new(1);
+    /// let guard = mutex.try_lock_hot().unwrap().unwrap();
+    /// assert!(mutex.try_lock_hot().is_none());
+    /// ```
+    #[cfg(feature = "poison")]
     #[inline]
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        // SAFETY: A guard is only created if no one holds the lock meaning that
-        // no one else can modify the data so it is safe to get a mutable reference
-        // to the data for the lifetime of the guard.
-        unsafe { &mut *self.mutex.data.get() }
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_lock_hot(&self) -> Option<LockResult<MutexGuard<'_, T, R>>> {
+        self.try_acquire_hot().map(|guard| self.check_poison(guard))
     }
-}
 
-impl<T> Drop for MutexGuard<'_, T> {
+    /// Acquires the lock and calls the closure with a mutable reference to the data.
+    ///
+    /// The lock is released once the closure returns, so the locked region is
+    /// limited to the closure.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let mutex = Mutex::new(1);
+    ///
+    /// let previous = mutex.with_lock(|data| {
+    ///     *data += 1;
+    ///     *data - 1
+    /// });
+    /// assert_eq!(previous, 1);
+    /// assert!(!mutex.is_locked());
+    /// ```
     #[inline]
-    fn drop(&mut self) {
-        // SAFETY: It is only possible that one guard exists for a certain mutex
-        // which is the current one so it is safe to unlock the mutex when the
-        // guard gets dropped.
-        unsafe { self.mutex.unlock() }
+    pub fn with_lock<U, F>(&self, f: F) -> U
+    where
+        F: FnOnce(&mut T) -> U,
+    {
+        f(&mut self.acquire())
     }
-}
 
-/// Prevents the guard from being sent to another thread.
-impl<T> !Send for MutexGuard<'_, T> {}
+    /// Acquires the lock and calls the closure with a shared reference to the
+    /// data, returning the value it computed.
+    ///
+    /// This is the read-only sibling of [`with_lock`](self::Mutex::with_lock),
+    /// the lock is released before the value is returned so it must not
+    /// borrow from the data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let mutex = Mutex::new((String::from("name"), 42));
+    ///
+    /// let name = mutex.map_locked(|data| data.0.clone());
+    /// assert_eq!(name, "name");
+    /// assert!(!mutex.is_locked());
+    /// ```
+    #[inline]
+    pub fn map_locked<U, F>(&self, f: F) -> U
+    where
+        F: FnOnce(&T) -> U,
+    {
+        f(&self.acquire())
+    }
+
+    /// Acquires the lock and calls the closure with a mutable reference to the
+    /// data, returning the value it computed.
+    ///
+    /// This is the mutable sibling of [`map_locked`](self::Mutex::map_locked)
+    /// and an alias of [`with_lock`](self::Mutex::with_lock), the lock is
+    /// released before the value is returned so it must not borrow from the
+    /// data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let mutex = Mutex::new(1);
+    ///
+    /// let incremented = mutex.map_locked_mut(|data| {
+    ///     *data += 1;
+    ///     *data
+    /// });
+    /// assert_eq!(incremented, 2);
+    /// assert!(!mutex.is_locked());
+    /// ```
+    #[inline]
+    pub fn map_locked_mut<U, F>(&self, f: F) -> U
+    where
+        F: FnOnce(&mut T) -> U,
+    {
+        self.with_lock(f)
+    }
+
+    /// Acquires the lock regardless of the poisoning.
+    #[inline]
+    pub(crate) fn acquire(&self) -> MutexGuard<'_, T, R> {
+        // The critical section is entered before spinning so that an interrupt
+        // can't happen once the lock is held.
+        let critical_section = CriticalSection::enter();
+        self.raw.lock();
+        MutexGuard::new(self, critical_section)
+    }
+
+    /// Acquires the locks of two distinct mutexes regardless of the poisoning
+    /// and calls the closure with mutable references to their data.
+    ///
+    /// The mutex with the lowest address is always locked first so that two
+    /// threads locking the same mutexes in opposite order do not deadlock.
+    /// The locks are released in the reverse order.
+    fn with_pair<U, F>(&self, other: &Self, f: F) -> U
+    where
+        F: FnOnce(&mut T, &mut T) -> U,
+    {
+        if self.id() < other.id() {
+            let mut ours = self.acquire();
+            let mut theirs = other.acquire();
+            f(&mut ours, &mut theirs)
+        } else {
+            let mut theirs = other.acquire();
+            let mut ours = self.acquire();
+            f(&mut ours, &mut theirs)
+        }
+    }
+
+    /// Tries to acquire the lock regardless of the poisoning.
+    pub(crate) fn try_acquire(&self) -> Option<MutexGuard<'_, T, R>> {
+        let critical_section = CriticalSection::enter();
+        if self.raw.try_lock() {
+            Some(MutexGuard::new(self, critical_section))
+        } else {
+            // SAFETY: The critical section was entered above and no other
+            // one was entered since.
+            unsafe { critical_section.exit() };
+            None
+        }
+    }
+
+    /// Tries to acquire the lock regardless of the poisoning, reloading it up to
+    /// `HOT_SPINS` times while it is held.
+    fn try_acquire_hot(&self) -> Option<MutexGuard<'_, T, R>> {
+        if let Some(guard) = self.try_acquire() {
+            return Some(guard);
+        }
+
+        for _ in 0..HOT_SPINS {
+            hint::spin_loop();
+            // Only tries again once the lock is observed free to not write
+            // to the cache line while it is held.
+            if !self.raw.is_locked() {
+                if let Some(guard) = self.try_acquire() {
+                    return Some(guard);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Tries to acquire the lock up to `max_spins` times regardless of the poisoning.
+    fn try_acquire_spin(&self, max_spins: usize) -> Option<MutexGuard<'_, T, R>> {
+        for _ in 0..max_spins {
+            if let Some(guard) = self.try_acquire() {
+                return Some(guard);
+            }
+            R::relax();
+        }
+
+        None
+    }
+
+    /// Acquires the lock regardless of the poisoning, spinning at most
+    /// `max_spins` iterations in total.
+    fn acquire_deadline(&self, max_spins: u64) -> Result<MutexGuard<'_, T, R>, Timeout> {
+        let mut spins = 0;
+        loop {
+            if let Some(guard) = self.try_acquire() {
+                return Ok(guard);
+            }
+
+            // Waits for the lock to be released before trying again, so that
+            // the cache line is not written while the lock is held.
+            loop {
+                if spins == max_spins {
+                    return Err(Timeout);
+                }
+                spins += 1;
+                R::relax();
+
+                if !self.is_locked() {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Returns whether the mutex is poisoned.
+    ///
+    /// A mutex is poisoned when a thread panics while holding it.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    /// use std::sync::Arc;
+    /// use std::thread;
+    ///
+    /// let mutex = Arc::new(Mutex::new(1));
+    /// let m = Arc::clone(&mutex);
+    ///
+    /// let _ = thread::spawn(move || {
+    ///     let _guard = m.lock().unwrap();
+    ///     panic!();
+    /// }).join();
+    /// assert!(mutex.is_poisoned());
+    /// ```
+    #[cfg(feature = "poison")]
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Relaxed)
+    }
+
+    /// Clears the poisoning of the mutex.
+    ///
+    /// This should be called once the data has been restored to a consistent state.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    /// use std::sync::Arc;
+    /// use std::thread;
+    ///
+    /// let mutex = Arc::new(Mutex::new(1));
+    /// let m = Arc::clone(&mutex);
+    ///
+    /// let _ = thread::spawn(move || {
+    ///     let _guard = m.lock().unwrap();
+    ///     panic!();
+    /// }).join();
+    ///
+    /// let mut guard = mutex.lock().unwrap_err().into_inner();
+    /// *guard = 1;
+    /// mutex.clear_poison();
+    /// assert!(!mutex.is_poisoned());
+    /// ```
+    #[cfg(feature = "poison")]
+    #[inline]
+    pub fn clear_poison(&self) {
+        self.poisoned.store(false, Ordering::Relaxed);
+    }
+
+    /// Wraps the guard within a `PoisonError` if the mutex is poisoned.
+    #[cfg(feature = "poison")]
+    #[inline]
+    pub(crate) fn check_poison<G>(&self, guard: G) -> LockResult<G> {
+        if self.is_poisoned() {
+            Err(PoisonError::new(guard))
+        } else {
+            Ok(guard)
+        }
+    }
+
+    /// Returns whether the lock is currently held by a thread.
+    ///
+    /// The returned value is only a snapshot of the lock state which may
+    /// change right after the call, so it must not be used to make
+    /// synchronization decisions. It is meant for diagnostics such as
+    /// metrics or logging.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let mutex = Mutex::new(1);
+    /// assert!(!mutex.is_locked());
+    ///
+    /// let guard = mutex.lock();
+    /// assert!(mutex.is_locked());
+    /// ```
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.raw.is_locked()
+    }
+
+    /// Returns the address of the mutex, a stable identity of this instance.
+    ///
+    /// The identity does not change while the mutex is borrowed, and two
+    /// mutexes alive at the same time never share it. It can be used to
+    /// acquire several locks in a consistent order, such as the order of
+    /// their addresses, or to track the locks held by a thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let a = Mutex::new(1);
+    /// let b = Mutex::new(2);
+    ///
+    /// assert_eq!(a.id(), a.id());
+    /// assert_ne!(a.id(), b.id());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn id(&self) -> usize {
+        ptr::from_ref(self).addr()
+    }
+
+    /// Returns whether the two references point to the same mutex, comparing
+    /// their identities rather than their data, see [`id`](self::Mutex::id).
+    ///
+    /// This is an associated function that needs to be used as
+    /// `Mutex::ptr_eq(...)`, like `Arc::ptr_eq`.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let a = Mutex::new(1);
+    /// let b = Mutex::new(1);
+    ///
+    /// assert!(Mutex::ptr_eq(&a, &a));
+    /// assert!(!Mutex::ptr_eq(&a, &b));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        ptr::addr_eq(this, other)
+    }
+
+    /// Returns the number of times a thread had to wait in
+    /// [`lock`](self::Mutex::lock) because the mutex was held, since it was
+    /// created.
+    ///
+    /// Each contended call to `lock` counts once however long it spins, the
+    /// uncontended ones and the non-blocking attempts are not counted. Like
+    /// [`is_locked`](self::Mutex::is_locked) the returned value is only a
+    /// snapshot meant for diagnostics, such as finding the hot locks of a
+    /// program.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    ///
+    /// let mutex = Mutex::new(1);
+    ///
+    /// drop(mutex.lock());
+    /// assert_eq!(mutex.contention_count(), 0);
+    /// ```
+    #[cfg(feature = "stats")]
+    #[inline]
+    #[must_use]
+    pub fn contention_count(&self) -> usize {
+        self.raw.contentions.load(Ordering::Relaxed)
+    }
+
+    /// Forcibly releases the lock.
+    ///
+    /// This is useful when the guard has been forgotten with [`core::mem::forget`]
+    /// to hand the responsibility of unlocking to another component.
+    ///
+    /// # Safety
+    /// The lock must be held by the caller and its guard must not be used nor
+    /// dropped afterwards, typically because it was forgotten. Releasing the lock
+    /// while a guard is still in use allows other threads to access the data
+    /// concurrently with it.
+    ///
+    /// With the `critical-section` feature, the critical section entered by
+    /// the forgotten guard is not exited.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Mutex;
+    /// use std::mem;
+    ///
+    /// let mutex = Mutex::new(1);
+    ///
+    /// mem::forget(mutex.try_lock());
+    /// assert!(mutex.is_locked());
+    ///
+    /// // SAFETY: The guard of the lock was forgotten.
+    /// unsafe { mutex.force_unlock() };
+    /// assert!(!mutex.is_locked());
+    /// ```
+    #[allow(clippy::inline_always)]
+    #[inline(always)]
+    pub unsafe fn force_unlock(&self) {
+        self.raw.unlock();
+    }
+}
+
+impl<T: Default, R: Relax> Default for Mutex<T, R> {
+    /// Creates a `Mutex<T, R>` which is unlocked containing the default of `T`.
+    #[inline]
+    fn default() -> Self {
+        Self::with_relax(T::default())
+    }
+}
+
+impl<T, R: Relax> From<T> for Mutex<T, R> {
+    /// Creates a `Mutex<T, R>` which is unlocked containing the given value.
+    #[inline]
+    fn from(data: T) -> Self {
+        Self::with_relax(data)
+    }
+}
+
+impl<T: Clone, R: Relax> Clone for Mutex<T, R> {
+    /// Creates a new unlocked mutex containing a clone of the data.
+    ///
+    /// The lock is acquired while the data is cloned regardless of the
+    /// poisoning. The clone is independent from the original mutex, it is
+    /// not a handle to the same data.
+    #[inline]
+    fn clone(&self) -> Self {
+        Self::with_relax(T::clone(&self.acquire()))
+    }
+}
+
+impl<T: ?Sized + fmt::Debug, R: Relax> fmt::Debug for Mutex<T, R> {
+    /// Formats the data of the mutex, or `<locked>` if the lock is held.
+    ///
+    /// This never blocks, the lock is only tried once.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("Mutex");
+        match self.try_acquire() {
+            Some(guard) => d.field("data", &&*guard),
+            None => d.field("data", &format_args!("<locked>")),
+        };
+        #[cfg(feature = "poison")]
+        d.field("poisoned", &self.is_poisoned());
+        d.finish()
+    }
+}
+
+impl<T: ?Sized, R: Relax> fmt::Pointer for Mutex<T, R> {
+    /// Formats the address of the mutex, see [`id`](self::Mutex::id).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&ptr::from_ref(self), f)
+    }
+}
+
+impl<T: PartialEq, R: Relax> PartialEq for Mutex<T, R> {
+    /// Compares the data of two mutexes, both locks are acquired during the
+    /// comparison regardless of the poisoning.
+    ///
+    /// To prevent deadlocks the locks are acquired in the order of the mutexes
+    /// addresses, comparing a mutex with itself acquires its lock only once.
+    fn eq(&self, other: &Self) -> bool {
+        if ptr::eq(self, other) {
+            let guard = self.acquire();
+            return T::eq(&guard, &guard);
+        }
+
+        self.with_pair(other, |ours, theirs| *ours == *theirs)
+    }
+}
+
+impl<T: Eq, R: Relax> Eq for Mutex<T, R> {}
+
+// SAFETY: The mutex owns the data so it can be sent to another thread along
+// with it.
+unsafe impl<T: ?Sized + Send, R: Relax> Send for Mutex<T, R> {}
+
+// SAFETY: The locking mechanism ensures that only one thread accesses the data
+// at a time, which may move it out of the mutex so it must be `Send`.
+unsafe impl<T: ?Sized + Send, R: Relax> Sync for Mutex<T, R> {}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize, R: Relax> serde::Serialize for Mutex<T, R> {
+    /// Serializes the data of the mutex, the lock is acquired during the serialization.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.acquire().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>, R: Relax> serde::Deserialize<'de> for Mutex<T, R> {
+    /// Deserializes the data into an unlocked mutex.
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::with_relax)
+    }
+}
+
+/// This structure is created by calling [`lock`](self::Mutex::lock)
+/// or [`try_lock`](self::Mutex::try_lock) on [`Mutex`](self::Mutex).
+///
+/// The lock is released when the guard is dropped, so a guard which is not
+/// bound to a variable releases the lock immediately. The `unused_must_use`
+/// lint warns about it:
+/// ```compile_fail
+/// #![deny(unused_must_use)]
+/// use spinlock::Mutex;
+///
+/// let mutex = Mutex::new(1);
+/// mutex.lock();
+/// ```
+///
+/// The guard is `!Send`, it must be dropped by the thread which acquired the
+/// lock. With the `send_guard` feature it is `Send` when `T` is `Send`
+/// instead, so that the lock can be released by another thread. **This is
+/// only meant for runtimes moving tasks between threads on purpose**: code
+/// relying on the lock being released by the thread which acquired it, such
+/// as thread-local bookkeeping of the held locks, breaks when the feature is
+/// enabled.
+#[derive(Debug)]
+#[must_use = "if unused the lock is immediately released"]
+pub struct MutexGuard<'mutex, T: ?Sized, R: Relax = Spin> {
+    mutex: &'mutex Mutex<T, R>,
+    #[cfg(feature = "poison")]
+    poison: PoisonOnPanic<'mutex>,
+    // Critical section entered before acquiring the lock.
+    critical_section: CriticalSection,
+}
+
+impl<'mutex, T: ?Sized, R: Relax> MutexGuard<'mutex, T, R> {
+    /// Creates a `MutexGuard<'mutex, T, R>` of a given Mutex.
+    #[cfg(not(feature = "poison"))]
+    #[inline]
+    const fn new(mutex: &'mutex Mutex<T, R>, critical_section: CriticalSection) -> Self {
+        Self {
+            mutex,
+            critical_section,
+        }
+    }
+
+    /// Creates a `MutexGuard<'mutex, T, R>` of a given Mutex.
+    #[cfg(feature = "poison")]
+    #[inline]
+    fn new(mutex: &'mutex Mutex<T, R>, critical_section: CriticalSection) -> Self {
+        Self {
+            mutex,
+            poison: PoisonOnPanic::new(&mutex.poisoned),
+            critical_section,
+        }
+    }
+
+    /// Returns the mutex locked by the guard.
+    ///
+    /// This is an associated function that needs to be used as `MutexGuard::mutex(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{Mutex, MutexGuard};
+    ///
+    /// let mutex = Mutex::new(1);
+    ///
+    /// # #[cfg(feature = "poison")]
+    /// # let guard = mutex.lock().unwrap();
+    /// # #[cfg(not(feature = "poison"))]
+    /// let guard = mutex.lock();
+    /// let locked = MutexGuard::mutex(&guard);
+    /// drop(guard);
+    ///
+    /// assert!(!locked.is_locked());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn mutex(guard: &Self) -> &'mutex Mutex<T, R> {
+        guard.mutex
+    }
+
+    /// Returns a reference to the locked data.
+    ///
+    /// This is the same as dereferencing the guard, as an explicit borrow
+    /// which is more readable in generic code.
+    ///
+    /// This is an associated function that needs to be used as `MutexGuard::get(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{Mutex, MutexGuard};
+    ///
+    /// let mutex = Mutex::new(1);
+    ///
+    /// # #[cfg(feature = "poison")]
+    /// # let guard = mutex.lock().unwrap();
+    /// # #[cfg(not(feature = "poison"))]
+    /// let guard = mutex.lock();
+    /// assert_eq!(*MutexGuard::get(&guard), 1);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn get(guard: &Self) -> &T {
+        guard
+    }
+
+    /// Returns a mutable reference to the locked data.
+    ///
+    /// This is the same as dereferencing the guard mutably, as an explicit
+    /// borrow which is more readable in generic code.
+    ///
+    /// This is an associated function that needs to be used as `MutexGuard::get_mut(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{Mutex, MutexGuard};
+    ///
+    /// let mutex = Mutex::new(1);
+    ///
+    /// # #[cfg(feature = "poison")]
+    /// # let mut guard = mutex.lock().unwrap();
+    /// # #[cfg(not(feature = "poison"))]
+    /// let mut guard = mutex.lock();
+    /// *MutexGuard::get_mut(&mut guard) = 2;
+    /// assert_eq!(*guard, 2);
+    /// ```
+    #[inline]
+    pub fn get_mut(guard: &mut Self) -> &mut T {
+        guard
+    }
+
+    /// Leaks the guard, returning a mutable reference to the data for the
+    /// lifetime of the mutex.
+    ///
+    /// The guard is forgotten so the mutex stays locked forever: every later
+    /// attempt to lock it spins endlessly or fails, unless it is forcibly
+    /// unlocked. With the `critical-section` feature the critical section
+    /// entered when locking is never exited either. This is meant for data
+    /// initialized once and never released, such as a singleton in a `static`.
+    ///
+    /// This is an associated function that needs to be used as `MutexGuard::leak(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{Mutex, MutexGuard};
+    ///
+    /// static CONFIG: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+    ///
+    /// # #[cfg(feature = "poison")]
+    /// # let guard = CONFIG.lock().unwrap();
+    /// # #[cfg(not(feature = "poison"))]
+    /// let guard = CONFIG.lock();
+    /// let config: &'static mut Vec<u32> = MutexGuard::leak(guard);
+    /// config.push(1);
+    ///
+    /// assert!(CONFIG.is_locked());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn leak(guard: Self) -> &'mutex mut T {
+        let mutex = guard.mutex;
+        // The lock is never released.
+        mem::forget(guard);
+
+        // SAFETY: The lock is held forever so no other reference to the data
+        // can be created.
+        mutex.data.with_mut(|data| unsafe { &mut *data })
+    }
+
+    /// Makes a new [`MappedMutexGuard`](self::MappedMutexGuard) for a component
+    /// of the locked data.
+    ///
+    /// The mutex stays locked until the returned guard is dropped.
+    ///
+    /// This is an associated function that needs to be used as `MutexGuard::map(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{Mutex, MutexGuard};
+    ///
+    /// let mutex = Mutex::new((1, 2));
+    ///
+    /// # #[cfg(feature = "poison")]
+    /// # let guard = mutex.lock().unwrap();
+    /// # #[cfg(not(feature = "poison"))]
+    /// let guard = mutex.lock();
+    /// let mut second = MutexGuard::map(guard, |data| &mut data.1);
+    /// *second = 42;
+    /// drop(second);
+    ///
+    /// assert_eq!(mutex.with_lock(|data| *data), (1, 42));
+    /// ```
+    #[inline]
+    pub fn map<U: ?Sized, F>(orig: Self, f: F) -> MappedMutexGuard<'mutex, U>
+    where
+        F: FnOnce(&mut T) -> &mut U,
+    {
+        // SAFETY: The guard is held so it is safe to get a mutable reference
+        // to the data.
+        let data = f(orig.mutex.data.with_mut(|data| unsafe { &mut *data }));
+        let mapped = MappedMutexGuard::new(&orig, data);
+        // The lock is now released by the mapped guard.
+        mem::forget(orig);
+
+        mapped
+    }
+
+    /// Tries to make a new [`MappedMutexGuard`](self::MappedMutexGuard) for a
+    /// component of the locked data.
+    ///
+    /// The mutex stays locked until the returned guard is dropped.
+    ///
+    /// This is an associated function that needs to be used as `MutexGuard::try_map(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Errors
+    /// If the closure returns `None` the original guard is returned back
+    /// so the lock is not released.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{Mutex, MutexGuard};
+    ///
+    /// let mutex = Mutex::new(Some(1));
+    ///
+    /// # #[cfg(feature = "poison")]
+    /// # let guard = mutex.lock().unwrap();
+    /// # #[cfg(not(feature = "poison"))]
+    /// let guard = mutex.lock();
+    /// let mut inner = MutexGuard::try_map(guard, Option::as_mut).unwrap();
+    /// *inner = 42;
+    /// drop(inner);
+    ///
+    /// assert_eq!(mutex.with_lock(|data| *data), Some(42));
+    /// ```
+    #[inline]
+    pub fn try_map<U: ?Sized, F>(orig: Self, f: F) -> Result<MappedMutexGuard<'mutex, U>, Self>
+    where
+        F: FnOnce(&mut T) -> Option<&mut U>,
+    {
+        // SAFETY: The guard is held so it is safe to get a mutable reference
+        // to the data.
+        match f(orig.mutex.data.with_mut(|data| unsafe { &mut *data })) {
+            Some(data) => {
+                let mapped = MappedMutexGuard::new(&orig, data);
+                // The lock is now released by the mapped guard.
+                mem::forget(orig);
+                Ok(mapped)
+            }
+            None => Err(orig),
+        }
+    }
+
+    /// Temporarily releases the lock while calling the closure, then
+    /// re-acquires it before returning.
+    ///
+    /// This lets other threads acquire the lock during a long operation which
+    /// does not need the data. The data may have been modified by another
+    /// thread once the closure returns. The lock is re-acquired even if the
+    /// closure panics.
+    ///
+    /// This is an associated function that needs to be used as `MutexGuard::unlocked(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{Mutex, MutexGuard};
+    ///
+    /// let mutex = Mutex::new(1);
+    ///
+    /// # #[cfg(feature = "poison")]
+    /// # let mut guard = mutex.lock().unwrap();
+    /// # #[cfg(not(feature = "poison"))]
+    /// let mut guard = mutex.lock();
+    /// MutexGuard::unlocked(&mut guard, || assert!(!mutex.is_locked()));
+    /// assert_eq!(*guard, 1);
+    /// ```
+    pub fn unlocked<U, F>(guard: &mut Self, f: F) -> U
+    where
+        F: FnOnce() -> U,
+    {
+        // Writes back a new guard when dropped, including when the closure
+        // panics, so that the guard is valid again once the function exits.
+        struct Relock<'guard, 'mutex, T: ?Sized, R: Relax> {
+            guard: &'guard mut MutexGuard<'mutex, T, R>,
+            mutex: &'mutex Mutex<T, R>,
+        }
+
+        impl<T: ?Sized, R: Relax> Drop for Relock<'_, '_, T, R> {
+            fn drop(&mut self) {
+                // SAFETY: The previous guard was dropped in place so it is
+                // overwritten without being dropped again.
+                unsafe { ptr::write(self.guard, self.mutex.acquire()) };
+            }
+        }
+
+        let mutex = guard.mutex;
+        // SAFETY: The guard is not used until it is written back by `Relock`.
+        unsafe { ptr::drop_in_place(ptr::from_mut(guard)) };
+        let _relock = Relock { guard, mutex };
+
+        f()
+    }
+}
+
+impl<T: ?Sized, R: Relax> Deref for MutexGuard<'_, T, R> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: A guard is only created if no one holds the lock meaning that
+        // no one else can modify the data so it is safe to get reference to the
+        // data for the lifetime of the guard.
+        self.mutex.data.with(|data| unsafe { &*data })
+    }
+}
+
+impl<T: ?Sized, R: Relax> DerefMut for MutexGuard<'_, T, R> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: A guard is only created if no one holds the lock meaning that
+        // no one else can modify the data so it is safe to get a mutable reference
+        // to the data for the lifetime of the guard.
+        self.mutex.data.with_mut(|data| unsafe { &mut *data })
+    }
+}
+
+impl<T: ?Sized + fmt::Display, R: Relax> fmt::Display for MutexGuard<'_, T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + Hash, R: Relax> Hash for MutexGuard<'_, T, R> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<T: ?Sized + PartialEq, R: Relax> PartialEq for MutexGuard<'_, T, R> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + Eq, R: Relax> Eq for MutexGuard<'_, T, R> {}
+
+impl<T: ?Sized + PartialOrd, R: Relax> PartialOrd for MutexGuard<'_, T, R> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: ?Sized + Ord, R: Relax> Ord for MutexGuard<'_, T, R> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: ?Sized, R: Relax> Borrow<T> for MutexGuard<'_, T, R> {
+    #[inline]
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized, R: Relax> BorrowMut<T> for MutexGuard<'_, T, R> {
+    #[inline]
+    fn borrow_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+impl<T: ?Sized, R: Relax> Drop for MutexGuard<'_, T, R> {
+    #[inline]
+    fn drop(&mut self) {
+        #[cfg(feature = "poison")]
+        self.poison.release();
+
+        // SAFETY: It is only possible that one guard exists for a certain mutex
+        // which is the current one so it is safe to unlock the mutex when the
+        // guard gets dropped.
+        unsafe { self.mutex.force_unlock() }
+
+        // SAFETY: The critical section is exited once the lock is released,
+        // only by the guard which entered it.
+        unsafe { self.critical_section.exit() }
+    }
+}
+
+/// Prevents the guard from being sent to another thread.
+#[cfg(not(feature = "send_guard"))]
+impl<T: ?Sized, R: Relax> !Send for MutexGuard<'_, T, R> {}
+
+// SAFETY: The spinlock can be released by any thread, sending the guard only
+// gives the exclusive access to the data to another thread.
+#[cfg(feature = "send_guard")]
+unsafe impl<T: ?Sized + Send, R: Relax> Send for MutexGuard<'_, T, R> {}
+
+unsafe impl<T: ?Sized + Sync, R: Relax> Sync for MutexGuard<'_, T, R> {}
+
+/// Guard structure referencing a component of the data of a locked [`Mutex`](self::Mutex).
+///
+/// This structure is created by [`MutexGuard::map`](self::MutexGuard::map).
+#[derive(Debug)]
+#[must_use = "if unused the lock is immediately released"]
+pub struct MappedMutexGuard<'mutex, T: ?Sized> {
+    // Lock of the mutex to release when dropped.
+    lock: &'mutex AtomicBool,
+    // Component of the locked data.
+    data: *mut T,
+    #[cfg(feature = "poison")]
+    poison: PoisonOnPanic<'mutex>,
+    // Critical section taken over from the original guard.
+    critical_section: CriticalSection,
+    // Wakes up the threads spinning on the lock once it is released.
+    wake: fn(),
+    _marker: PhantomData<&'mutex mut T>,
+}
+
+impl<'mutex, T: ?Sized> MappedMutexGuard<'mutex, T> {
+    /// Creates a `MappedMutexGuard<'mutex, T>` taking over the lock of a guard
+    /// with a component of its data.
+    #[inline]
+    const fn new<U: ?Sized, R: Relax>(
+        orig: &MutexGuard<'mutex, U, R>,
+        data: &'mutex mut T,
+    ) -> Self {
+        Self {
+            lock: &orig.mutex.raw.lock,
+            data,
+            #[cfg(feature = "poison")]
+            poison: orig.poison,
+            critical_section: orig.critical_section,
+            wake: R::wake,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for MappedMutexGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The pointer comes from a mutable reference to the locked data
+        // which stays valid as long as the lock is held by this guard.
+        unsafe { &*self.data }
+    }
+}
+
+impl<T: ?Sized> DerefMut for MappedMutexGuard<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: The pointer comes from a mutable reference to the locked data
+        // which stays valid as long as the lock is held by this guard.
+        unsafe { &mut *self.data }
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for MappedMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + Hash> Hash for MappedMutexGuard<'_, T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for MappedMutexGuard<'_, T> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for MappedMutexGuard<'_, T> {}
+
+impl<T: ?Sized + PartialOrd> PartialOrd for MappedMutexGuard<'_, T> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: ?Sized + Ord> Ord for MappedMutexGuard<'_, T> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: ?Sized> Borrow<T> for MappedMutexGuard<'_, T> {
+    #[inline]
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> BorrowMut<T> for MappedMutexGuard<'_, T> {
+    #[inline]
+    fn borrow_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+impl<T: ?Sized> Drop for MappedMutexGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        #[cfg(feature = "poison")]
+        self.poison.release();
+
+        // The mapped guard took over the lock of the original guard
+        // so it is the only one responsible for releasing it.
+        store_release!(self.lock, false);
+        (self.wake)();
+
+        // SAFETY: The mapped guard took over the critical section of the
+        // original guard, it is exited once the lock is released.
+        unsafe { self.critical_section.exit() }
+    }
+}
+
+/// Prevents the guard from being sent to another thread.
+#[cfg(not(feature = "send_guard"))]
+impl<T: ?Sized> !Send for MappedMutexGuard<'_, T> {}
+
+// SAFETY: The spinlock can be released by any thread, sending the guard only
+// gives the exclusive access to the data to another thread.
+#[cfg(feature = "send_guard")]
+unsafe impl<T: ?Sized + Send> Send for MappedMutexGuard<'_, T> {}
 
-unsafe impl<T: Sync> Sync for MutexGuard<'_, T> {}
+unsafe impl<T: ?Sized + Sync> Sync for MappedMutexGuard<'_, T> {}
@@ -0,0 +1,98 @@
+use crate::mutex::{Mutex, MutexGuard};
+#[cfg(feature = "poison")]
+use crate::poison::LockResult;
+
+/// A mutual exclusion lock which can only be acquired without blocking.
+///
+/// This is a [`Mutex`](crate::Mutex) which only exposes
+/// [`try_lock`](self::NonBlockingMutex::try_lock), so that the type system
+/// prevents spinning on it in contexts where blocking is forbidden, such as
+/// interrupt handlers:
+/// ```compile_fail
+/// use spinlock::NonBlockingMutex;
+///
+/// let mutex = NonBlockingMutex::new(1);
+/// let guard = mutex.lock();
+/// ```
+///
+/// # Examples
+/// ```
+/// use spinlock::NonBlockingMutex;
+///
+/// static COUNTER: NonBlockingMutex<u32> = NonBlockingMutex::new(0);
+///
+/// let counter = COUNTER.try_lock();
+/// assert!(counter.is_some());
+/// assert!(COUNTER.try_lock().is_none());
+/// ```
+#[derive(Debug)]
+pub struct NonBlockingMutex<T> {
+    mutex: Mutex<T>,
+}
+
+impl<T> NonBlockingMutex<T> {
+    const_fn! {
+        /// Creates a new `NonBlockingMutex<T>` which is unlocked.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::NonBlockingMutex;
+        ///
+        /// let mutex = NonBlockingMutex::new(1);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn new(data: T) -> Self {
+            Self {
+                mutex: Mutex::new(data),
+            }
+        }
+    }
+
+    /// Tries to acquire the lock. If the lock is not available returns `None`.
+    ///
+    /// This function does not block the current thread, it behaves like
+    /// [`Mutex::try_lock`](crate::Mutex::try_lock).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::NonBlockingMutex;
+    ///
+    /// let mutex = NonBlockingMutex::new(1);
+    ///
+    /// let guard = mutex.try_lock();
+    /// assert!(guard.is_some());
+    /// assert!(mutex.try_lock().is_none());
+    /// ```
+    #[cfg(not(feature = "poison"))]
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.mutex.try_lock()
+    }
+
+    /// Tries to acquire the lock. If the lock is not available returns `None`.
+    ///
+    /// This function does not block the current thread, it behaves like
+    /// [`Mutex::try_lock`](crate::Mutex::try_lock).
+    ///
+    /// If another thread panicked while holding the lock, the mutex is poisoned
+    /// and the guard is returned within a [`PoisonError`](crate::PoisonError).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::NonBlockingMutex;
+    ///
+    /// let mutex = NonBlockingMutex::new(1);
+    ///
+    /// let guard = mutex.try_lock();
+    /// assert!(guard.is_some());
+    /// assert!(mutex.try_lock().is_none());
+    /// ```
+    #[cfg(feature = "poison")]
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_lock(&self) -> Option<LockResult<MutexGuard<'_, T>>> {
+        self.mutex.try_lock()
+    }
+}
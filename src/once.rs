@@ -0,0 +1,133 @@
+use core::mem;
+
+use crate::backoff::Backoff;
+use crate::sync::{AtomicU8, Ordering};
+
+// The initialization has not run yet, or the initializer panicked.
+const INCOMPLETE: u8 = 0;
+// A thread is running the initializer.
+const RUNNING: u8 = 1;
+// The initializer has run to completion.
+const COMPLETE: u8 = 2;
+
+/// A synchronization primitive to run a one-time initialization.
+///
+/// If the initializer panics the `Once` goes back to its incomplete state so
+/// that a later call can run the initialization again.
+#[derive(Debug)]
+pub struct Once {
+    // The state of the initialization, one of `INCOMPLETE`, `RUNNING` or `COMPLETE`.
+    state: AtomicU8,
+}
+
+impl Once {
+    const_fn! {
+        /// Creates a new `Once` whose initialization has not run yet.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::Once;
+        ///
+        /// static INIT: Once = Once::new();
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn new() -> Self {
+            Self {
+                state: AtomicU8::new(INCOMPLETE),
+            }
+        }
+    }
+
+    /// Runs the closure if the initialization has not run yet.
+    ///
+    /// The closure is run exactly once across all threads, if another thread
+    /// is currently running the initialization this function blocks the current
+    /// thread by spinning until it completes. When this function returns the
+    /// initialization is complete.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Once;
+    ///
+    /// let once = Once::new();
+    /// let mut value = 0;
+    ///
+    /// once.call_once(|| value += 1);
+    /// once.call_once(|| value += 1);
+    /// assert_eq!(value, 1);
+    /// ```
+    pub fn call_once<F: FnOnce()>(&self, f: F) {
+        if self.is_completed() {
+            return;
+        }
+
+        let mut backoff = Backoff::new();
+        loop {
+            match self.state.compare_exchange(
+                INCOMPLETE,
+                RUNNING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    // Resets the state if the initializer panics so that the
+                    // other threads do not spin forever.
+                    let reset = ResetOnPanic { state: &self.state };
+                    f();
+                    mem::forget(reset);
+
+                    store_release!(self.state, COMPLETE);
+                    return;
+                }
+                Err(COMPLETE) => return,
+                Err(_) => {
+                    while self.state.load(Ordering::Relaxed) == RUNNING {
+                        backoff.spin();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns whether the initialization has completed.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Once;
+    ///
+    /// let once = Once::new();
+    /// assert!(!once.is_completed());
+    ///
+    /// once.call_once(|| {});
+    /// assert!(once.is_completed());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn is_completed(&self) -> bool {
+        self.state.load(Ordering::Acquire) == COMPLETE
+    }
+}
+
+impl Default for Once {
+    /// Creates a new `Once` whose initialization has not run yet.
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sets the state of a `Once` back to incomplete when dropped.
+///
+/// It is forgotten once the initializer returned so that it is only dropped
+/// when the initializer panics.
+struct ResetOnPanic<'once> {
+    state: &'once AtomicU8,
+}
+
+impl Drop for ResetOnPanic<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        store_release!(self.state, INCOMPLETE);
+    }
+}
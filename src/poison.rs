@@ -0,0 +1,93 @@
+//! Lock poisoning, enabled by the `poison` feature.
+//!
+//! A lock is poisoned when a thread panics while holding it, the next threads
+//! acquiring it are then notified that the data may be in an inconsistent state.
+
+use core::fmt;
+use std::thread;
+
+use crate::sync::{AtomicBool, Ordering};
+
+/// A type alias for the result of a lock method which can be poisoned.
+pub type LockResult<G> = Result<G, PoisonError<G>>;
+
+/// An error returned when acquiring a poisoned lock.
+///
+/// The guard is still returned within the error so that the data can be
+/// accessed anyway.
+pub struct PoisonError<G> {
+    guard: G,
+}
+
+impl<G> PoisonError<G> {
+    /// Creates a `PoisonError<G>` containing the given guard.
+    #[inline]
+    #[must_use]
+    pub const fn new(guard: G) -> Self {
+        Self { guard }
+    }
+
+    /// Consumes this error, returning the guard of the poisoned lock.
+    #[inline]
+    pub fn into_inner(self) -> G {
+        self.guard
+    }
+
+    /// Returns a reference to the guard of the poisoned lock.
+    #[inline]
+    #[must_use]
+    pub const fn get_ref(&self) -> &G {
+        &self.guard
+    }
+
+    /// Returns a mutable reference to the guard of the poisoned lock.
+    #[inline]
+    pub const fn get_mut(&mut self) -> &mut G {
+        &mut self.guard
+    }
+}
+
+impl<G> fmt::Debug for PoisonError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoisonError").finish_non_exhaustive()
+    }
+}
+
+impl<G> fmt::Display for PoisonError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("poisoned lock: another thread panicked while holding it")
+    }
+}
+
+impl<G> std::error::Error for PoisonError<G> {}
+
+/// Poisons a lock if the thread panics while it is held.
+///
+/// This structure is held by the guards of the lock.
+#[derive(Debug, Clone, Copy)]
+pub struct PoisonOnPanic<'lock> {
+    // Poison flag of the lock.
+    poisoned: &'lock AtomicBool,
+    // Was the thread already panicking when the lock was acquired.
+    panicking: bool,
+}
+
+impl<'lock> PoisonOnPanic<'lock> {
+    /// Creates a `PoisonOnPanic<'lock>` for the given poison flag.
+    #[inline]
+    #[must_use]
+    pub fn new(poisoned: &'lock AtomicBool) -> Self {
+        Self {
+            poisoned,
+            panicking: thread::panicking(),
+        }
+    }
+
+    /// Poisons the lock if the thread started panicking since the lock was acquired.
+    #[inline]
+    pub fn release(&self) {
+        if !self.panicking && thread::panicking() {
+            self.poisoned.store(true, Ordering::Relaxed);
+        }
+    }
+}
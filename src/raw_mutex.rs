@@ -0,0 +1,234 @@
+use core::marker::PhantomData;
+
+use crate::backoff::Backoff;
+use crate::relax::{Relax, Spin};
+#[cfg(feature = "stats")]
+use crate::sync::AtomicUsize;
+use crate::sync::{AtomicBool, Ordering};
+
+/// A mutual exclusion lock which does not own the data it protects.
+///
+/// This is the locking mechanism of [`Mutex`](crate::Mutex) without the data
+/// wrapper, it is useful to protect data stored elsewhere. Since no guard is
+/// returned the lock must be released explicitly with
+/// [`unlock`](self::RawMutex::unlock).
+///
+/// The way the CPU is relaxed while spinning is given by the [`Relax`]
+/// strategy `R`, which defaults to [`Spin`].
+///
+/// # Examples
+/// ```
+/// use spinlock::RawMutex;
+///
+/// static LOCK: RawMutex = RawMutex::new();
+///
+/// LOCK.lock();
+/// // The data protected by the lock can be accessed here.
+/// // SAFETY: The lock is held by this thread.
+/// unsafe { LOCK.unlock() };
+/// ```
+#[derive(Debug)]
+pub struct RawMutex<R: Relax = Spin> {
+    // Is the lock held by a thread.
+    pub(crate) lock: AtomicBool,
+    // Number of times the lock was found held when trying to acquire it.
+    #[cfg(feature = "stats")]
+    pub(crate) contentions: AtomicUsize,
+    // Number of single spins before backing off while waiting for the lock.
+    spin_budget: u32,
+    _relax: PhantomData<fn() -> R>,
+}
+
+impl RawMutex {
+    const_fn! {
+        /// Creates a new `RawMutex` which is unlocked.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::RawMutex;
+        ///
+        /// let raw = RawMutex::new();
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn new() -> Self {
+            Self::with_relax()
+        }
+    }
+}
+
+impl<R: Relax> RawMutex<R> {
+    const_fn! {
+        /// Creates a new `RawMutex<R>` which is unlocked, relaxing the CPU with `R`
+        /// while spinning.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::{RawMutex, Spin};
+        ///
+        /// let raw = RawMutex::<Spin>::with_relax();
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn with_relax() -> Self {
+            Self::with_spin_budget(0)
+        }
+    }
+
+    const_fn! {
+        /// Creates a new `RawMutex<R>` which is unlocked, checking the lock after
+        /// each single spin the first `budget` times it waits for the lock before
+        /// backing off exponentially.
+        ///
+        /// A large budget lowers the latency of rarely contended locks, while
+        /// a budget of 0 makes heavily contended locks back off immediately.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::{RawMutex, Spin};
+        ///
+        /// let raw = RawMutex::<Spin>::with_spin_budget(64);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn with_spin_budget(budget: u32) -> Self {
+            Self {
+                lock: AtomicBool::new(false),
+                #[cfg(feature = "stats")]
+                contentions: AtomicUsize::new(0),
+                spin_budget: budget,
+                _relax: PhantomData,
+            }
+        }
+    }
+
+    /// Acquires the lock, blocking the current thread until the lock is available.
+    ///
+    /// This function blocks the current thread by spinning until the lock is
+    /// released.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RawMutex;
+    ///
+    /// let raw = RawMutex::new();
+    ///
+    /// raw.lock();
+    /// assert!(raw.is_locked());
+    /// ```
+    #[inline]
+    pub fn lock(&self) {
+        // The uncontended path is a single swap, kept small so that it is
+        // inlined in the callers while the spinning stays out of line. Unlike
+        // a compare and swap it can't fail spuriously nor needs a weak variant.
+        // Memory order acquire is used to make sure no reordering happens after it.
+        if self.lock.swap(true, Ordering::Acquire) {
+            self.lock_contended();
+        }
+    }
+
+    /// Spins until the lock is acquired, once it was found held by
+    /// [`lock`](self::RawMutex::lock).
+    #[cold]
+    #[inline(never)]
+    fn lock_contended(&self) {
+        // Each contended call to `lock` counts once however long it spins.
+        #[cfg(feature = "stats")]
+        self.contentions.fetch_add(1, Ordering::Relaxed);
+
+        // To reduce the cache coherency traffic we spin on an atomic load which does
+        // not requires write access to the cache line (as opposed to compare_and_swap).
+        let mut backoff = Backoff::with_budget(self.spin_budget);
+        loop {
+            while self.lock.load(Ordering::Relaxed) {
+                backoff.spin_with::<R>();
+            }
+
+            if !self.lock.swap(true, Ordering::Acquire) {
+                return;
+            }
+        }
+    }
+
+    /// Tries to acquire the lock, returns whether it was acquired.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RawMutex;
+    ///
+    /// let raw = RawMutex::new();
+    ///
+    /// assert!(raw.try_lock());
+    /// assert!(!raw.try_lock());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn try_lock(&self) -> bool {
+        // A single attempt does not benefit from loading the lock first, as
+        // opposed to `lock` which spins on the load, so it is a single
+        // read-modify-write.
+        self.lock
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    /// Releases the lock.
+    ///
+    /// # Safety
+    /// The lock must be held by the caller, typically acquired with
+    /// [`lock`](self::RawMutex::lock) or [`try_lock`](self::RawMutex::try_lock).
+    /// Releasing the lock while the protected data is still accessed allows
+    /// other threads to access it concurrently.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RawMutex;
+    ///
+    /// let raw = RawMutex::new();
+    ///
+    /// raw.lock();
+    /// // SAFETY: The lock is held by this thread.
+    /// unsafe { raw.unlock() };
+    /// assert!(!raw.is_locked());
+    /// ```
+    #[allow(clippy::inline_always)]
+    #[inline(always)]
+    pub unsafe fn unlock(&self) {
+        // Memory order release is used to make sure no reordering happens before it.
+        store_release!(self.lock, false);
+        R::wake();
+    }
+
+    /// Returns whether the lock is currently held by a thread.
+    ///
+    /// The returned value is only a snapshot of the lock state which may
+    /// change right after the call, so it must not be used to make
+    /// synchronization decisions. It is meant for diagnostics such as
+    /// metrics or logging.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RawMutex;
+    ///
+    /// let raw = RawMutex::new();
+    /// assert!(!raw.is_locked());
+    ///
+    /// raw.lock();
+    /// assert!(raw.is_locked());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn is_locked(&self) -> bool {
+        self.lock.load(Ordering::Relaxed)
+    }
+}
+
+impl<R: Relax> Default for RawMutex<R> {
+    /// Creates a new `RawMutex<R>` which is unlocked.
+    #[inline]
+    fn default() -> Self {
+        Self::with_relax()
+    }
+}
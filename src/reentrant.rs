@@ -0,0 +1,463 @@
+use core::cell::Cell;
+use core::fmt;
+use core::marker::PhantomData;
+use core::num::NonZeroUsize;
+use core::ops::Deref;
+
+use crate::raw_mutex::RawMutex;
+use crate::sync::{AtomicUsize, Ordering, UnsafeCell};
+
+/// Provides an identifier for the current thread.
+///
+/// This is used by [`ReentrantMutex`] to know whether the thread trying to
+/// acquire the lock already holds it. It is implemented by [`StdThreadId`]
+/// with the `std` feature, `no_std` users can implement it on top of their
+/// own threading facility.
+///
+/// # Safety
+/// The returned identifier must be unique among the running threads, two
+/// threads alive at the same time must never get the same identifier.
+pub unsafe trait GetThreadId {
+    /// Returns the identifier of the current thread.
+    fn current() -> NonZeroUsize;
+}
+
+/// A [`GetThreadId`] implementation for the `std` threads.
+///
+/// The identifier is the address of a thread local variable, which is unique
+/// among the running threads.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct StdThreadId;
+
+// SAFETY: The thread local variables of the running threads are distinct and
+// alive, so their addresses are distinct.
+#[cfg(feature = "std")]
+unsafe impl GetThreadId for StdThreadId {
+    #[inline]
+    fn current() -> NonZeroUsize {
+        #[cfg(not(feature = "loom"))]
+        crate::sync::thread_local!(static KEY: u8 = const { 0 });
+        // The loom thread locals can't be initialized in const contexts.
+        #[cfg(feature = "loom")]
+        crate::sync::thread_local!(static KEY: u8 = 0);
+
+        KEY.with(|key| NonZeroUsize::new(core::ptr::from_ref(key) as usize))
+            .expect("a reference is never null")
+    }
+}
+
+/// A mutual exclusion lock which can be acquired multiple times by the same
+/// thread.
+///
+/// This is like [`Mutex`](crate::Mutex) except that the thread holding the
+/// lock can lock it again without deadlocking, which is useful for recursive
+/// call paths. The lock tracks the identifier of its owner, given by `G`, and
+/// a recursion count, it is released once every guard of the owner is dropped.
+///
+/// Since the owner may hold several guards at once, the guards only give
+/// shared access to the data. Use a [`Cell`](core::cell::Cell) or a
+/// [`RefCell`](core::cell::RefCell) to mutate it.
+///
+/// With the `std` feature `G` defaults to [`StdThreadId`], otherwise it must
+/// be given explicitly.
+///
+/// # Examples
+/// ```
+/// use spinlock::ReentrantMutex;
+/// use std::cell::Cell;
+///
+/// let mutex = ReentrantMutex::new(Cell::new(0));
+///
+/// let guard = mutex.lock();
+/// // Locking again from the same thread does not deadlock.
+/// let nested = mutex.lock();
+/// nested.set(1);
+/// drop(nested);
+/// assert_eq!(guard.get(), 1);
+/// ```
+#[cfg(feature = "std")]
+pub struct ReentrantMutex<T, G: GetThreadId = StdThreadId> {
+    // Inner data contained in the mutex.
+    data: UnsafeCell<T>,
+    // The lock protecting the data.
+    raw: RawMutex,
+    // Identifier of the thread holding the lock, 0 when unlocked.
+    owner: AtomicUsize,
+    // Number of guards held by the owner.
+    count: Cell<usize>,
+    _thread_id: PhantomData<fn() -> G>,
+}
+
+/// A mutual exclusion lock which can be acquired multiple times by the same
+/// thread.
+///
+/// This is like [`Mutex`](crate::Mutex) except that the thread holding the
+/// lock can lock it again without deadlocking, which is useful for recursive
+/// call paths. The lock tracks the identifier of its owner, given by `G`, and
+/// a recursion count, it is released once every guard of the owner is dropped.
+///
+/// Since the owner may hold several guards at once, the guards only give
+/// shared access to the data. Use a [`Cell`](core::cell::Cell) or a
+/// [`RefCell`](core::cell::RefCell) to mutate it.
+///
+/// With the `std` feature `G` defaults to [`StdThreadId`], otherwise it must
+/// be given explicitly.
+///
+/// # Examples
+/// ```
+/// use spinlock::ReentrantMutex;
+/// # use spinlock::GetThreadId;
+/// # use std::num::NonZeroUsize;
+/// # struct ThreadId;
+/// # unsafe impl GetThreadId for ThreadId {
+/// #     fn current() -> NonZeroUsize {
+/// #         thread_local!(static KEY: u8 = 0);
+/// #         KEY.with(|key| NonZeroUsize::new(key as *const u8 as usize).unwrap())
+/// #     }
+/// # }
+/// use std::cell::Cell;
+///
+/// let mutex = ReentrantMutex::<_, ThreadId>::with_thread_id(Cell::new(0));
+///
+/// let guard = mutex.lock();
+/// // Locking again from the same thread does not deadlock.
+/// let nested = mutex.lock();
+/// nested.set(1);
+/// drop(nested);
+/// assert_eq!(guard.get(), 1);
+/// ```
+#[cfg(not(feature = "std"))]
+pub struct ReentrantMutex<T, G: GetThreadId> {
+    // Inner data contained in the mutex.
+    data: UnsafeCell<T>,
+    // The lock protecting the data.
+    raw: RawMutex,
+    // Identifier of the thread holding the lock, 0 when unlocked.
+    owner: AtomicUsize,
+    // Number of guards held by the owner.
+    count: Cell<usize>,
+    _thread_id: PhantomData<fn() -> G>,
+}
+
+#[cfg(feature = "std")]
+impl<T> ReentrantMutex<T> {
+    const_fn! {
+        /// Creates a new `ReentrantMutex<T>` which is unlocked, identifying the
+        /// threads with [`StdThreadId`].
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::ReentrantMutex;
+        ///
+        /// let mutex = ReentrantMutex::new(1);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn new(data: T) -> Self {
+            Self::with_thread_id(data)
+        }
+    }
+}
+
+impl<T, G: GetThreadId> ReentrantMutex<T, G> {
+    const_fn! {
+        /// Creates a new `ReentrantMutex<T, G>` which is unlocked, identifying
+        /// the threads with `G`.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::{GetThreadId, ReentrantMutex};
+        /// use std::num::NonZeroUsize;
+        ///
+        /// struct ThreadId;
+        ///
+        /// // SAFETY: The thread local variables of the running threads have
+        /// // distinct addresses.
+        /// unsafe impl GetThreadId for ThreadId {
+        ///     fn current() -> NonZeroUsize {
+        ///         thread_local!(static KEY: u8 = 0);
+        ///         KEY.with(|key| NonZeroUsize::new(key as *const u8 as usize).unwrap())
+        ///     }
+        /// }
+        ///
+        /// let mutex = ReentrantMutex::<_, ThreadId>::with_thread_id(1);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn with_thread_id(data: T) -> Self {
+            Self {
+                data: UnsafeCell::new(data),
+                raw: RawMutex::new(),
+                owner: AtomicUsize::new(0),
+                count: Cell::new(0),
+                _thread_id: PhantomData,
+            }
+        }
+    }
+
+    /// Consumes this mutex, returning the underlying data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::ReentrantMutex;
+    /// # use spinlock::GetThreadId;
+    /// # use std::num::NonZeroUsize;
+    /// # struct ThreadId;
+    /// # unsafe impl GetThreadId for ThreadId {
+    /// #     fn current() -> NonZeroUsize {
+    /// #         thread_local!(static KEY: u8 = 0);
+    /// #         KEY.with(|key| NonZeroUsize::new(key as *const u8 as usize).unwrap())
+    /// #     }
+    /// # }
+    ///
+    /// let mutex = ReentrantMutex::<_, ThreadId>::with_thread_id(1);
+    /// assert_eq!(mutex.into_inner(), 1);
+    /// ```
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+
+    /// Acquires the lock, blocking the current thread until the lock is
+    /// available.
+    ///
+    /// If the current thread already holds the lock this function returns
+    /// immediately, the lock is released once all its guards are dropped.
+    ///
+    /// # Panics
+    /// Panics if the lock is acquired more than `usize::MAX` times.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::ReentrantMutex;
+    /// # use spinlock::GetThreadId;
+    /// # use std::num::NonZeroUsize;
+    /// # struct ThreadId;
+    /// # unsafe impl GetThreadId for ThreadId {
+    /// #     fn current() -> NonZeroUsize {
+    /// #         thread_local!(static KEY: u8 = 0);
+    /// #         KEY.with(|key| NonZeroUsize::new(key as *const u8 as usize).unwrap())
+    /// #     }
+    /// # }
+    ///
+    /// let mutex = ReentrantMutex::<_, ThreadId>::with_thread_id(1);
+    ///
+    /// let guard = mutex.lock();
+    /// let nested = mutex.lock();
+    /// assert_eq!(*guard + *nested, 2);
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn lock(&self) -> ReentrantMutexGuard<'_, T, G> {
+        let id = G::current().get();
+        if !self.relock(id) {
+            self.raw.lock();
+            self.acquired(id);
+        }
+
+        ReentrantMutexGuard { mutex: self }
+    }
+
+    /// Tries to acquire the lock. If the lock is held by another thread
+    /// returns `None`.
+    ///
+    /// This function does not block the current thread. If the current thread
+    /// already holds the lock it always succeeds.
+    ///
+    /// # Panics
+    /// Panics if the lock is acquired more than `usize::MAX` times.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::ReentrantMutex;
+    /// # use spinlock::GetThreadId;
+    /// # use std::num::NonZeroUsize;
+    /// # struct ThreadId;
+    /// # unsafe impl GetThreadId for ThreadId {
+    /// #     fn current() -> NonZeroUsize {
+    /// #         thread_local!(static KEY: u8 = 0);
+    /// #         KEY.with(|key| NonZeroUsize::new(key as *const u8 as usize).unwrap())
+    /// #     }
+    /// # }
+    ///
+    /// let mutex = ReentrantMutex::<_, ThreadId>::with_thread_id(1);
+    ///
+    /// let guard = mutex.try_lock().unwrap();
+    /// assert!(mutex.try_lock().is_some());
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_lock(&self) -> Option<ReentrantMutexGuard<'_, T, G>> {
+        let id = G::current().get();
+        if !self.relock(id) {
+            if !self.raw.try_lock() {
+                return None;
+            }
+            self.acquired(id);
+        }
+
+        Some(ReentrantMutexGuard { mutex: self })
+    }
+
+    /// Returns whether the lock is currently held by a thread.
+    ///
+    /// The returned value is only a snapshot of the lock state which may
+    /// change right after the call, so it must not be used to make
+    /// synchronization decisions.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::ReentrantMutex;
+    /// # use spinlock::GetThreadId;
+    /// # use std::num::NonZeroUsize;
+    /// # struct ThreadId;
+    /// # unsafe impl GetThreadId for ThreadId {
+    /// #     fn current() -> NonZeroUsize {
+    /// #         thread_local!(static KEY: u8 = 0);
+    /// #         KEY.with(|key| NonZeroUsize::new(key as *const u8 as usize).unwrap())
+    /// #     }
+    /// # }
+    ///
+    /// let mutex = ReentrantMutex::<_, ThreadId>::with_thread_id(1);
+    /// assert!(!mutex.is_locked());
+    ///
+    /// let guard = mutex.lock();
+    /// assert!(mutex.is_locked());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn is_locked(&self) -> bool {
+        self.raw.is_locked()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the mutex mutably, no actual locking needs to
+    /// take place.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::ReentrantMutex;
+    /// # use spinlock::GetThreadId;
+    /// # use std::num::NonZeroUsize;
+    /// # struct ThreadId;
+    /// # unsafe impl GetThreadId for ThreadId {
+    /// #     fn current() -> NonZeroUsize {
+    /// #         thread_local!(static KEY: u8 = 0);
+    /// #         KEY.with(|key| NonZeroUsize::new(key as *const u8 as usize).unwrap())
+    /// #     }
+    /// # }
+    ///
+    /// let mut mutex = ReentrantMutex::<_, ThreadId>::with_thread_id(1);
+    /// *mutex.get_mut() = 2;
+    /// assert_eq!(*mutex.lock(), 2);
+    /// ```
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        // SAFETY: The mutable borrow guarantees that no guard is alive.
+        self.data.with_mut(|data| unsafe { &mut *data })
+    }
+
+    // Increments the recursion count if the lock is held by the thread `id`,
+    // returns whether it was.
+    #[inline]
+    fn relock(&self, id: usize) -> bool {
+        // Only the thread `id` may have stored `id` as owner, so a relaxed load
+        // can't observe it unless this thread holds the lock.
+        if self.owner.load(Ordering::Relaxed) != id {
+            return false;
+        }
+
+        let count = self
+            .count
+            .get()
+            .checked_add(1)
+            .expect("lock count overflow in reentrant mutex");
+        self.count.set(count);
+
+        true
+    }
+
+    // Records the thread `id` as owner once it acquired the raw lock.
+    #[inline]
+    fn acquired(&self, id: usize) {
+        self.owner.store(id, Ordering::Relaxed);
+        self.count.set(1);
+    }
+}
+
+impl<T: Default, G: GetThreadId> Default for ReentrantMutex<T, G> {
+    /// Creates a `ReentrantMutex<T, G>` which is unlocked containing the default of `T`.
+    #[inline]
+    fn default() -> Self {
+        Self::with_thread_id(T::default())
+    }
+}
+
+impl<T: fmt::Debug, G: GetThreadId> fmt::Debug for ReentrantMutex<T, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("ReentrantMutex");
+        match self.try_lock() {
+            Some(guard) => d.field("data", &&*guard),
+            None => d.field("data", &format_args!("<locked>")),
+        };
+        d.finish_non_exhaustive()
+    }
+}
+
+// SAFETY: The owner and count are only accessed by the thread holding the
+// lock, and the data may be shared between threads since it is only accessed
+// by the owner.
+unsafe impl<T: Send, G: GetThreadId> Sync for ReentrantMutex<T, G> {}
+
+// SAFETY: Moving the mutex moves the data.
+unsafe impl<T: Send, G: GetThreadId> Send for ReentrantMutex<T, G> {}
+
+/// Guard structure used to release the lock when dropped.
+///
+/// The lock is released once every guard held by the owner is dropped.
+///
+/// This structure is created by [`lock`](self::ReentrantMutex::lock) and
+/// [`try_lock`](self::ReentrantMutex::try_lock) on
+/// [`ReentrantMutex`](self::ReentrantMutex).
+#[must_use = "if unused the lock is immediately released"]
+pub struct ReentrantMutexGuard<'mutex, T, G: GetThreadId> {
+    mutex: &'mutex ReentrantMutex<T, G>,
+}
+
+impl<T, G: GetThreadId> Deref for ReentrantMutexGuard<'_, T, G> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The guards only give shared access to the data, and only the
+        // thread holding the lock can hold them.
+        self.mutex.data.with(|data| unsafe { &*data })
+    }
+}
+
+impl<T: fmt::Debug, G: GetThreadId> fmt::Debug for ReentrantMutexGuard<'_, T, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T, G: GetThreadId> Drop for ReentrantMutexGuard<'_, T, G> {
+    #[inline]
+    fn drop(&mut self) {
+        let count = self.mutex.count.get() - 1;
+        self.mutex.count.set(count);
+
+        if count == 0 {
+            self.mutex.owner.store(0, Ordering::Relaxed);
+            // SAFETY: The last guard of the owner is dropped.
+            unsafe { self.mutex.raw.unlock() };
+        }
+    }
+}
+
+// Prevents the guard from being moved to an other thread, the lock is owned by
+// the thread which acquired it.
+impl<T, G: GetThreadId> !Send for ReentrantMutexGuard<'_, T, G> {}
+
+// SAFETY: The guard only gives shared access to the data.
+unsafe impl<T: Sync, G: GetThreadId> Sync for ReentrantMutexGuard<'_, T, G> {}
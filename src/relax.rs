@@ -0,0 +1,197 @@
+use crate::sync::hint;
+#[cfg(feature = "std")]
+use crate::sync::yield_now;
+
+/// A strategy to relax the CPU while spinning on a lock.
+///
+/// The locks are generic over it so that the spinning can be tuned to the
+/// environment, for instance by yielding to the scheduler when one exists.
+///
+/// It can be implemented to take control of the spinning, for instance to
+/// yield to a custom executor in a deterministic simulation or to count the
+/// spin iterations in a test. Each type implementing it is its own strategy,
+/// so the locks relaxing with one don't interfere with the others.
+///
+/// # Examples
+/// ```
+/// use spinlock::{Mutex, Relax};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// static SPINS: AtomicUsize = AtomicUsize::new(0);
+///
+/// struct CountSpins;
+///
+/// impl Relax for CountSpins {
+///     fn relax() {
+///         SPINS.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let mutex = Mutex::<_, CountSpins>::with_relax(1);
+/// let guard = mutex.lock();
+/// assert!(mutex.try_lock_spin(3).is_none());
+/// assert_eq!(SPINS.load(Ordering::Relaxed), 3);
+/// ```
+pub trait Relax {
+    /// Performs a single iteration of the spinning.
+    fn relax();
+
+    /// Wakes up the threads relaxing with this strategy, it is called by the
+    /// locks each time they are released.
+    ///
+    /// Does nothing by default.
+    #[inline]
+    fn wake() {}
+}
+
+/// Relaxes the CPU by hinting that it is in a busy-wait spin loop.
+///
+/// This is the default strategy of the locks, it is `no_std`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Spin;
+
+impl Relax for Spin {
+    #[inline]
+    fn relax() {
+        // Hints the CPU that we are in a busy-wait spin loop, so the CPU can
+        // optimize its behavior.
+        hint::spin_loop();
+    }
+}
+
+// Number of bursts of spin loop hints in a relaxation of `PoliteSpin`.
+const POLITE_BURSTS: u32 = 4;
+// Number of spin loop hints in a burst of `PoliteSpin`.
+const POLITE_BURST_LEN: u32 = 4;
+
+/// Relaxes the CPU with bursts of busy-wait hints separated by pauses of
+/// increasing length.
+///
+/// On a core with simultaneous multithreading (SMT, also known as
+/// hyperthreading) the hardware threads share the pipeline, so a thread
+/// spinning with [`Spin`] still takes execution resources from its sibling,
+/// which may well be the one holding the lock. Each relaxation of this
+/// strategy issues a few spin loop hints then pauses on a short loop of
+/// independent instructions, doubling the pause after each burst. The spinning
+/// thread thus issues fewer hints and leaves more of the core to its sibling,
+/// which should improve the throughput of the sibling at the cost of a
+/// slightly longer wake up of the spinning thread.
+///
+/// It is `no_std` and does not need any support from the architecture.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PoliteSpin;
+
+impl Relax for PoliteSpin {
+    #[inline]
+    fn relax() {
+        for burst in 0..POLITE_BURSTS {
+            for _ in 0..POLITE_BURST_LEN {
+                hint::spin_loop();
+            }
+
+            // The black box prevents the pause from being optimized out.
+            for i in 0..1_u32 << burst {
+                core::hint::black_box(i);
+            }
+        }
+    }
+}
+
+/// Relaxes by yielding the current thread to the scheduler of the operating
+/// system.
+///
+/// This is better suited than [`Spin`] when the threads outnumber the cores,
+/// so that the thread holding the lock gets scheduled sooner.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Yield;
+
+#[cfg(feature = "std")]
+impl Relax for Yield {
+    #[inline]
+    fn relax() {
+        yield_now();
+    }
+}
+
+// Number of spin loop hints of `Contention<false>` before yielding.
+#[cfg(feature = "std")]
+const CONTENTION_SPINS: u32 = 16;
+
+/// Relaxes according to the contention expected on the lock, chosen at compile
+/// time by `SPIN_ONLY`.
+///
+/// `Contention<true>`, the default, expects little contention and only spins
+/// like [`Spin`]. `Contention<false>` expects heavy contention: each
+/// relaxation spins for a short budget then yields the current thread to the
+/// scheduler like `Yield`. The strategy is part of the type of the lock so
+/// it is picked without any branch at runtime.
+///
+/// The yielding variant requires the `std` feature, `Contention<false>` does
+/// not implement [`Relax`] without it.
+///
+/// # Examples
+/// ```
+/// use spinlock::{Contention, Mutex};
+///
+/// // Rarely contended, spinning is cheaper than a system call.
+/// let stats = Mutex::<_, Contention<true>>::with_relax(0);
+/// stats.with_lock(|count| *count += 1);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Contention<const SPIN_ONLY: bool = true>;
+
+impl Relax for Contention<true> {
+    #[inline]
+    fn relax() {
+        Spin::relax();
+    }
+}
+
+#[cfg(feature = "std")]
+impl Relax for Contention<false> {
+    #[inline]
+    fn relax() {
+        for _ in 0..CONTENTION_SPINS {
+            hint::spin_loop();
+        }
+        yield_now();
+    }
+}
+
+/// Relaxes the CPU by putting the core to sleep until an event is signaled.
+///
+/// On ARM the spinning core waits with the `wfe` (wait for event)
+/// instruction, it stays in a low-power state instead of busy-spinning until
+/// the lock is released and another core signals it with `sev` (send event),
+/// or until any other event such as an interrupt. This saves power when the
+/// locks are held for a while, at the cost of a slightly longer wake up.
+///
+/// On the other architectures it falls back to the [`Spin`] strategy.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WaitForEvent;
+
+impl Relax for WaitForEvent {
+    #[inline]
+    fn relax() {
+        #[cfg(any(target_arch = "aarch64", target_arch = "arm"))]
+        // SAFETY: Waiting for an event has no other effect than suspending the
+        // core until the next event.
+        unsafe {
+            core::arch::asm!("wfe", options(nomem, nostack, preserves_flags));
+        }
+
+        #[cfg(not(any(target_arch = "aarch64", target_arch = "arm")))]
+        Spin::relax();
+    }
+
+    #[inline]
+    fn wake() {
+        #[cfg(any(target_arch = "aarch64", target_arch = "arm"))]
+        // SAFETY: Sending an event has no other effect than waking up the
+        // cores waiting for one.
+        unsafe {
+            core::arch::asm!("sev", options(nomem, nostack, preserves_flags));
+        }
+    }
+}
@@ -1,49 +1,333 @@
-use core::cell::UnsafeCell;
-use core::hint;
+use core::borrow::{Borrow, BorrowMut};
+use core::cmp;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut};
-use core::sync::atomic::{AtomicIsize, Ordering};
+use core::{mem, ptr};
+
+use crate::backoff::Backoff;
+use crate::relax::{Relax, Spin};
+#[cfg(feature = "stats")]
+use crate::stats::LockStats;
+use crate::sync::{fence, AtomicUsize, Ordering, UnsafeCell};
+use crate::would_block::WouldBlock;
 
 /// A reader-writer lock.
 ///
 /// This primitive allows multiple readers or one unique writer.
-#[derive(Debug)]
-pub struct RwLock<T> {
-    // Inner data contained in the RwLock.
+///
+/// At most [`max_readers`](self::RwLock::max_readers) shared read accesses can
+/// be held at the same time, [`read`](self::RwLock::read) then waits for some
+/// of them to be released as if the lock was contended.
+///
+/// By default readers are preferred, a writer acquiring with
+/// [`write_biased`](self::RwLock::write_biased) instead prevents new readers
+/// from acquiring the lock while it waits. A rwlock created with the
+/// [`Policy::WriterPreferring`] policy of
+/// [`with_policy`](self::RwLock::with_policy) makes every writer do so.
+///
+/// The way the CPU is relaxed while spinning is given by the [`Relax`]
+/// strategy `R`, which defaults to [`Spin`].
+///
+/// The data may be unsized, for instance a `&RwLock<[T; N]>` coerces to a
+/// `&RwLock<[T]>`.
+///
+/// A `RwLock<T>` is `Send` when `T` is `Send`, and `Sync` when `T` is both
+/// `Send` and `Sync`: the readers share the data between threads, and a
+/// writer may move it out of the rwlock. A rwlock of a value which can't be
+/// sent to another thread can't be either:
+/// ```compile_fail
+/// use spinlock::RwLock;
+/// use std::rc::Rc;
+///
+/// fn assert_send<T: Send>() {}
+///
+/// assert_send::<RwLock<Rc<i32>>>();
+/// ```
+pub struct RwLock<T: ?Sized, R: Relax = Spin> {
+    // The lock, a bitfield of the accesses held:
+    // - the `WRITER` bit is set when the exclusive write access is held,
+    //   the other bits are then cleared
+    // - the `UPGRADEABLE` bit is set when the upgradeable read access is held
+    // - the remaining bits count the shared read accesses
+    //
+    // lock == 0 => no access held
+    lock: AtomicUsize,
+
+    // Number of writers waiting through `write_biased`, new readers then wait
+    // for all of them.
+    writers_waiting: AtomicUsize,
+
+    // Do the writers acquire the lock through `write_biased`.
+    policy: Policy,
+
+    // Number of single spins before backing off while waiting for the lock.
+    spin_budget: u32,
+
+    // Sequence number of the writes, odd while the exclusive write access is
+    // held and incremented again to an even number when it is released. An
+    // optimistic read validates only if it stays at the same even number.
+    version: AtomicUsize,
+
+    // Number of spin iterations of the readers and the writers waiting for the lock.
+    #[cfg(feature = "stats")]
+    read_spins: AtomicUsize,
+    #[cfg(feature = "stats")]
+    write_spins: AtomicUsize,
+
+    _relax: PhantomData<fn() -> R>,
+
+    // Inner data contained in the RwLock, last so that it can be unsized.
     data: UnsafeCell<T>,
+}
 
-    // The lock
-    // lock > 0 => number of shared read access held
-    // lock == 0 => no access held
-    // lock == -1 => exclusive write access is held
-    //
-    // Note: This is not optimized we are only using -1, 0, and positive values
-    // It could be improved by using a bit to represent exclusive write access
-    lock: AtomicIsize,
+/// Priority between the readers and the writers of a [`RwLock`].
+///
+/// It is chosen when creating the rwlock with
+/// [`with_policy`](self::RwLock::with_policy).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Policy {
+    /// New readers acquire the lock while a writer waits for the readers to
+    /// release it, so a continuous stream of readers can starve the writers.
+    ///
+    /// Only the writers acquiring with
+    /// [`write_biased`](self::RwLock::write_biased) make the readers wait.
+    #[default]
+    ReaderPreferring,
+    /// Every writer acquires like [`write_biased`](self::RwLock::write_biased):
+    /// new readers wait while a writer is waiting, so a continuous stream of
+    /// writers can starve the readers.
+    ///
+    /// A thread already holding a shared read access that calls
+    /// [`read`](self::RwLock::read) again deadlocks while a writer is
+    /// waiting, [`read_recursive`](self::RwLock::read_recursive) must be used
+    /// instead.
+    WriterPreferring,
 }
 
+// Bit of the lock set when the exclusive write access is held.
+const WRITER: usize = 1 << (usize::BITS - 1);
+// Bit of the lock set when the upgradeable read access is held.
+const UPGRADEABLE: usize = 1 << (usize::BITS - 2);
+// Increment of the lock for a shared read access.
+const READER: usize = 1;
+// Maximum number of shared read accesses that can be held at the same time,
+// the reader count must not overflow into the `UPGRADEABLE` bit. It is also
+// the mask of the reader count.
+const MAX_READERS: usize = UPGRADEABLE - 1;
+
 impl<T> RwLock<T> {
-    /// Creates a new `RwLock<T>` which is unlocked.
+    const_fn! {
+        /// Creates a new `RwLock<T>` which is unlocked.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::RwLock;
+        ///
+        /// let lock = RwLock::new(1);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn new(data: T) -> Self {
+            Self::with_relax(data)
+        }
+    }
+
+    const_fn! {
+        /// Creates a new `RwLock<T>` which is already locked with exclusive
+        /// write access, without any guard.
+        ///
+        /// This is useful to hand off a value which is still being set up:
+        /// the readers and writers block until the producer releases the
+        /// write access with [`force_write_unlock`](self::RwLock::force_write_unlock).
+        /// The rwlock stays locked forever if it is never called.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::RwLock;
+        ///
+        /// static CONFIG: RwLock<u32> = RwLock::new_locked(0);
+        ///
+        /// assert!(CONFIG.try_read().is_none());
+        ///
+        /// // SAFETY: The write access is held by no guard, it was created
+        /// // locked.
+        /// unsafe {
+        ///     *CONFIG.data_ptr() = 42;
+        ///     CONFIG.force_write_unlock();
+        /// }
+        /// assert_eq!(*CONFIG.read(), 42);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn new_locked(data: T) -> Self {
+            let mut rwlock = Self::with_relax(data);
+            rwlock.lock = AtomicUsize::new(WRITER);
+            // The write access is held, the version is odd until it is released.
+            rwlock.version = AtomicUsize::new(1);
+            rwlock
+        }
+    }
+}
+
+impl<T, R: Relax> RwLock<T, R> {
+    const_fn! {
+        /// Creates a new `RwLock<T, R>` which is unlocked, relaxing the CPU with `R`
+        /// while spinning.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::{RwLock, Spin};
+        ///
+        /// let lock = RwLock::<_, Spin>::with_relax(1);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn with_relax(data: T) -> Self {
+            Self::with_spin_budget(data, 0)
+        }
+    }
+
+    const_fn! {
+        /// Creates a new `RwLock<T, R>` which is unlocked, checking the lock after
+        /// each single spin the first `budget` times it waits for the lock before
+        /// backing off exponentially.
+        ///
+        /// A large budget lowers the latency of rarely contended locks, while
+        /// a budget of 0, the default of [`new`](self::RwLock::new), makes
+        /// heavily contended locks back off immediately.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::RwLock;
+        ///
+        /// static CONFIG: RwLock<u32> = RwLock::with_spin_budget(0, 64);
+        ///
+        /// *CONFIG.write() = 42;
+        /// assert_eq!(*CONFIG.read(), 42);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn with_spin_budget(data: T, budget: u32) -> Self {
+            Self {
+                lock: AtomicUsize::new(0),
+                writers_waiting: AtomicUsize::new(0),
+                policy: Policy::ReaderPreferring,
+                spin_budget: budget,
+                version: AtomicUsize::new(0),
+                #[cfg(feature = "stats")]
+                read_spins: AtomicUsize::new(0),
+                #[cfg(feature = "stats")]
+                write_spins: AtomicUsize::new(0),
+                _relax: PhantomData,
+                data: UnsafeCell::new(data),
+            }
+        }
+    }
+
+    const_fn! {
+        /// Creates a new `RwLock<T, R>` which is unlocked, giving the priority to
+        /// the readers or to the writers according to `policy`.
+        ///
+        /// The rwlocks created by the other constructors are
+        /// [`ReaderPreferring`](self::Policy::ReaderPreferring).
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::{Policy, RwLock};
+        ///
+        /// let rwlock: RwLock<_> = RwLock::with_policy(1, Policy::WriterPreferring);
+        ///
+        /// *rwlock.write() = 2;
+        /// assert_eq!(*rwlock.read(), 2);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn with_policy(data: T, policy: Policy) -> Self {
+            let mut rwlock = Self::with_relax(data);
+            rwlock.policy = policy;
+            rwlock
+        }
+    }
+
+    /// Replaces the data with the value computed by the closure from the
+    /// current one.
+    ///
+    /// Since this call borrows the rwlock mutably, no other reference to it
+    /// can exist so no locking is needed. The data is moved out, passed to the
+    /// closure, and the returned value is moved back in without requiring
+    /// `T: Default`.
+    ///
+    /// # Panics
+    /// If the closure panics the process is aborted: the data was moved into
+    /// the closure so unwinding would drop it a second time along with the
+    /// rwlock.
     ///
     /// # Examples
     /// ```
     /// use spinlock::RwLock;
     ///
-    /// let lock = RwLock::new(1);
+    /// let mut rwlock = RwLock::new(String::from("config"));
+    ///
+    /// rwlock.map_in_place(|config| config + ".toml");
+    /// assert_eq!(*rwlock.read(), "config.toml");
     /// ```
-    #[inline]
-    #[must_use]
-    pub const fn new(data: T) -> Self {
-        Self {
-            data: UnsafeCell::new(data),
-            lock: AtomicIsize::new(0),
+    pub fn map_in_place<F>(&mut self, f: F)
+    where
+        F: FnOnce(T) -> T,
+    {
+        // Panics again while unwinding from a panic of the closure, which
+        // aborts the process.
+        struct Abort;
+
+        impl Drop for Abort {
+            fn drop(&mut self) {
+                panic!("the closure of map_in_place panicked");
+            }
         }
+
+        let data = self.get_mut();
+        let abort = Abort;
+        // SAFETY: The data is borrowed mutably so nothing else can observe it
+        // while it is moved out. It is overwritten by the value returned by the
+        // closure, and if the closure panics the process aborts before the
+        // moved out data can be dropped again.
+        unsafe { ptr::write(data, f(ptr::read(data))) };
+        mem::forget(abort);
+    }
+}
+
+impl<T: ?Sized, R: Relax> RwLock<T, R> {
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the rwlock mutably, no other reference to it
+    /// can exist so no locking is needed.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let mut rwlock = RwLock::new(1);
+    /// *rwlock.get_mut() = 42;
+    /// assert_eq!(*rwlock.read(), 42);
+    /// ```
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        // SAFETY: The rwlock is borrowed mutably so no guard can be held
+        // and it is safe to get a mutable reference to the data.
+        self.data.with_mut(|data| unsafe { &mut *data })
     }
 
     /// Acquires the rwlock with shared read access,
     /// blocking the thread until it is available.
     ///
     /// This function blocks the current thread by spinning
-    /// if write access is held until it is released.
+    /// if write access is held until it is released. It also waits while a
+    /// writer is waiting in [`write_biased`](self::RwLock::write_biased).
+    ///
+    /// It also waits while the maximum number of shared read accesses, given
+    /// by [`max_readers`](self::RwLock::max_readers), is held.
     ///
     /// ```
     /// use spinlock::RwLock;
@@ -57,32 +341,108 @@ impl<T> RwLock<T> {
     ///     assert_eq!(*r.read(), 1);
     /// }).join();
     /// ```
-    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn read(&self) -> RwLockReadGuard<'_, T, R> {
+        self.acquire_read(false)
+    }
+
+    /// Acquires the rwlock with shared read access, blocking the thread until it
+    /// is available, even if the current thread already holds a shared read
+    /// access.
+    ///
+    /// Unlike [`read`](self::RwLock::read) it does not wait for the writers
+    /// waiting in [`write_biased`](self::RwLock::write_biased), so it can't
+    /// deadlock when called while holding a read guard on the same thread. It
+    /// only blocks while the exclusive write access is held.
+    ///
+    /// The tradeoff is that a continuous stream of recursive readers can starve
+    /// the writers, including the biased ones.
+    ///
+    /// It also waits while the maximum number of shared read accesses, given
+    /// by [`max_readers`](self::RwLock::max_readers), is held.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let r1 = rwlock.read_recursive();
+    /// let r2 = rwlock.read_recursive();
+    /// assert_eq!(*r1 + *r2, 2);
+    /// ```
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn read_recursive(&self) -> RwLockReadGuard<'_, T, R> {
+        self.acquire_read(true)
+    }
+
+    /// Acquires a shared read access, waiting for the biased writers unless it
+    /// is `recursive`.
+    ///
+    /// The reader count is optimistically incremented with a single
+    /// `fetch_add`, which unlike a compare and swap loop can't fail because of
+    /// the other readers. If the exclusive write access turns out to be held
+    /// the increment is rolled back and the reader spins until it is released.
+    ///
+    /// Near the maximum number of shared read accesses the count is
+    /// incremented with a compare and swap instead, like in `try_read`, so
+    /// that it never goes past the maximum, not even transiently: the
+    /// `fetch_add` is only used while the count is below half of the maximum,
+    /// far more than the number of threads which could increment it at once.
+    ///
+    /// The writers only acquire the lock with a compare and swap from 0, so a
+    /// transient increment in flight makes them fail and retry: a writer can
+    /// never acquire the lock between the increment and its rollback. While
+    /// the exclusive write access is held the reader count may thus be
+    /// non-zero, so the write accesses are released by clearing their bit
+    /// with a read-modify-write, never by storing a value which would erase
+    /// the transient increments.
+    fn acquire_read(&self, recursive: bool) -> RwLockReadGuard<'_, T, R> {
+        let mut backoff = Backoff::with_budget(self.spin_budget);
         loop {
-            // Gets the current valid lock value ie not
-            // exclusive write access held.
-            let lock = loop {
+            if recursive || self.writers_waiting.load(Ordering::Relaxed) == 0 {
                 let lock = self.lock.load(Ordering::Relaxed);
-                if lock >= 0 {
-                    break lock;
-                }
+                if lock & MAX_READERS < MAX_READERS / 2 {
+                    if self.lock.fetch_add(READER, Ordering::Acquire) & WRITER == 0 {
+                        return RwLockReadGuard::new(self);
+                    }
 
-                hint::spin_loop();
-            };
+                    // The exclusive write access is held, rolls back the increment.
+                    self.lock.fetch_sub(READER, Ordering::Relaxed);
+                } else if lock & WRITER == 0
+                    && lock & MAX_READERS != MAX_READERS
+                    && self
+                        .lock
+                        .compare_exchange(lock, lock + READER, Ordering::Acquire, Ordering::Relaxed)
+                        .is_ok()
+                {
+                    return RwLockReadGuard::new(self);
+                }
+            }
 
-            if self
-                .lock
-                .compare_exchange(lock, lock + 1, Ordering::Acquire, Ordering::Relaxed)
-                .is_ok()
+            // Waits for the exclusive write access to be released, for a reader
+            // to leave if the count is full, and for the biased writers unless
+            // recursive, before incrementing again.
+            while self.lock.load(Ordering::Relaxed) & WRITER != 0
+                || self.lock.load(Ordering::Relaxed) & MAX_READERS == MAX_READERS
+                || (!recursive && self.writers_waiting.load(Ordering::Relaxed) != 0)
             {
-                return RwLockReadGuard::new(self);
+                #[cfg(feature = "stats")]
+                self.read_spins.fetch_add(1, Ordering::Relaxed);
+                backoff.spin_with::<R>();
             }
         }
     }
 
     /// Tries to acquire the rwlock with shared read access. If the lock is not available returns `None`.
     ///
-    /// This function does not block the current thread.
+    /// This function does not block the current thread. It also returns `None`
+    /// if the maximum number of shared read accesses is already held. It does
+    /// not fail because of the other readers nor the writers waiting in
+    /// [`write_biased`](self::RwLock::write_biased), see
+    /// [`try_read_recursive`](self::RwLock::try_read_recursive) to make the
+    /// nesting explicit.
     ///
     /// # Examples
     /// ```
@@ -92,12 +452,100 @@ impl<T> RwLock<T> {
     ///
     /// assert_eq!(*rwlock.try_read().unwrap(), 1);
     /// ```
-    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
-        if self.lock.fetch_add(1, Ordering::Acquire) >= 0 {
-            Some(RwLockReadGuard::new(self))
-        } else {
-            self.lock.fetch_sub(1, Ordering::Release);
-            None
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T, R>> {
+        // Only increments the reader count when no exclusive write access is
+        // held so that the write state is never perturbed.
+        let mut lock = self.lock.load(Ordering::Relaxed);
+        while lock & WRITER == 0 && lock & MAX_READERS != MAX_READERS {
+            match self.lock.compare_exchange(
+                lock,
+                lock + READER,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(RwLockReadGuard::new(self)),
+                Err(current) => lock = current,
+            }
+        }
+
+        None
+    }
+
+    /// Tries to acquire the rwlock with shared read access, even if the current
+    /// thread already holds a shared read access. If the lock is not available
+    /// returns `None`.
+    ///
+    /// This is the non-blocking counterpart of
+    /// [`read_recursive`](self::RwLock::read_recursive): it succeeds as long as
+    /// only readers hold the lock, including the current thread, and fails when
+    /// the exclusive write access is held. The other readers and the writers
+    /// waiting in [`write_biased`](self::RwLock::write_biased) never make it
+    /// fail. It also returns `None` if the maximum number of shared read
+    /// accesses is already held.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let r1 = rwlock.read_recursive();
+    /// let r2 = rwlock.try_read_recursive().unwrap();
+    /// assert_eq!(*r1 + *r2, 2);
+    /// drop((r1, r2));
+    ///
+    /// let w = rwlock.write();
+    /// assert!(rwlock.try_read_recursive().is_none());
+    /// ```
+    #[inline]
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_read_recursive(&self) -> Option<RwLockReadGuard<'_, T, R>> {
+        // A single attempt never waits for the biased writers, so it can't
+        // deadlock behind the current thread's read access.
+        self.try_read()
+    }
+
+    /// Starts an optimistic read of the rwlock, without acquiring any access.
+    ///
+    /// Inspired by the `StampedLock` of Java, the returned token records the
+    /// version of the rwlock, incremented when a write access is acquired and
+    /// again when it is released. The data can then be read
+    /// through it without writing to the lock at all, which keeps the cache
+    /// line of the lock shared between the readers, but a writer may modify
+    /// the data at the same time. Once the data is read the token must be
+    /// validated with [`validate`](self::OptimisticReadGuard::validate), what
+    /// was read is only consistent if it returns `true`, otherwise the read
+    /// must be retried, usually falling back to [`read`](self::RwLock::read).
+    ///
+    /// A token started while the exclusive write access is held never
+    /// validates.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let rwlock = RwLock::new((1, 2));
+    ///
+    /// let sum = loop {
+    ///     let token = rwlock.optimistic_read();
+    ///     // SAFETY: The copy is discarded unless the token validates.
+    ///     let (a, b) = unsafe { token.read() };
+    ///     if token.validate() {
+    ///         break a + b;
+    ///     }
+    /// };
+    /// assert_eq!(sum, 3);
+    /// ```
+    #[inline]
+    pub fn optimistic_read(&self) -> OptimisticReadGuard<'_, T, R> {
+        OptimisticReadGuard {
+            rwlock: self,
+            version: self.version.load(Ordering::Acquire),
         }
     }
 
@@ -105,7 +553,9 @@ impl<T> RwLock<T> {
     /// blocking the thread until it is available.
     ///
     /// This function blocks the current thread by spinning
-    /// if any read access is held until it is released.
+    /// if any read access is held until it is released. With the
+    /// [`WriterPreferring`](self::Policy::WriterPreferring) policy it acquires
+    /// like [`write_biased`](self::RwLock::write_biased).
     ///
     /// ```
     /// use spinlock::RwLock;
@@ -120,18 +570,91 @@ impl<T> RwLock<T> {
     /// }).join();
     /// assert_eq!(*rwlock.read(), 42);
     /// ```
-    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn write(&self) -> RwLockWriteGuard<'_, T, R> {
+        if self.policy == Policy::WriterPreferring {
+            return self.write_biased();
+        }
+
+        let mut backoff = Backoff::with_budget(self.spin_budget);
+        // Optimistically assumes the lock is free on the first attempt.
+        let mut lock = 0;
+        loop {
+            // Attempts to acquire the lock each time it is observed free, the
+            // readers may take it again right after so waiting for a specific
+            // transition could miss the short windows where it is free.
+            //
+            // The loop already retries so the weak compare and swap is used, on
+            // LL/SC architectures (ARM, RISC-V) the strong one is itself a
+            // retry loop which would be nested in this one. The single attempt
+            // `try_*` functions use the strong one to not fail spuriously.
+            if lock == 0 {
+                match self.lock.compare_exchange_weak(
+                    0,
+                    WRITER,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => return RwLockWriteGuard::new(self),
+                    Err(current) => lock = current,
+                }
+            } else {
+                #[cfg(feature = "stats")]
+                self.write_spins.fetch_add(1, Ordering::Relaxed);
+                backoff.spin_with::<R>();
+                lock = self.lock.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Acquires the rwlock with exclusive write access, preventing new readers
+    /// from acquiring the lock while waiting.
+    ///
+    /// This function blocks the current thread by spinning
+    /// if any read access is held until it is released. Meanwhile
+    /// [`read`](self::RwLock::read) blocks so a continuous stream of readers
+    /// cannot starve this writer.
+    ///
+    /// The tradeoff is that readers can now be starved by a continuous stream
+    /// of biased writers, and a thread already holding a shared read access
+    /// that calls [`read`](self::RwLock::read) again deadlocks while a biased
+    /// writer is waiting.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    /// use std::thread;
+    /// use std::sync::Arc;
+    ///
+    /// let rwlock = Arc::new(RwLock::new(1));
+    /// let r = Arc::clone(&rwlock);
+    ///
+    /// thread::spawn(move || {
+    ///     *r.write_biased() = 42;
+    /// }).join();
+    /// assert_eq!(*rwlock.read(), 42);
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn write_biased(&self) -> RwLockWriteGuard<'_, T, R> {
+        let mut backoff = Backoff::with_budget(self.spin_budget);
+        // Counts this writer as waiting until it acquires the lock, so that
+        // the readers keep waiting while any other biased writer still does.
+        self.writers_waiting.fetch_add(1, Ordering::Relaxed);
         loop {
+            // A spurious failure is retried by the loop, see `write`.
             if self
                 .lock
-                .compare_exchange(0, -1, Ordering::Acquire, Ordering::Relaxed)
+                .compare_exchange_weak(0, WRITER, Ordering::Acquire, Ordering::Relaxed)
                 .is_ok()
             {
+                self.writers_waiting.fetch_sub(1, Ordering::Release);
                 return RwLockWriteGuard::new(self);
             }
 
             while self.lock.load(Ordering::Relaxed) != 0 {
-                hint::spin_loop();
+                #[cfg(feature = "stats")]
+                self.write_spins.fetch_add(1, Ordering::Relaxed);
+                backoff.spin_with::<R>();
             }
         }
     }
@@ -153,113 +676,2174 @@ impl<T> RwLock<T> {
     ///
     /// assert_eq!(*rwlock.read(), 2);
     /// ```
-    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T, R>> {
         self.lock
-            .compare_exchange(0, -1, Ordering::Acquire, Ordering::Relaxed)
+            .compare_exchange(0, WRITER, Ordering::Acquire, Ordering::Relaxed)
             .map_or(None, |_| Some(RwLockWriteGuard::new(self)))
     }
-}
 
-impl<T: Default> Default for RwLock<T> {
-    /// Creates a new `RwLock<T>` which is unlocked containing the default of `T`.
+    /// Tries to acquire the rwlock with shared read access, like [`try_read`](self::RwLock::try_read) but
+    /// returning a `Result`.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// # Errors
+    /// If the rwlock is not available returns [`WouldBlock`](crate::WouldBlock).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, WouldBlock};
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// assert_eq!(*rwlock.try_read_result().unwrap(), 1);
+    ///
+    /// let w = rwlock.write();
+    /// assert_eq!(rwlock.try_read_result().unwrap_err(), WouldBlock);
+    /// ```
     #[inline]
-    fn default() -> Self {
-        Self::new(Default::default())
+    pub fn try_read_result(&self) -> Result<RwLockReadGuard<'_, T, R>, WouldBlock> {
+        self.try_read().ok_or(WouldBlock)
     }
-}
-
-// SAFETY: The locking mechanism ensures that only one write access
-// or multiple read access are possible so it is safe to implement Sync
-// for a `T` that is Sync itself.
-unsafe impl<T: Sync> Sync for RwLock<T> {}
-
-/// Guard structure used to release the shared read access when dropped.
-///
-/// This structure is created by [`read`](self::RwLock::read) and
-/// [`try_read`](self::RwLock::try_read) on [`RwLock`](self::RwLock).
-#[derive(Debug)]
-pub struct RwLockReadGuard<'rwlock, T> {
-    rwlock: &'rwlock RwLock<T>,
-}
 
-impl<'rwlock, T> RwLockReadGuard<'rwlock, T> {
-    /// Creates a new `RwLockReadGuard<'rwlock, T>` from a given `RwLock<T>`.
+    /// Tries to acquire the rwlock with exclusive write access, like [`try_write`](self::RwLock::try_write) but
+    /// returning a `Result`.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// # Errors
+    /// If the rwlock is not available returns [`WouldBlock`](crate::WouldBlock).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, WouldBlock};
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// *rwlock.try_write_result().unwrap() = 2;
+    ///
+    /// let r = rwlock.read();
+    /// assert_eq!(*r, 2);
+    /// assert_eq!(rwlock.try_write_result().unwrap_err(), WouldBlock);
+    /// ```
     #[inline]
-    #[must_use]
-    const fn new(rwlock: &'rwlock RwLock<T>) -> Self {
-        Self { rwlock }
+    pub fn try_write_result(&self) -> Result<RwLockWriteGuard<'_, T, R>, WouldBlock> {
+        self.try_write().ok_or(WouldBlock)
     }
-}
-
-impl<T> Deref for RwLockReadGuard<'_, T> {
-    type Target = T;
 
+    /// Tries to acquire a shared read access up to `max_spins` times, returns `None`
+    /// if it never became available.
+    ///
+    /// This function spins for a bounded amount of time, in between an unbounded
+    /// [`read`](self::RwLock::read) and a single attempt [`try_read`](self::RwLock::try_read).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let w = rwlock.write();
+    /// assert!(rwlock.try_read_spin(100).is_none());
+    /// drop(w);
+    /// assert!(rwlock.try_read_spin(100).is_some());
+    /// ```
     #[inline]
-    fn deref(&self) -> &Self::Target {
-        // SAFETY: ReadGuards are only created when no WriteGuard
-        // are held so the data can't be modified while a ReadGuard is held
-        // so it is safe to get a reference to the data for the lifetime of
-        // the guard.
-        unsafe { &*self.rwlock.data.get() }
-    }
-}
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_read_spin(&self, max_spins: usize) -> Option<RwLockReadGuard<'_, T, R>> {
+        for _ in 0..max_spins {
+            if let Some(guard) = self.try_read() {
+                return Some(guard);
+            }
+            #[cfg(feature = "stats")]
+            self.read_spins.fetch_add(1, Ordering::Relaxed);
+            R::relax();
+        }
 
-impl<T> Drop for RwLockReadGuard<'_, T> {
-    #[inline]
-    fn drop(&mut self) {
-        self.rwlock.lock.fetch_sub(1, Ordering::Release);
+        None
     }
-}
 
-// Prevents the read guard from being moved to an other thread.
-impl<T> !Send for RwLockReadGuard<'_, T> {}
+    /// Tries to acquire an exclusive write access up to `max_spins` times,
+    /// returns `None` if it never became available.
+    ///
+    /// This function spins for a bounded amount of time, in between an unbounded
+    /// [`write`](self::RwLock::write) and a single attempt [`try_write`](self::RwLock::try_write).
+    /// With a `max_spins` of 0 it makes a single attempt.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let r = rwlock.read();
+    /// assert!(rwlock.try_write_spin(100).is_none());
+    /// drop(r);
+    /// assert!(rwlock.try_write_spin(100).is_some());
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_write_spin(&self, max_spins: usize) -> Option<RwLockWriteGuard<'_, T, R>> {
+        let mut spins = 0;
+        loop {
+            if let Some(guard) = self.try_write() {
+                return Some(guard);
+            }
 
-/// Guard structure used to release the excusive write access when dropped.
-///
-/// This structure is created by [`write`](self::RwLock::write) and
-/// [`try_write`](self::RwLock::try_write) on [`RwLock`](self::RwLock).
-#[derive(Debug)]
-pub struct RwLockWriteGuard<'rwlock, T> {
-    rwlock: &'rwlock RwLock<T>,
-}
+            // Spins on a load until no access is held rather than on the
+            // compare and swap, each failed attempt costs at least one spin
+            // so that the budget is always exhausted.
+            loop {
+                if spins == max_spins {
+                    return None;
+                }
+                spins += 1;
+                #[cfg(feature = "stats")]
+                self.write_spins.fetch_add(1, Ordering::Relaxed);
+                R::relax();
 
-impl<'rwlock, T> RwLockWriteGuard<'rwlock, T> {
-    /// Creates a new `RwLockWriteGuard<'rwlock, T>` from a given `RwLock<T>`.
-    #[inline]
-    #[must_use]
-    const fn new(rwlock: &'rwlock RwLock<T>) -> Self {
-        Self { rwlock }
+                if self.lock.load(Ordering::Relaxed) == 0 {
+                    break;
+                }
+            }
+        }
     }
-}
 
-impl<T> Deref for RwLockWriteGuard<'_, T> {
-    type Target = T;
+    /// Tries to acquire an exclusive write access, waiting up to `spins`
+    /// iterations for the shared read accesses to be released.
+    ///
+    /// Unlike [`try_write_spin`](self::RwLock::try_write_spin) it only waits
+    /// for the readers, which are expected to leave shortly, and gives up
+    /// right away if the exclusive write access or the upgradeable read access
+    /// is held. With a `spins` of 0 it makes a single attempt like
+    /// [`try_write`](self::RwLock::try_write).
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let w = rwlock.write();
+    /// assert!(rwlock.try_write_patient(usize::MAX).is_none());
+    /// drop(w);
+    ///
+    /// let r = rwlock.read();
+    /// assert!(rwlock.try_write_patient(100).is_none());
+    /// drop(r);
+    /// assert!(rwlock.try_write_patient(100).is_some());
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_write_patient(&self, spins: usize) -> Option<RwLockWriteGuard<'_, T, R>> {
+        let mut remaining = spins;
+        loop {
+            match self
+                .lock
+                .compare_exchange(0, WRITER, Ordering::Acquire, Ordering::Relaxed)
+            {
+                Ok(_) => return Some(RwLockWriteGuard::new(self)),
+                // Only the shared read accesses are waited for.
+                Err(lock) if lock & (WRITER | UPGRADEABLE) != 0 || remaining == 0 => {
+                    return None;
+                }
+                Err(_) => {}
+            }
 
-    fn deref(&self) -> &Self::Target {
-        // SAFETY: A WriteGuard is created only if no other guard is held
-        // so it is safe to give a reference to the data for the lifetime of
-        // the guard.
-        unsafe { &*self.rwlock.data.get() }
-    }
-}
+            // Spins on a load until the readers left rather than on the
+            // compare and swap.
+            while remaining > 0 {
+                remaining -= 1;
+                #[cfg(feature = "stats")]
+                self.write_spins.fetch_add(1, Ordering::Relaxed);
+                R::relax();
 
-impl<T> DerefMut for RwLockWriteGuard<'_, T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
+                let lock = self.lock.load(Ordering::Relaxed);
+                if lock == 0 || lock & (WRITER | UPGRADEABLE) != 0 {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Acquires a shared read access and calls the closure with a reference to the data.
+    ///
+    /// The read access is released once the closure returns, so the locked region
+    /// is limited to the closure.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let rwlock = RwLock::new(vec![1, 2, 3]);
+    ///
+    /// assert_eq!(rwlock.with_read(Vec::len), 3);
+    /// assert_eq!(rwlock.reader_count(), 0);
+    /// ```
+    #[inline]
+    pub fn with_read<U, F>(&self, f: F) -> U
+    where
+        F: FnOnce(&T) -> U,
+    {
+        f(&self.read())
+    }
+
+    /// Acquires an exclusive write access and calls the closure with a mutable
+    /// reference to the data.
+    ///
+    /// The write access is released once the closure returns, so the locked region
+    /// is limited to the closure.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let rwlock = RwLock::new(vec![1, 2, 3]);
+    ///
+    /// rwlock.with_write(|data| data.push(4));
+    /// assert!(!rwlock.is_write_locked());
+    /// assert_eq!(*rwlock.read(), [1, 2, 3, 4]);
+    /// ```
+    #[inline]
+    pub fn with_write<U, F>(&self, f: F) -> U
+    where
+        F: FnOnce(&mut T) -> U,
+    {
+        f(&mut self.write())
+    }
+
+    /// Acquires a shared read access and calls the closure with a reference to
+    /// the data, like [`with_read`](self::RwLock::with_read).
+    ///
+    /// The read access is released when the closure returns and also when it
+    /// panics, the guard being dropped while unwinding, so the lock is never
+    /// left held past the scope of the closure.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    /// use std::panic::{self, AssertUnwindSafe};
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let result = panic::catch_unwind(AssertUnwindSafe(|| rwlock.scoped_read(|_| panic!())));
+    /// assert!(result.is_err());
+    /// assert_eq!(rwlock.reader_count(), 0);
+    /// ```
+    #[inline]
+    pub fn scoped_read<U, F>(&self, f: F) -> U
+    where
+        F: FnOnce(&T) -> U,
+    {
+        self.with_read(f)
+    }
+
+    /// Acquires an exclusive write access and calls the closure with a mutable
+    /// reference to the data, like [`with_write`](self::RwLock::with_write).
+    ///
+    /// The write access is released when the closure returns and also when it
+    /// panics, the guard being dropped while unwinding, so the lock is never
+    /// left held past the scope of the closure.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    /// use std::panic::{self, AssertUnwindSafe};
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let result = panic::catch_unwind(AssertUnwindSafe(|| {
+    ///     rwlock.scoped_write(|data| {
+    ///         *data = 2;
+    ///         panic!();
+    ///     })
+    /// }));
+    /// assert!(result.is_err());
+    /// assert!(!rwlock.is_write_locked());
+    /// assert_eq!(*rwlock.read(), 2);
+    /// ```
+    #[inline]
+    pub fn scoped_write<U, F>(&self, f: F) -> U
+    where
+        F: FnOnce(&mut T) -> U,
+    {
+        self.with_write(f)
+    }
+
+    /// Acquires an exclusive write access, calls the closure with a mutable
+    /// reference to the data, then atomically downgrades to a shared read
+    /// access which is returned.
+    ///
+    /// No writer can acquire the lock between the closure and the returned
+    /// guard, so it observes the data left by the closure while other readers
+    /// may share the lock right away. It is a shorthand for
+    /// [`write`](self::RwLock::write) followed by
+    /// [`RwLockWriteGuard::downgrade`].
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let rwlock = RwLock::new(vec![1, 2, 3]);
+    ///
+    /// let r = rwlock.write_then_downgrade(|data| data.push(4));
+    /// assert_eq!(*r, [1, 2, 3, 4]);
+    /// assert!(rwlock.try_read().is_some());
+    /// assert!(rwlock.try_write().is_none());
+    /// ```
+    #[inline]
+    pub fn write_then_downgrade<F>(&self, f: F) -> RwLockReadGuard<'_, T, R>
+    where
+        F: FnOnce(&mut T),
+    {
+        let mut guard = self.write();
+        f(&mut guard);
+        RwLockWriteGuard::downgrade(guard)
+    }
+
+    /// Acquires the rwlock with upgradeable read access,
+    /// blocking the thread until it is available.
+    ///
+    /// The upgradeable read access can be held along shared read accesses
+    /// but only one upgradeable read access can be held at a time.
+    /// It can later be upgraded to an exclusive write access without
+    /// releasing the lock in between.
+    ///
+    /// This function blocks the current thread by spinning
+    /// if write access or upgradeable read access is held until it is released.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, RwLockUpgradableReadGuard};
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let upgradeable = rwlock.upgradeable_read();
+    /// assert_eq!(*upgradeable, 1);
+    ///
+    /// let mut w = RwLockUpgradableReadGuard::upgrade(upgradeable);
+    /// *w = 2;
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn upgradeable_read(&self) -> RwLockUpgradableReadGuard<'_, T, R> {
+        loop {
+            if let Some(guard) = self.try_upgradeable_read() {
+                return guard;
+            }
+
+            while {
+                let lock = self.lock.load(Ordering::Relaxed);
+                lock & (WRITER | UPGRADEABLE) != 0
+            } {
+                #[cfg(feature = "stats")]
+                self.read_spins.fetch_add(1, Ordering::Relaxed);
+                R::relax();
+            }
+        }
+    }
+
+    /// Tries to acquire the rwlock with upgradeable read access. If the lock is not available returns `None`.
+    ///
+    /// This function does not block the current thread. It returns `None` when
+    /// the exclusive write access is held, and also when another upgradeable
+    /// read access is held since only one can be held at a time. The shared
+    /// read accesses do not prevent it.
+    ///
+    /// The lock state is a single word: its highest bit is set while the
+    /// exclusive write access is held, the next one while the upgradeable read
+    /// access is held and the remaining bits count the shared read accesses.
+    /// The upgradeable read access is acquired by setting its bit only when
+    /// both of these bits are clear, the reader count is left untouched.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let upgradeable = rwlock.try_upgradeable_read().unwrap();
+    /// assert_eq!(*upgradeable, 1);
+    ///
+    /// assert!(rwlock.try_upgradeable_read().is_none());
+    /// assert!(rwlock.try_read().is_some());
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_upgradeable_read(&self) -> Option<RwLockUpgradableReadGuard<'_, T, R>> {
+        // Only sets the bit when neither the write access nor the upgradeable
+        // read access is held, the shared read accesses are left untouched.
+        let mut lock = self.lock.load(Ordering::Relaxed);
+        while lock & (WRITER | UPGRADEABLE) == 0 {
+            match self.lock.compare_exchange(
+                lock,
+                lock | UPGRADEABLE,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(RwLockUpgradableReadGuard::new(self)),
+                Err(current) => lock = current,
+            }
+        }
+
+        None
+    }
+
+    /// Returns the maximum number of shared read accesses that can be held at
+    /// the same time, that is `2^(usize::BITS - 2) - 1` accesses.
+    ///
+    /// Once it is reached [`read`](self::RwLock::read) waits for a shared read
+    /// access to be released and [`try_read`](self::RwLock::try_read) returns
+    /// `None`.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// assert_eq!(RwLock::<i32>::max_readers(), usize::MAX >> 2);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn max_readers() -> usize {
+        MAX_READERS
+    }
+
+    /// Returns the number of shared read accesses currently held.
+    ///
+    /// The returned value is only a snapshot of the lock state which may
+    /// change right after the call, so it must not be used to make
+    /// synchronization decisions. It is meant for diagnostics such as
+    /// metrics or logging. It may also count the readers about to back off
+    /// while the exclusive write access is held.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let rwlock = RwLock::new(1);
+    /// let r1 = rwlock.read();
+    /// let r2 = rwlock.read();
+    ///
+    /// assert_eq!(rwlock.reader_count(), 2);
+    /// ```
+    #[inline]
+    pub fn reader_count(&self) -> usize {
+        self.lock.load(Ordering::Relaxed) & MAX_READERS
+    }
+
+    /// Returns whether the exclusive write access is currently held.
+    ///
+    /// The returned value is only a snapshot of the lock state which may
+    /// change right after the call, so it must not be used to make
+    /// synchronization decisions. It is meant for diagnostics such as
+    /// metrics or logging.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let rwlock = RwLock::new(1);
+    /// let w = rwlock.write();
+    ///
+    /// assert!(rwlock.is_write_locked());
+    /// ```
+    #[inline]
+    pub fn is_write_locked(&self) -> bool {
+        self.lock.load(Ordering::Relaxed) & WRITER != 0
+    }
+
+    /// Returns the address of the rwlock, a stable identity of this instance.
+    ///
+    /// The identity does not change while the rwlock is borrowed, and two
+    /// rwlocks alive at the same time never share it. It can be used to
+    /// acquire several locks in a consistent order, such as the order of
+    /// their addresses, or to track the locks held by a thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let a = RwLock::new(1);
+    /// let b = RwLock::new(2);
+    ///
+    /// assert_eq!(a.id(), a.id());
+    /// assert_ne!(a.id(), b.id());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn id(&self) -> usize {
+        ptr::from_ref(self).addr()
+    }
+
+    /// Returns whether the two references point to the same rwlock, comparing
+    /// their identities rather than their data, see [`id`](self::RwLock::id).
+    ///
+    /// This is an associated function that needs to be used as
+    /// `RwLock::ptr_eq(...)`, like `Arc::ptr_eq`.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let a = RwLock::new(1);
+    /// let b = RwLock::new(1);
+    ///
+    /// assert!(RwLock::ptr_eq(&a, &a));
+    /// assert!(!RwLock::ptr_eq(&a, &b));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        ptr::addr_eq(this, other)
+    }
+
+    /// Returns the number of spin iterations the readers and the writers
+    /// spent waiting for the lock since it was created.
+    ///
+    /// The counters are only updated while waiting so an uncontended lock
+    /// reports no spin. Like [`reader_count`](self::RwLock::reader_count) the
+    /// returned value is only a snapshot meant for diagnostics.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let r = rwlock.read();
+    /// assert!(rwlock.try_write_spin(10).is_none());
+    /// drop(r);
+    ///
+    /// let stats = rwlock.stats();
+    /// assert_eq!(stats.read_spins, 0);
+    /// assert_eq!(stats.write_spins, 10);
+    /// ```
+    #[cfg(feature = "stats")]
+    #[inline]
+    pub fn stats(&self) -> LockStats {
+        LockStats {
+            read_spins: self.read_spins.load(Ordering::Relaxed),
+            write_spins: self.write_spins.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Forcibly releases a shared read access.
+    ///
+    /// This is useful when the guard has been forgotten with [`core::mem::forget`]
+    /// to hand the responsibility of unlocking to another component.
+    ///
+    /// # Safety
+    /// A shared read access must be held by the caller and its guard must not be
+    /// used nor dropped afterwards, typically because it was forgotten. Releasing
+    /// a shared read access that is not held corrupts the reader count and may
+    /// allow a writer to acquire the lock while readers still hold it.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    /// use std::mem;
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// mem::forget(rwlock.read());
+    /// assert_eq!(rwlock.reader_count(), 1);
+    ///
+    /// // SAFETY: The guard of the read access was forgotten.
+    /// unsafe { rwlock.force_read_unlock() };
+    /// assert_eq!(rwlock.reader_count(), 0);
+    /// ```
+    #[allow(clippy::inline_always)]
+    #[inline(always)]
+    pub unsafe fn force_read_unlock(&self) {
+        self.lock.fetch_sub(READER, Ordering::Release);
+        R::wake();
+    }
+
+    /// Forcibly releases the exclusive write access.
+    ///
+    /// This is useful when the guard has been forgotten with [`core::mem::forget`]
+    /// to hand the responsibility of unlocking to another component.
+    ///
+    /// # Safety
+    /// The exclusive write access must be held by the caller and its guard must
+    /// not be used nor dropped afterwards, typically because it was forgotten.
+    /// Releasing the write access while a guard is still in use allows other
+    /// threads to access the data concurrently with it, and releasing it when it
+    /// is not held discards any shared read access currently held.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    /// use std::mem;
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// mem::forget(rwlock.write());
+    /// assert!(rwlock.is_write_locked());
+    ///
+    /// // SAFETY: The guard of the write access was forgotten.
+    /// unsafe { rwlock.force_write_unlock() };
+    /// assert!(!rwlock.is_write_locked());
+    /// ```
+    #[allow(clippy::inline_always)]
+    #[inline(always)]
+    pub unsafe fn force_write_unlock(&self) {
+        self.end_write();
+        // Only clears the write bit since readers may have transiently
+        // incremented the reader count, see `acquire_read`.
+        self.lock.fetch_sub(WRITER, Ordering::Release);
+        R::wake();
+    }
+
+    /// Makes the version odd once the exclusive write access is acquired, so
+    /// that the optimistic reads overlapping the write fail to validate.
+    #[inline]
+    fn begin_write(&self) {
+        self.version.fetch_add(1, Ordering::Relaxed);
+        // Orders the odd version before the writes to the data, so that an
+        // optimistic read of these writes fails to validate.
+        fence(Ordering::Release);
+    }
+
+    /// Makes the version even again before the exclusive write access is
+    /// released, publishing the writes to the data with it.
+    #[inline]
+    fn end_write(&self) {
+        self.version.fetch_add(1, Ordering::Release);
+    }
+
+    /// Returns a raw pointer to the underlying data.
+    ///
+    /// This is an escape hatch for protocols which guarantee the exclusive
+    /// access to the data by other means than the lock. No access is
+    /// acquired, the pointer is valid as long as the rwlock is alive.
+    ///
+    /// Dereferencing the pointer is unsafe: no mutable reference created from
+    /// it may alias a reference obtained through a guard, and writing through
+    /// it while another thread reads the data is a data race.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let data = rwlock.data_ptr();
+    /// // SAFETY: No guard is held and the rwlock is not shared with another
+    /// // thread so this is the only access to the data.
+    /// unsafe { *data = 42 };
+    /// assert_eq!(*rwlock.read(), 42);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn data_ptr(&self) -> *mut T {
+        self.data.with_mut(|data| data)
+    }
+
+    /// Returns a shared reference to the underlying data without acquiring a
+    /// shared read access.
+    ///
+    /// This avoids the atomic operations of [`read`](self::RwLock::read) in
+    /// hot loops where the absence of writers is already proven by other
+    /// means, for instance during a phase where a single thread uses the
+    /// rwlock. The reference can be held along read guards.
+    ///
+    /// # Safety
+    /// No exclusive write access may be held, nor acquired by any thread,
+    /// while the returned reference is alive. This includes the write guards,
+    /// the upgrades of upgradeable read guards and the writes through
+    /// [`data_ptr`](self::RwLock::data_ptr). Otherwise the data could be
+    /// modified while it is borrowed, which is undefined behavior.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let rwlock = RwLock::new(vec![1, 2, 3]);
+    ///
+    /// // SAFETY: The rwlock is not shared with another thread and no write
+    /// // access is acquired while the data is borrowed.
+    /// let data = unsafe { rwlock.borrow_data_unchecked() };
+    /// let sum: i32 = data.iter().sum();
+    /// assert_eq!(sum, 6);
+    /// assert_eq!(rwlock.reader_count(), 0);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub unsafe fn borrow_data_unchecked(&self) -> &T {
+        // SAFETY: The caller guarantees that the data is not modified while
+        // the reference is alive.
+        self.data.with(|data| unsafe { &*data })
+    }
+}
+
+impl<T: Default, R: Relax> Default for RwLock<T, R> {
+    /// Creates a new `RwLock<T, R>` which is unlocked containing the default of `T`.
+    #[inline]
+    fn default() -> Self {
+        Self::with_relax(Default::default())
+    }
+}
+
+impl<T, R: Relax> From<T> for RwLock<T, R> {
+    /// Creates a new `RwLock<T, R>` which is unlocked containing the given value.
+    #[inline]
+    fn from(data: T) -> Self {
+        Self::with_relax(data)
+    }
+}
+
+impl<T: Clone, R: Relax> Clone for RwLock<T, R> {
+    /// Creates a new unlocked rwlock containing a clone of the data.
+    ///
+    /// A shared read access is acquired while the data is cloned. The clone
+    /// is independent from the original rwlock, it is not a handle to the
+    /// same data.
+    #[inline]
+    fn clone(&self) -> Self {
+        Self::with_relax(T::clone(&self.read()))
+    }
+}
+
+impl<T: ?Sized + fmt::Debug, R: Relax> fmt::Debug for RwLock<T, R> {
+    /// Formats the data of the rwlock, or `<locked>` if the exclusive write access is held.
+    ///
+    /// This never blocks, a shared read access is only tried once.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("RwLock");
+        match self.try_read() {
+            Some(guard) => d.field("data", &&*guard),
+            None => d.field("data", &format_args!("<locked>")),
+        };
+        d.finish()
+    }
+}
+
+impl<T: ?Sized, R: Relax> fmt::Pointer for RwLock<T, R> {
+    /// Formats the address of the rwlock, see [`id`](self::RwLock::id).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&ptr::from_ref(self), f)
+    }
+}
+
+impl<T: PartialEq, R: Relax> PartialEq for RwLock<T, R> {
+    /// Compares the data of two rwlocks, a shared read access of both is
+    /// acquired during the comparison.
+    ///
+    /// The read accesses are acquired in the order of the rwlocks addresses,
+    /// comparing a rwlock with itself acquires a single read access.
+    fn eq(&self, other: &Self) -> bool {
+        if ptr::eq(self, other) {
+            let guard = self.read();
+            return T::eq(&guard, &guard);
+        }
+
+        // The rwlock with the lowest address is always read first.
+        let (ours, theirs) = if self.id() < other.id() {
+            let ours = self.read();
+            (ours, other.read())
+        } else {
+            let theirs = other.read();
+            (self.read(), theirs)
+        };
+        *ours == *theirs
+    }
+}
+
+impl<T: Eq, R: Relax> Eq for RwLock<T, R> {}
+
+// SAFETY: The rwlock owns the data so it can be sent to another thread along
+// with it.
+unsafe impl<T: ?Sized + Send, R: Relax> Send for RwLock<T, R> {}
+
+// SAFETY: The locking mechanism ensures that only one write access or multiple
+// read accesses are possible, so the data is shared between the readers and
+// may be moved out by a writer of another thread.
+unsafe impl<T: ?Sized + Send + Sync, R: Relax> Sync for RwLock<T, R> {}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize, R: Relax> serde::Serialize for RwLock<T, R> {
+    /// Serializes the data of the rwlock, a shared read access is acquired
+    /// during the serialization.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.read().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>, R: Relax> serde::Deserialize<'de> for RwLock<T, R> {
+    /// Deserializes the data into an unlocked rwlock.
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::with_relax)
+    }
+}
+
+/// Token of an optimistic read of a [`RwLock`], which doesn't hold any access.
+///
+/// This structure is created by
+/// [`optimistic_read`](self::RwLock::optimistic_read) on [`RwLock`](self::RwLock).
+/// The data read through it may be concurrently written, it must only be
+/// trusted once [`validate`](self::OptimisticReadGuard::validate) returned
+/// `true`.
+#[derive(Debug)]
+#[must_use = "the data read through the token must be validated"]
+pub struct OptimisticReadGuard<'rwlock, T: ?Sized, R: Relax = Spin> {
+    rwlock: &'rwlock RwLock<T, R>,
+    // Version of the rwlock when the optimistic read started, odd if the
+    // exclusive write access was held.
+    version: usize,
+}
+
+impl<T: ?Sized, R: Relax> OptimisticReadGuard<'_, T, R> {
+    /// Returns `true` if no exclusive write access was held since the
+    /// optimistic read started, in which case the data read through the token
+    /// is consistent.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::RwLock;
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let token = rwlock.optimistic_read();
+    /// assert!(token.validate());
+    ///
+    /// *rwlock.write() = 2;
+    /// assert!(!token.validate());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn validate(&self) -> bool {
+        // Orders the reads of the data before the check of the version.
+        fence(Ordering::Acquire);
+        self.version & 1 == 0 && self.rwlock.version.load(Ordering::Relaxed) == self.version
+    }
+
+    /// Returns a raw pointer to the underlying data.
+    ///
+    /// The data may be concurrently written so dereferencing the pointer is
+    /// unsafe, see [`read`](self::OptimisticReadGuard::read).
+    #[inline]
+    #[must_use]
+    pub fn data_ptr(&self) -> *const T {
+        self.rwlock.data_ptr()
+    }
+
+    /// Returns a copy of the data, which may be torn by a concurrent write.
+    ///
+    /// # Safety
+    /// The copy must be discarded without being used unless
+    /// [`validate`](self::OptimisticReadGuard::validate) returns `true` after
+    /// it. `T` must also be valid for any mix of the bytes of its values,
+    /// since a concurrent write can leave a torn copy which must be safe to
+    /// discard, which is the case for plain data such as integers.
+    #[inline]
+    #[must_use]
+    pub unsafe fn read(&self) -> T
+    where
+        T: Copy,
+    {
+        // SAFETY: The data is read with a volatile read, the caller discards
+        // it unless no write happened concurrently.
+        unsafe { ptr::read_volatile(self.data_ptr()) }
+    }
+}
+
+/// Guard structure used to release the shared read access when dropped.
+///
+/// This structure is created by [`read`](self::RwLock::read) and
+/// [`try_read`](self::RwLock::try_read) on [`RwLock`](self::RwLock).
+///
+/// The guards of a `RwLock` are `!Send`, they must be dropped by the thread
+/// which acquired the access. With the `send_guard` feature they are `Send`
+/// instead when `T` allows it, see [`MutexGuard`](crate::MutexGuard) for the
+/// caveats.
+#[derive(Debug)]
+#[must_use = "if unused the lock is immediately released"]
+pub struct RwLockReadGuard<'rwlock, T: ?Sized, R: Relax = Spin> {
+    rwlock: &'rwlock RwLock<T, R>,
+}
+
+impl<'rwlock, T: ?Sized, R: Relax> RwLockReadGuard<'rwlock, T, R> {
+    /// Creates a new `RwLockReadGuard<'rwlock, T, R>` from a given `RwLock<T, R>`.
+    #[inline]
+    const fn new(rwlock: &'rwlock RwLock<T, R>) -> Self {
+        Self { rwlock }
+    }
+
+    /// Returns the rwlock whose shared read access is held by the guard.
+    ///
+    /// This is an associated function that needs to be used as `RwLockReadGuard::rwlock(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, RwLockReadGuard};
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let guard = rwlock.read();
+    /// let locked = RwLockReadGuard::rwlock(&guard);
+    /// drop(guard);
+    ///
+    /// assert!(locked.try_write().is_some());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn rwlock(guard: &Self) -> &'rwlock RwLock<T, R> {
+        guard.rwlock
+    }
+
+    /// Returns a reference to the locked data.
+    ///
+    /// This is the same as dereferencing the guard, as an explicit borrow
+    /// which is more readable in generic code.
+    ///
+    /// This is an associated function that needs to be used as `RwLockReadGuard::get(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, RwLockReadGuard};
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let guard = rwlock.read();
+    /// assert_eq!(*RwLockReadGuard::get(&guard), 1);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn get(guard: &Self) -> &T {
+        guard
+    }
+
+    /// Makes a new [`MappedRwLockReadGuard`](self::MappedRwLockReadGuard) for a
+    /// component of the locked data.
+    ///
+    /// The shared read access is held until the returned guard is dropped.
+    ///
+    /// This is an associated function that needs to be used as `RwLockReadGuard::map(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, RwLockReadGuard};
+    ///
+    /// let rwlock = RwLock::new((1, 2));
+    ///
+    /// let second = RwLockReadGuard::map(rwlock.read(), |data| &data.1);
+    /// assert_eq!(*second, 2);
+    /// ```
+    #[inline]
+    pub fn map<U: ?Sized, F>(orig: Self, f: F) -> MappedRwLockReadGuard<'rwlock, U>
+    where
+        F: FnOnce(&T) -> &U,
+    {
+        let lock = &orig.rwlock.lock;
+        // SAFETY: The shared read access is held so it is safe to get a
+        // reference to the data.
+        let data = f(orig.rwlock.data.with(|data| unsafe { &*data }));
+        // The shared read access is now released by the mapped guard.
+        mem::forget(orig);
+
+        MappedRwLockReadGuard::new(lock, data, R::wake)
+    }
+
+    /// Tries to make a new [`MappedRwLockReadGuard`](self::MappedRwLockReadGuard)
+    /// for a component of the locked data.
+    ///
+    /// The shared read access is held until the returned guard is dropped.
+    ///
+    /// This is an associated function that needs to be used as `RwLockReadGuard::try_map(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Errors
+    /// If the closure returns `None` the original guard is returned back
+    /// so the shared read access is not released.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, RwLockReadGuard};
+    ///
+    /// let rwlock = RwLock::new(Some(1));
+    ///
+    /// let inner = RwLockReadGuard::try_map(rwlock.read(), Option::as_ref).unwrap();
+    /// assert_eq!(*inner, 1);
+    /// ```
+    #[inline]
+    pub fn try_map<U: ?Sized, F>(
+        orig: Self,
+        f: F,
+    ) -> Result<MappedRwLockReadGuard<'rwlock, U>, Self>
+    where
+        F: FnOnce(&T) -> Option<&U>,
+    {
+        let lock = &orig.rwlock.lock;
+        // SAFETY: The shared read access is held so it is safe to get a
+        // reference to the data.
+        match f(orig.rwlock.data.with(|data| unsafe { &*data })) {
+            Some(data) => {
+                // The shared read access is now released by the mapped guard.
+                mem::forget(orig);
+                Ok(MappedRwLockReadGuard::new(lock, data, R::wake))
+            }
+            None => Err(orig),
+        }
+    }
+
+    /// Temporarily releases the shared read access while calling the closure,
+    /// then re-acquires it before returning.
+    ///
+    /// This lets a waiting writer acquire the lock during a long operation
+    /// which does not need the data. The data may have been modified by a
+    /// writer once the closure returns. The shared read access is re-acquired
+    /// even if the closure panics.
+    ///
+    /// This is an associated function that needs to be used as `RwLockReadGuard::unlocked(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, RwLockReadGuard};
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let mut guard = rwlock.read();
+    /// RwLockReadGuard::unlocked(&mut guard, || *rwlock.write() = 2);
+    /// assert_eq!(*guard, 2);
+    /// ```
+    pub fn unlocked<U, F>(guard: &mut Self, f: F) -> U
+    where
+        F: FnOnce() -> U,
+    {
+        // Writes back a new guard when dropped, including when the closure
+        // panics, so that the guard is valid again once the function exits.
+        struct Relock<'guard, 'rwlock, T: ?Sized, R: Relax> {
+            guard: &'guard mut RwLockReadGuard<'rwlock, T, R>,
+            rwlock: &'rwlock RwLock<T, R>,
+        }
+
+        impl<T: ?Sized, R: Relax> Drop for Relock<'_, '_, T, R> {
+            fn drop(&mut self) {
+                // SAFETY: The previous guard was dropped in place so it is
+                // overwritten without being dropped again.
+                unsafe { ptr::write(self.guard, self.rwlock.read()) };
+            }
+        }
+
+        let rwlock = guard.rwlock;
+        // SAFETY: The guard is not used until it is written back by `Relock`.
+        unsafe { ptr::drop_in_place(ptr::from_mut(guard)) };
+        let _relock = Relock { guard, rwlock };
+
+        f()
+    }
+}
+
+impl<T: ?Sized, R: Relax> Deref for RwLockReadGuard<'_, T, R> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: ReadGuards are only created when no WriteGuard
+        // are held so the data can't be modified while a ReadGuard is held
+        // so it is safe to get a reference to the data for the lifetime of
+        // the guard.
+        self.rwlock.data.with(|data| unsafe { &*data })
+    }
+}
+
+impl<T: ?Sized + fmt::Display, R: Relax> fmt::Display for RwLockReadGuard<'_, T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + Hash, R: Relax> Hash for RwLockReadGuard<'_, T, R> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<T: ?Sized + PartialEq, R: Relax> PartialEq for RwLockReadGuard<'_, T, R> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + Eq, R: Relax> Eq for RwLockReadGuard<'_, T, R> {}
+
+impl<T: ?Sized + PartialOrd, R: Relax> PartialOrd for RwLockReadGuard<'_, T, R> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: ?Sized + Ord, R: Relax> Ord for RwLockReadGuard<'_, T, R> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: ?Sized, R: Relax> Borrow<T> for RwLockReadGuard<'_, T, R> {
+    #[inline]
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized, R: Relax> Drop for RwLockReadGuard<'_, T, R> {
+    #[inline]
+    fn drop(&mut self) {
+        // SAFETY: The guard holds a shared read access so it is safe to
+        // release it when the guard gets dropped.
+        unsafe { self.rwlock.force_read_unlock() }
+    }
+}
+
+// Prevents the read guard from being moved to an other thread.
+#[cfg(not(feature = "send_guard"))]
+impl<T: ?Sized, R: Relax> !Send for RwLockReadGuard<'_, T, R> {}
+
+// SAFETY: The read access can be released by any thread, sending the guard
+// shares the data with another thread like sending a reference.
+#[cfg(feature = "send_guard")]
+unsafe impl<T: ?Sized + Sync, R: Relax> Send for RwLockReadGuard<'_, T, R> {}
+
+/// Guard structure used to release the excusive write access when dropped.
+///
+/// This structure is created by [`write`](self::RwLock::write) and
+/// [`try_write`](self::RwLock::try_write) on [`RwLock`](self::RwLock).
+#[derive(Debug)]
+#[must_use = "if unused the lock is immediately released"]
+pub struct RwLockWriteGuard<'rwlock, T: ?Sized, R: Relax = Spin> {
+    rwlock: &'rwlock RwLock<T, R>,
+}
+
+impl<'rwlock, T: ?Sized, R: Relax> RwLockWriteGuard<'rwlock, T, R> {
+    /// Creates a new `RwLockWriteGuard<'rwlock, T, R>` from a given `RwLock<T, R>`.
+    #[inline]
+    fn new(rwlock: &'rwlock RwLock<T, R>) -> Self {
+        rwlock.begin_write();
+        Self { rwlock }
+    }
+
+    /// Returns the rwlock whose exclusive write access is held by the guard.
+    ///
+    /// This is an associated function that needs to be used as `RwLockWriteGuard::rwlock(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, RwLockWriteGuard};
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let guard = rwlock.write();
+    /// let locked = RwLockWriteGuard::rwlock(&guard);
+    /// drop(guard);
+    ///
+    /// assert!(locked.try_write().is_some());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn rwlock(guard: &Self) -> &'rwlock RwLock<T, R> {
+        guard.rwlock
+    }
+
+    /// Returns a reference to the locked data.
+    ///
+    /// This is the same as dereferencing the guard, as an explicit borrow
+    /// which is more readable in generic code.
+    ///
+    /// This is an associated function that needs to be used as `RwLockWriteGuard::get(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, RwLockWriteGuard};
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let guard = rwlock.write();
+    /// assert_eq!(*RwLockWriteGuard::get(&guard), 1);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn get(guard: &Self) -> &T {
+        guard
+    }
+
+    /// Returns a mutable reference to the locked data.
+    ///
+    /// This is the same as dereferencing the guard mutably, as an explicit
+    /// borrow which is more readable in generic code.
+    ///
+    /// This is an associated function that needs to be used as `RwLockWriteGuard::get_mut(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, RwLockWriteGuard};
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let mut guard = rwlock.write();
+    /// *RwLockWriteGuard::get_mut(&mut guard) = 2;
+    /// assert_eq!(*guard, 2);
+    /// ```
+    #[inline]
+    pub fn get_mut(guard: &mut Self) -> &mut T {
+        guard
+    }
+
+    /// Makes a new [`MappedRwLockWriteGuard`](self::MappedRwLockWriteGuard) for a
+    /// component of the locked data.
+    ///
+    /// The exclusive write access is held until the returned guard is dropped.
+    ///
+    /// This is an associated function that needs to be used as `RwLockWriteGuard::map(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, RwLockWriteGuard};
+    ///
+    /// let rwlock = RwLock::new((1, 2));
+    ///
+    /// let mut second = RwLockWriteGuard::map(rwlock.write(), |data| &mut data.1);
+    /// *second = 42;
+    /// drop(second);
+    ///
+    /// assert_eq!(*rwlock.read(), (1, 42));
+    /// ```
+    #[inline]
+    pub fn map<U: ?Sized, F>(orig: Self, f: F) -> MappedRwLockWriteGuard<'rwlock, U>
+    where
+        F: FnOnce(&mut T) -> &mut U,
+    {
+        let lock = &orig.rwlock.lock;
+        let version = &orig.rwlock.version;
+        // SAFETY: The exclusive write access is held so it is safe to get a
+        // mutable reference to the data.
+        let data = f(orig.rwlock.data.with_mut(|data| unsafe { &mut *data }));
+        // The exclusive write access is now released by the mapped guard.
+        mem::forget(orig);
+
+        MappedRwLockWriteGuard::new(lock, version, data, R::wake)
+    }
+
+    /// Atomically downgrades the exclusive write access to a shared read access.
+    ///
+    /// No writer can acquire the lock in between so the data observed through the
+    /// returned guard is the one left by this write guard. Other readers may
+    /// acquire the lock right after.
+    ///
+    /// This is an associated function that needs to be used as `RwLockWriteGuard::downgrade(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, RwLockWriteGuard};
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let mut w = rwlock.write();
+    /// *w = 2;
+    ///
+    /// let r = RwLockWriteGuard::downgrade(w);
+    /// assert_eq!(*r, 2);
+    /// assert!(rwlock.try_read().is_some());
+    /// ```
+    pub fn downgrade(guard: Self) -> RwLockReadGuard<'rwlock, T, R> {
+        let rwlock = guard.rwlock;
+        // The lock is now released by the read guard.
+        mem::forget(guard);
+
+        rwlock.end_write();
+        // Swaps the write bit for a single shared read access, keeping the
+        // transient increments of the readers, see `acquire_read`.
+        rwlock.lock.fetch_sub(WRITER - READER, Ordering::Release);
+        R::wake();
+
+        RwLockReadGuard::new(rwlock)
+    }
+
+    /// Atomically downgrades the exclusive write access to an upgradeable read
+    /// access.
+    ///
+    /// No writer nor upgradeable reader can acquire the lock in between, so the
+    /// returned guard observes the data left by this write guard and can be
+    /// upgraded again later. Other readers may acquire the lock right after.
+    ///
+    /// This is an associated function that needs to be used as
+    /// `RwLockWriteGuard::downgrade_to_upgradeable(...)` so that it does not
+    /// conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, RwLockUpgradableReadGuard, RwLockWriteGuard};
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let mut w = rwlock.write();
+    /// *w = 2;
+    ///
+    /// let u = RwLockWriteGuard::downgrade_to_upgradeable(w);
+    /// assert_eq!(*u, 2);
+    /// assert!(rwlock.try_read().is_some());
+    /// assert!(rwlock.try_upgradeable_read().is_none());
+    ///
+    /// let mut w = RwLockUpgradableReadGuard::upgrade(u);
+    /// *w = 3;
+    /// ```
+    pub fn downgrade_to_upgradeable(guard: Self) -> RwLockUpgradableReadGuard<'rwlock, T, R> {
+        let rwlock = guard.rwlock;
+        // The lock is now released by the upgradeable read guard.
+        mem::forget(guard);
+
+        rwlock.end_write();
+        // Swaps the write bit for the upgradeable bit, keeping the transient
+        // increments of the readers, see `acquire_read`.
+        rwlock
+            .lock
+            .fetch_sub(WRITER - UPGRADEABLE, Ordering::Release);
+        R::wake();
+
+        RwLockUpgradableReadGuard::new(rwlock)
+    }
+
+    /// Tries to make a new [`MappedRwLockWriteGuard`](self::MappedRwLockWriteGuard)
+    /// for a component of the locked data.
+    ///
+    /// The exclusive write access is held until the returned guard is dropped.
+    ///
+    /// This is an associated function that needs to be used as `RwLockWriteGuard::try_map(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Errors
+    /// If the closure returns `None` the original guard is returned back
+    /// so the exclusive write access is not released.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, RwLockWriteGuard};
+    ///
+    /// let rwlock = RwLock::new(Some(1));
+    ///
+    /// let mut inner = RwLockWriteGuard::try_map(rwlock.write(), Option::as_mut).unwrap();
+    /// *inner = 42;
+    /// drop(inner);
+    ///
+    /// assert_eq!(*rwlock.read(), Some(42));
+    /// ```
+    #[inline]
+    pub fn try_map<U: ?Sized, F>(
+        orig: Self,
+        f: F,
+    ) -> Result<MappedRwLockWriteGuard<'rwlock, U>, Self>
+    where
+        F: FnOnce(&mut T) -> Option<&mut U>,
+    {
+        let lock = &orig.rwlock.lock;
+        let version = &orig.rwlock.version;
+        // SAFETY: The exclusive write access is held so it is safe to get a
+        // mutable reference to the data.
+        match f(orig.rwlock.data.with_mut(|data| unsafe { &mut *data })) {
+            Some(data) => {
+                // The exclusive write access is now released by the mapped guard.
+                mem::forget(orig);
+                Ok(MappedRwLockWriteGuard::new(lock, version, data, R::wake))
+            }
+            None => Err(orig),
+        }
+    }
+}
+
+impl<T: ?Sized, R: Relax> Deref for RwLockWriteGuard<'_, T, R> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: A WriteGuard is created only if no other guard is held
+        // so it is safe to give a reference to the data for the lifetime of
+        // the guard.
+        self.rwlock.data.with(|data| unsafe { &*data })
+    }
+}
+
+impl<T: ?Sized, R: Relax> DerefMut for RwLockWriteGuard<'_, T, R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
         // SAFETY: A WriteGuard is created only if no other guard is held
         // so it is safe to give a mutable reference to the data for the
         // lifetime of the guard.
-        unsafe { &mut *self.rwlock.data.get() }
+        self.rwlock.data.with_mut(|data| unsafe { &mut *data })
+    }
+}
+
+impl<T: ?Sized + fmt::Display, R: Relax> fmt::Display for RwLockWriteGuard<'_, T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + Hash, R: Relax> Hash for RwLockWriteGuard<'_, T, R> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<T: ?Sized + PartialEq, R: Relax> PartialEq for RwLockWriteGuard<'_, T, R> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + Eq, R: Relax> Eq for RwLockWriteGuard<'_, T, R> {}
+
+impl<T: ?Sized + PartialOrd, R: Relax> PartialOrd for RwLockWriteGuard<'_, T, R> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: ?Sized + Ord, R: Relax> Ord for RwLockWriteGuard<'_, T, R> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: ?Sized, R: Relax> Borrow<T> for RwLockWriteGuard<'_, T, R> {
+    #[inline]
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized, R: Relax> BorrowMut<T> for RwLockWriteGuard<'_, T, R> {
+    #[inline]
+    fn borrow_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+impl<T: ?Sized, R: Relax> Drop for RwLockWriteGuard<'_, T, R> {
+    #[inline]
+    fn drop(&mut self) {
+        // SAFETY: The guard holds the exclusive write access so it is safe to
+        // release it when the guard gets dropped.
+        unsafe { self.rwlock.force_write_unlock() }
+    }
+}
+
+// Prevents the write guard from being moved to an other thread.
+#[cfg(not(feature = "send_guard"))]
+impl<T: ?Sized, R: Relax> !Send for RwLockWriteGuard<'_, T, R> {}
+
+// SAFETY: The write access can be released by any thread, sending the guard
+// gives the exclusive access to the data to another thread, which may then
+// share it with the other readers by downgrading.
+#[cfg(feature = "send_guard")]
+unsafe impl<T: ?Sized + Send + Sync, R: Relax> Send for RwLockWriteGuard<'_, T, R> {}
+
+/// Guard structure used to release the upgradeable read access when dropped.
+///
+/// This structure is created by [`upgradeable_read`](self::RwLock::upgradeable_read) and
+/// [`try_upgradeable_read`](self::RwLock::try_upgradeable_read) on [`RwLock`](self::RwLock).
+#[derive(Debug)]
+#[must_use = "if unused the lock is immediately released"]
+pub struct RwLockUpgradableReadGuard<'rwlock, T: ?Sized, R: Relax = Spin> {
+    rwlock: &'rwlock RwLock<T, R>,
+}
+
+impl<'rwlock, T: ?Sized, R: Relax> RwLockUpgradableReadGuard<'rwlock, T, R> {
+    /// Creates a new `RwLockUpgradableReadGuard<'rwlock, T, R>` from a given `RwLock<T, R>`.
+    #[inline]
+    const fn new(rwlock: &'rwlock RwLock<T, R>) -> Self {
+        Self { rwlock }
+    }
+
+    /// Upgrades the upgradeable read access to an exclusive write access,
+    /// blocking the thread until it is available.
+    ///
+    /// This function blocks the current thread by spinning
+    /// if any shared read access is held until it is released.
+    ///
+    /// This is an associated function that needs to be used as `RwLockUpgradableReadGuard::upgrade(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, RwLockUpgradableReadGuard};
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let upgradeable = rwlock.upgradeable_read();
+    /// let mut w = RwLockUpgradableReadGuard::upgrade(upgradeable);
+    /// *w = 2;
+    /// drop(w);
+    ///
+    /// assert_eq!(*rwlock.read(), 2);
+    /// ```
+    pub fn upgrade(guard: Self) -> RwLockWriteGuard<'rwlock, T, R> {
+        let rwlock = guard.rwlock;
+        // The upgradeable read access is now released by the write guard.
+        mem::forget(guard);
+
+        // The version is already made odd by `acquire_upgrade`.
+        Self::acquire_upgrade(rwlock);
+        RwLockWriteGuard { rwlock }
+    }
+
+    /// Temporarily upgrades the upgradeable read access to an exclusive write
+    /// access while calling the closure with a mutable reference to the data,
+    /// then downgrades it back before returning.
+    ///
+    /// This function blocks the current thread by spinning
+    /// if any shared read access is held until it is released. Unlike
+    /// [`upgrade`](self::RwLockUpgradableReadGuard::upgrade) the guard is not
+    /// consumed so it can still be used afterward, the upgradeable read
+    /// access is restored even if the closure panics.
+    ///
+    /// This is an associated function that needs to be used as `RwLockUpgradableReadGuard::with_upgraded(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, RwLockUpgradableReadGuard};
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let mut upgradeable = rwlock.upgradeable_read();
+    /// if *upgradeable < 2 {
+    ///     RwLockUpgradableReadGuard::with_upgraded(&mut upgradeable, |data| *data = 2);
+    /// }
+    /// assert_eq!(*upgradeable, 2);
+    /// ```
+    pub fn with_upgraded<U, F>(guard: &mut Self, f: F) -> U
+    where
+        F: FnOnce(&mut T) -> U,
+    {
+        // Downgrades the exclusive write access back to the upgradeable read
+        // access when dropped, including when the closure panics.
+        struct Downgrade<'rwlock, T: ?Sized, R: Relax> {
+            rwlock: &'rwlock RwLock<T, R>,
+        }
+
+        impl<T: ?Sized, R: Relax> Drop for Downgrade<'_, T, R> {
+            fn drop(&mut self) {
+                self.rwlock.end_write();
+                // Swaps the write bit for the upgradeable one, keeping the
+                // transient increments of the readers, see `acquire_read`.
+                self.rwlock
+                    .lock
+                    .fetch_sub(WRITER - UPGRADEABLE, Ordering::Release);
+                R::wake();
+            }
+        }
+
+        let rwlock = guard.rwlock;
+        Self::acquire_upgrade(rwlock);
+        let _downgrade = Downgrade { rwlock };
+
+        // SAFETY: The exclusive write access is held until `Downgrade` is
+        // dropped, and the guard can't be used while it is mutably borrowed.
+        f(rwlock.data.with_mut(|data| unsafe { &mut *data }))
+    }
+
+    /// Turns the upgradeable read access held on `rwlock` into an exclusive
+    /// write access, waiting for the shared read accesses to be released.
+    ///
+    /// The version is made odd like in `RwLockWriteGuard::new` since the data
+    /// may then be modified.
+    fn acquire_upgrade(rwlock: &RwLock<T, R>) {
+        loop {
+            // A spurious failure is retried by the loop, see `RwLock::write`.
+            if rwlock
+                .lock
+                .compare_exchange_weak(UPGRADEABLE, WRITER, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+            {
+                rwlock.begin_write();
+                return;
+            }
+
+            while rwlock.lock.load(Ordering::Relaxed) != UPGRADEABLE {
+                #[cfg(feature = "stats")]
+                rwlock.write_spins.fetch_add(1, Ordering::Relaxed);
+                R::relax();
+            }
+        }
+    }
+
+    /// Tries to upgrade the upgradeable read access to an exclusive write access.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// This is an associated function that needs to be used as `RwLockUpgradableReadGuard::try_upgrade(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Errors
+    /// If any shared read access is held the original guard is returned back.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{RwLock, RwLockUpgradableReadGuard};
+    ///
+    /// let rwlock = RwLock::new(1);
+    ///
+    /// let upgradeable = rwlock.upgradeable_read();
+    /// let r = rwlock.read();
+    /// let upgradeable = RwLockUpgradableReadGuard::try_upgrade(upgradeable).unwrap_err();
+    ///
+    /// drop(r);
+    /// assert!(RwLockUpgradableReadGuard::try_upgrade(upgradeable).is_ok());
+    /// ```
+    pub fn try_upgrade(guard: Self) -> Result<RwLockWriteGuard<'rwlock, T, R>, Self> {
+        if guard
+            .rwlock
+            .lock
+            .compare_exchange(UPGRADEABLE, WRITER, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            let rwlock = guard.rwlock;
+            // The upgradeable read access is now released by the write guard.
+            mem::forget(guard);
+
+            Ok(RwLockWriteGuard::new(rwlock))
+        } else {
+            Err(guard)
+        }
+    }
+}
+
+impl<T: ?Sized, R: Relax> Deref for RwLockUpgradableReadGuard<'_, T, R> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: An UpgradableReadGuard is only created when no WriteGuard
+        // is held and prevents any WriteGuard from being created so the data
+        // can't be modified while it is held so it is safe to get a reference
+        // to the data for the lifetime of the guard.
+        self.rwlock.data.with(|data| unsafe { &*data })
+    }
+}
+
+impl<T: ?Sized + fmt::Display, R: Relax> fmt::Display for RwLockUpgradableReadGuard<'_, T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + Hash, R: Relax> Hash for RwLockUpgradableReadGuard<'_, T, R> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<T: ?Sized + PartialEq, R: Relax> PartialEq for RwLockUpgradableReadGuard<'_, T, R> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + Eq, R: Relax> Eq for RwLockUpgradableReadGuard<'_, T, R> {}
+
+impl<T: ?Sized + PartialOrd, R: Relax> PartialOrd for RwLockUpgradableReadGuard<'_, T, R> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: ?Sized + Ord, R: Relax> Ord for RwLockUpgradableReadGuard<'_, T, R> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: ?Sized, R: Relax> Borrow<T> for RwLockUpgradableReadGuard<'_, T, R> {
+    #[inline]
+    fn borrow(&self) -> &T {
+        self
     }
 }
 
-impl<T> Drop for RwLockWriteGuard<'_, T> {
+impl<T: ?Sized, R: Relax> Drop for RwLockUpgradableReadGuard<'_, T, R> {
     #[inline]
     fn drop(&mut self) {
-        // There could only be one WriteGuard and no other guards
-        // so we can directly store 0.
-        self.rwlock.lock.store(0, Ordering::Release);
+        self.rwlock.lock.fetch_sub(UPGRADEABLE, Ordering::Release);
+        R::wake();
     }
 }
 
-// Prevents the write guard from being moved to an other thread.
-impl<T> !Send for RwLockWriteGuard<'_, T> {}
+// Prevents the upgradeable read guard from being moved to an other thread.
+#[cfg(not(feature = "send_guard"))]
+impl<T: ?Sized, R: Relax> !Send for RwLockUpgradableReadGuard<'_, T, R> {}
+
+// SAFETY: The upgradeable read access can be released by any thread, sending
+// the guard shares the data with another thread which may upgrade it to an
+// exclusive access.
+#[cfg(feature = "send_guard")]
+unsafe impl<T: ?Sized + Send + Sync, R: Relax> Send for RwLockUpgradableReadGuard<'_, T, R> {}
+
+/// Guard structure referencing a component of the data of a [`RwLock`](self::RwLock)
+/// used to release the shared read access when dropped.
+///
+/// This structure is created by [`RwLockReadGuard::map`](self::RwLockReadGuard::map).
+#[derive(Debug)]
+#[must_use = "if unused the lock is immediately released"]
+pub struct MappedRwLockReadGuard<'rwlock, T: ?Sized> {
+    // Lock of the rwlock to release when dropped.
+    lock: &'rwlock AtomicUsize,
+    // Component of the locked data.
+    data: &'rwlock T,
+    // Wakes up the threads spinning on the lock once it is released.
+    wake: fn(),
+}
+
+impl<'rwlock, T: ?Sized> MappedRwLockReadGuard<'rwlock, T> {
+    /// Creates a new `MappedRwLockReadGuard<'rwlock, T>` from a held shared
+    /// read access and a component of its data.
+    #[inline]
+    const fn new(lock: &'rwlock AtomicUsize, data: &'rwlock T, wake: fn()) -> Self {
+        Self { lock, data, wake }
+    }
+}
+
+impl<T: ?Sized> Deref for MappedRwLockReadGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.data
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for MappedRwLockReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + Hash> Hash for MappedRwLockReadGuard<'_, T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for MappedRwLockReadGuard<'_, T> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for MappedRwLockReadGuard<'_, T> {}
+
+impl<T: ?Sized + PartialOrd> PartialOrd for MappedRwLockReadGuard<'_, T> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: ?Sized + Ord> Ord for MappedRwLockReadGuard<'_, T> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: ?Sized> Borrow<T> for MappedRwLockReadGuard<'_, T> {
+    #[inline]
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> Drop for MappedRwLockReadGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.fetch_sub(READER, Ordering::Release);
+        (self.wake)();
+    }
+}
+
+// Prevents the mapped read guard from being moved to an other thread.
+#[cfg(not(feature = "send_guard"))]
+impl<T: ?Sized> !Send for MappedRwLockReadGuard<'_, T> {}
+
+// SAFETY: The read access can be released by any thread, sending the guard
+// shares the data with another thread like sending a reference.
+#[cfg(feature = "send_guard")]
+unsafe impl<T: ?Sized + Sync> Send for MappedRwLockReadGuard<'_, T> {}
+
+/// Guard structure referencing a component of the data of a [`RwLock`](self::RwLock)
+/// used to release the exclusive write access when dropped.
+///
+/// This structure is created by [`RwLockWriteGuard::map`](self::RwLockWriteGuard::map).
+#[derive(Debug)]
+#[must_use = "if unused the lock is immediately released"]
+pub struct MappedRwLockWriteGuard<'rwlock, T: ?Sized> {
+    // Lock of the rwlock to release when dropped.
+    lock: &'rwlock AtomicUsize,
+    // Version of the rwlock to make even again when dropped.
+    version: &'rwlock AtomicUsize,
+    // Component of the locked data.
+    data: *mut T,
+    // Wakes up the threads spinning on the lock once it is released.
+    wake: fn(),
+    _marker: PhantomData<&'rwlock mut T>,
+}
+
+impl<'rwlock, T: ?Sized> MappedRwLockWriteGuard<'rwlock, T> {
+    /// Creates a new `MappedRwLockWriteGuard<'rwlock, T>` from a held exclusive
+    /// write access and a component of its data.
+    #[inline]
+    const fn new(
+        lock: &'rwlock AtomicUsize,
+        version: &'rwlock AtomicUsize,
+        data: &'rwlock mut T,
+        wake: fn(),
+    ) -> Self {
+        Self {
+            lock,
+            version,
+            data,
+            wake,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for MappedRwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The pointer comes from a mutable reference to the locked data
+        // which stays valid as long as the exclusive write access is held.
+        unsafe { &*self.data }
+    }
+}
+
+impl<T: ?Sized> DerefMut for MappedRwLockWriteGuard<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: The pointer comes from a mutable reference to the locked data
+        // which stays valid as long as the exclusive write access is held.
+        unsafe { &mut *self.data }
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for MappedRwLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + Hash> Hash for MappedRwLockWriteGuard<'_, T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for MappedRwLockWriteGuard<'_, T> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for MappedRwLockWriteGuard<'_, T> {}
+
+impl<T: ?Sized + PartialOrd> PartialOrd for MappedRwLockWriteGuard<'_, T> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: ?Sized + Ord> Ord for MappedRwLockWriteGuard<'_, T> {
+    /// Compares the locked data, not the identity of the locks.
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: ?Sized> Borrow<T> for MappedRwLockWriteGuard<'_, T> {
+    #[inline]
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> BorrowMut<T> for MappedRwLockWriteGuard<'_, T> {
+    #[inline]
+    fn borrow_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+impl<T: ?Sized> Drop for MappedRwLockWriteGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // Publishes the writes to the optimistic reads, see `RwLock::end_write`.
+        self.version.fetch_add(1, Ordering::Release);
+        // Only clears the write bit since readers may have transiently
+        // incremented the reader count, see `RwLock::acquire_read`.
+        self.lock.fetch_sub(WRITER, Ordering::Release);
+        (self.wake)();
+    }
+}
+
+// Prevents the mapped write guard from being moved to an other thread.
+#[cfg(not(feature = "send_guard"))]
+impl<T: ?Sized> !Send for MappedRwLockWriteGuard<'_, T> {}
+
+// SAFETY: The write access can be released by any thread, sending the guard
+// gives the exclusive access to the data to another thread.
+#[cfg(feature = "send_guard")]
+unsafe impl<T: ?Sized + Send> Send for MappedRwLockWriteGuard<'_, T> {}
+
+unsafe impl<T: ?Sized + Sync> Sync for MappedRwLockWriteGuard<'_, T> {}
+
+#[cfg(all(test, not(feature = "loom")))]
+mod tests {
+    extern crate std;
+
+    use core::sync::atomic::Ordering;
+    use core::time::Duration;
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::{
+        RwLock, RwLockUpgradableReadGuard, RwLockWriteGuard, MAX_READERS, READER, UPGRADEABLE,
+        WRITER,
+    };
+
+    fn state<T>(rwlock: &RwLock<T>) -> usize {
+        rwlock.lock.load(Ordering::Relaxed)
+    }
+
+    #[test]
+    fn read_transitions() {
+        let rwlock = RwLock::new(0);
+
+        let r1 = rwlock.read();
+        let r2 = rwlock.try_read().unwrap();
+        assert_eq!(state(&rwlock), 2 * READER);
+
+        drop(r1);
+        assert_eq!(state(&rwlock), READER);
+        drop(r2);
+        assert_eq!(state(&rwlock), 0);
+    }
+
+    #[test]
+    fn write_transitions() {
+        let rwlock = RwLock::new(0);
+
+        let w = rwlock.write();
+        assert_eq!(state(&rwlock), WRITER);
+        assert!(rwlock.try_read().is_none());
+        assert!(rwlock.try_upgradeable_read().is_none());
+        assert!(rwlock.try_write().is_none());
+        // The failed attempts left the write state untouched.
+        assert_eq!(state(&rwlock), WRITER);
+
+        let r = RwLockWriteGuard::downgrade(w);
+        assert_eq!(state(&rwlock), READER);
+        drop(r);
+        assert_eq!(state(&rwlock), 0);
+    }
+
+    #[test]
+    fn read_and_upgradeable_coexist() {
+        let rwlock = RwLock::new(0);
+
+        let r1 = rwlock.read();
+        let upgradeable = rwlock.upgradeable_read();
+        let r2 = rwlock.read();
+        assert_eq!(state(&rwlock), UPGRADEABLE | (2 * READER));
+        assert_eq!(rwlock.reader_count(), 2);
+
+        // A single upgradeable read access can be held at a time.
+        assert!(rwlock.try_upgradeable_read().is_none());
+        assert!(rwlock.try_write().is_none());
+        assert_eq!(state(&rwlock), UPGRADEABLE | (2 * READER));
+
+        drop(r1);
+        drop(upgradeable);
+        assert_eq!(state(&rwlock), READER);
+        drop(r2);
+        assert_eq!(state(&rwlock), 0);
+    }
+
+    #[test]
+    fn upgrade_blocked_by_readers() {
+        let rwlock = RwLock::new(0);
+
+        let upgradeable = rwlock.upgradeable_read();
+        let r = rwlock.read();
+        let upgradeable = RwLockUpgradableReadGuard::try_upgrade(upgradeable).unwrap_err();
+        assert_eq!(state(&rwlock), UPGRADEABLE | READER);
+
+        drop(r);
+        let w = RwLockUpgradableReadGuard::try_upgrade(upgradeable).unwrap();
+        assert_eq!(state(&rwlock), WRITER);
+        assert!(rwlock.is_write_locked());
+        assert_eq!(rwlock.reader_count(), 0);
+
+        drop(w);
+        assert_eq!(state(&rwlock), 0);
+    }
+
+    #[test]
+    fn write_release_keeps_transient_readers() {
+        let rwlock = RwLock::new(0);
+
+        // Readers incremented the count before noticing the write access.
+        let w = rwlock.write();
+        rwlock.lock.fetch_add(2 * READER, Ordering::Relaxed);
+        let r = RwLockWriteGuard::downgrade(w);
+        assert_eq!(state(&rwlock), 3 * READER);
+
+        // They roll back their increments.
+        rwlock.lock.fetch_sub(2 * READER, Ordering::Relaxed);
+        drop(r);
+        assert_eq!(state(&rwlock), 0);
+
+        let w = rwlock.write();
+        rwlock.lock.fetch_add(READER, Ordering::Relaxed);
+        drop(w);
+        assert_eq!(state(&rwlock), READER);
+    }
+
+    #[test]
+    fn try_read_at_max_readers() {
+        let mut rwlock = RwLock::new(0);
+
+        *rwlock.lock.get_mut() = MAX_READERS - 1;
+        let rguard = rwlock.try_read().unwrap();
+        assert!(rwlock.try_read().is_none());
+
+        drop(rguard);
+        assert!(rwlock.try_read().is_some());
+    }
+
+    #[test]
+    fn try_read_at_max_readers_with_upgradeable() {
+        let mut rwlock = RwLock::new(0);
+
+        *rwlock.lock.get_mut() = UPGRADEABLE | MAX_READERS;
+        assert!(rwlock.try_read().is_none());
+    }
+
+    #[test]
+    fn read_waits_at_max_readers() {
+        let mut rwlock = RwLock::new(0);
+
+        // Presets the count as if all the readers but two were held elsewhere.
+        *rwlock.lock.get_mut() = RwLock::<i32>::max_readers() - 2 * READER;
+        let rwlock = Arc::new(rwlock);
+        let r1 = rwlock.read();
+        let r2 = rwlock.read();
+        assert_eq!(rwlock.reader_count(), RwLock::<i32>::max_readers());
+        assert!(rwlock.try_read().is_none());
+        assert!(rwlock.try_write().is_none());
+
+        // A reader spins while the count is full instead of overflowing.
+        let r = Arc::clone(&rwlock);
+        let waiter = thread::spawn(move || *r.read());
+        thread::sleep(Duration::from_millis(10));
+        assert!(!waiter.is_finished());
+
+        drop(r1);
+        assert_eq!(waiter.join().unwrap(), 0);
+        drop(r2);
+
+        // Drains the preset readers, the writer can then acquire the lock.
+        rwlock
+            .lock
+            .fetch_sub(MAX_READERS - 2 * READER, Ordering::Relaxed);
+        *rwlock.write() = 1;
+        assert_eq!(*rwlock.read(), 1);
+    }
+
+    #[test]
+    fn read_waits_at_max_readers_with_upgradeable() {
+        let mut rwlock = RwLock::new(0);
+
+        *rwlock.lock.get_mut() = UPGRADEABLE | MAX_READERS;
+        assert!(rwlock.try_read().is_none());
+
+        // The read waits until a reader leaves, the count never carries into
+        // the upgradeable nor the write bit meanwhile.
+        let rwlock = Arc::new(rwlock);
+        let r = Arc::clone(&rwlock);
+        let waiter = thread::spawn(move || *r.read());
+        thread::sleep(Duration::from_millis(10));
+        assert!(!waiter.is_finished());
+        assert_eq!(state(&rwlock), UPGRADEABLE | MAX_READERS);
+        assert!(!rwlock.is_write_locked());
+
+        rwlock.lock.fetch_sub(READER, Ordering::Relaxed);
+        assert_eq!(waiter.join().unwrap(), 0);
+        assert_eq!(state(&rwlock), UPGRADEABLE | (MAX_READERS - READER));
+    }
+
+    #[test]
+    fn read_up_to_max_readers() {
+        let mut rwlock = RwLock::new(0);
+
+        // The last reader fills the count without touching the other bits.
+        *rwlock.lock.get_mut() = MAX_READERS - READER;
+        let r = rwlock.read();
+        assert_eq!(state(&rwlock), MAX_READERS);
+        assert_eq!(rwlock.reader_count(), MAX_READERS);
+        assert!(!rwlock.is_write_locked());
+
+        // The failed attempts leave the count untouched.
+        assert!(rwlock.try_read().is_none());
+        assert!(rwlock.try_read_recursive().is_none());
+        assert!(rwlock.try_write().is_none());
+        assert_eq!(state(&rwlock), MAX_READERS);
+
+        // The upgradeable read access is a separate bit, not a reader.
+        let upgradeable = rwlock.try_upgradeable_read().unwrap();
+        assert_eq!(state(&rwlock), UPGRADEABLE | MAX_READERS);
+
+        drop(r);
+        assert_eq!(state(&rwlock), UPGRADEABLE | (MAX_READERS - READER));
+        drop(upgradeable);
+        assert_eq!(state(&rwlock), MAX_READERS - READER);
+        assert!(rwlock.try_read().is_some());
+    }
+}
@@ -0,0 +1,149 @@
+use crate::backoff::Backoff;
+use crate::sync::{AtomicIsize, Ordering};
+
+/// A counting semaphore.
+///
+/// This primitive limits the number of threads concurrently holding one of its
+/// permits, it is useful to limit the concurrent accesses to a pool of resources.
+#[derive(Debug)]
+pub struct Semaphore {
+    // Number of permits currently available.
+    count: AtomicIsize,
+}
+
+impl Semaphore {
+    const_fn! {
+        /// Creates a new `Semaphore` with the given number of permits.
+        ///
+        /// # Panics
+        /// Panics if `permits` is greater than `isize::MAX`.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::Semaphore;
+        ///
+        /// let semaphore = Semaphore::new(2);
+        /// ```
+        #[inline]
+        #[must_use]
+        #[allow(clippy::cast_possible_wrap)]
+        pub const fn new(permits: usize) -> Self {
+            assert!(permits <= isize::MAX.unsigned_abs(), "too many permits");
+
+            Self {
+                // The assertion above makes sure the cast does not wrap.
+                count: AtomicIsize::new(permits as isize),
+            }
+        }
+    }
+
+    /// Acquires a permit, blocking the current thread until one is available.
+    ///
+    /// This function blocks the current thread by spinning until a permit
+    /// is released.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Semaphore;
+    /// use std::sync::Arc;
+    /// use std::thread;
+    ///
+    /// let semaphore = Arc::new(Semaphore::new(1));
+    /// let s = Arc::clone(&semaphore);
+    ///
+    /// thread::spawn(move || {
+    ///     let _permit = s.acquire();
+    /// }).join().expect("thread::spawn failed");
+    /// assert_eq!(semaphore.available_permits(), 1);
+    /// ```
+    #[must_use]
+    pub fn acquire(&self) -> SemaphorePermit<'_> {
+        let mut backoff = Backoff::new();
+        loop {
+            if let Some(permit) = self.try_acquire() {
+                return permit;
+            }
+
+            while self.count.load(Ordering::Relaxed) <= 0 {
+                backoff.spin();
+            }
+        }
+    }
+
+    /// Tries to acquire a permit. If no permit is available returns `None`.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Semaphore;
+    ///
+    /// let semaphore = Semaphore::new(1);
+    ///
+    /// let permit = semaphore.try_acquire().unwrap();
+    /// assert!(semaphore.try_acquire().is_none());
+    /// ```
+    #[must_use]
+    pub fn try_acquire(&self) -> Option<SemaphorePermit<'_>> {
+        // Only decrements the count when a permit is available
+        // so that it never goes negative.
+        let mut count = self.count.load(Ordering::Relaxed);
+        while count > 0 {
+            match self.count.compare_exchange(
+                count,
+                count - 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(SemaphorePermit::new(self)),
+                Err(current) => count = current,
+            }
+        }
+
+        None
+    }
+
+    /// Returns the number of permits currently available.
+    ///
+    /// Note that the number may already be outdated when it is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::Semaphore;
+    ///
+    /// let semaphore = Semaphore::new(2);
+    ///
+    /// let permit = semaphore.acquire();
+    /// assert_eq!(semaphore.available_permits(), 1);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn available_permits(&self) -> usize {
+        self.count.load(Ordering::Relaxed).unsigned_abs()
+    }
+}
+
+/// This structure is created by calling [`acquire`](self::Semaphore::acquire)
+/// or [`try_acquire`](self::Semaphore::try_acquire) on [`Semaphore`](self::Semaphore).
+///
+/// The permit is released when it is dropped.
+#[derive(Debug)]
+pub struct SemaphorePermit<'semaphore> {
+    semaphore: &'semaphore Semaphore,
+}
+
+impl<'semaphore> SemaphorePermit<'semaphore> {
+    /// Creates a `SemaphorePermit<'semaphore>` of a given Semaphore.
+    #[inline]
+    #[must_use]
+    const fn new(semaphore: &'semaphore Semaphore) -> Self {
+        Self { semaphore }
+    }
+}
+
+impl Drop for SemaphorePermit<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.semaphore.count.fetch_add(1, Ordering::Release);
+    }
+}
@@ -0,0 +1,157 @@
+use core::ptr;
+
+use crate::backoff::Backoff;
+use crate::sync::{fence, AtomicUsize, Ordering, UnsafeCell};
+
+/// A sequence lock for small, frequently read and rarely written data.
+///
+/// Unlike an [`RwLock`](crate::RwLock) the readers never write to the lock,
+/// they copy the data out and retry if a write happened in the meantime. This
+/// is why `T` must be [`Copy`]: a reader may copy a value which is being
+/// written, it is only returned once the lock made sure it is not torn.
+///
+/// The writers are serialized, while a reader spins as long as a write is in
+/// progress so readers may spin for a long time under frequent writes.
+///
+/// # Examples
+/// ```
+/// use spinlock::SeqLock;
+///
+/// static OFFSET: SeqLock<(u64, u64)> = SeqLock::new((0, 0));
+///
+/// OFFSET.write((1, 2));
+/// assert_eq!(OFFSET.read(), (1, 2));
+/// ```
+#[derive(Debug)]
+pub struct SeqLock<T: Copy> {
+    // Sequence number incremented before and after each write, it is odd
+    // while a write is in progress.
+    sequence: AtomicUsize,
+    data: UnsafeCell<T>,
+}
+
+impl<T: Copy> SeqLock<T> {
+    const_fn! {
+        /// Creates a new `SeqLock<T>` containing the given value.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::SeqLock;
+        ///
+        /// let seqlock = SeqLock::new(0);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn new(value: T) -> Self {
+            Self {
+                sequence: AtomicUsize::new(0),
+                data: UnsafeCell::new(value),
+            }
+        }
+    }
+
+    /// Returns a copy of the value.
+    ///
+    /// This function blocks the current thread by spinning while a write is in
+    /// progress, and retries if a write happened while the value was copied.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::SeqLock;
+    ///
+    /// let seqlock = SeqLock::new(42);
+    /// assert_eq!(seqlock.read(), 42);
+    /// ```
+    #[must_use]
+    pub fn read(&self) -> T {
+        let mut backoff = Backoff::new();
+        loop {
+            let sequence = self.sequence.load(Ordering::Acquire);
+            if sequence & 1 == 0 {
+                // SAFETY: The value may be concurrently written, it is read
+                // with a volatile read and discarded below if it was.
+                let value = self.data.with(|data| unsafe { ptr::read_volatile(data) });
+
+                // Orders the read of the value before the check of the sequence.
+                fence(Ordering::Acquire);
+                if self.sequence.load(Ordering::Relaxed) == sequence {
+                    return value;
+                }
+            }
+
+            backoff.spin();
+        }
+    }
+
+    /// Replaces the value.
+    ///
+    /// This function blocks the current thread by spinning while another
+    /// write is in progress.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::SeqLock;
+    ///
+    /// let seqlock = SeqLock::new(0);
+    ///
+    /// seqlock.write(42);
+    /// assert_eq!(seqlock.read(), 42);
+    /// ```
+    pub fn write(&self, value: T) {
+        let mut backoff = Backoff::new();
+        let mut sequence = self.sequence.load(Ordering::Relaxed);
+        loop {
+            // Makes the sequence odd to exclude the other writers.
+            if sequence & 1 == 0 {
+                match self.sequence.compare_exchange_weak(
+                    sequence,
+                    sequence.wrapping_add(1),
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => break,
+                    Err(current) => sequence = current,
+                }
+            } else {
+                backoff.spin();
+                sequence = self.sequence.load(Ordering::Relaxed);
+            }
+        }
+
+        // Orders the odd sequence before the write of the value.
+        fence(Ordering::Release);
+
+        // SAFETY: The odd sequence excludes the other writers, and the readers
+        // discard the value they read concurrently.
+        self.data
+            .with_mut(|data| unsafe { ptr::write_volatile(data, value) });
+
+        store_release!(self.sequence, sequence.wrapping_add(2));
+    }
+
+    /// Consumes this `SeqLock`, returning the underlying value.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::SeqLock;
+    ///
+    /// let seqlock = SeqLock::new(42);
+    /// assert_eq!(seqlock.into_inner(), 42);
+    /// ```
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: Copy + Default> Default for SeqLock<T> {
+    /// Creates a new `SeqLock<T>` containing the default value of `T`.
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+// SAFETY: It is safe to impl Sync since the writers are serialized and the
+// readers only return values which were not concurrently written.
+unsafe impl<T: Copy + Send> Sync for SeqLock<T> {}
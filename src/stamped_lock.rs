@@ -0,0 +1,663 @@
+use core::ops::{Deref, DerefMut};
+use core::{mem, ptr};
+
+use crate::backoff::Backoff;
+use crate::sync::{fence, AtomicU64, Ordering, UnsafeCell};
+
+// Number of low bits of the state counting the shared read accesses.
+const READER_BITS: u32 = 24;
+// Increment of the state for a shared read access.
+const READER: u64 = 1;
+// Maximum number of shared read accesses that can be held at the same time,
+// it is also the mask of the reader count.
+const MAX_READERS: u64 = (1 << READER_BITS) - 1;
+// Bit of the state set when the exclusive write access is held. Releasing it
+// adds it again so that the carry increments the version in the upper bits.
+const WRITER: u64 = 1 << READER_BITS;
+
+/// A reader-writer lock with optimistic reads, in the manner of the
+/// `StampedLock` of Java.
+///
+/// The state of the lock packs in a single `AtomicU64` the shared read
+/// accesses held, the exclusive write access and a version incremented each
+/// time a write access is released. Besides the usual read and write
+/// accesses, returned as the [`ReadStamp`] and [`WriteStamp`] guards, a reader
+/// can take an optimistic [`Stamp`] of the version which doesn't write to the
+/// lock at all. The data is then read without any access, and the stamp is
+/// validated afterwards: what was read is only consistent if no write access
+/// was acquired in the meantime.
+///
+/// Reading the data without any access is unsafe since a writer may modify it
+/// concurrently, see [`read_optimistic`](self::StampedLock::read_optimistic)
+/// for the contract. It suits small and plain data frequently read, for
+/// which retrying a read is cheaper than writing to the lock.
+///
+/// The lock is not reentrant, and a continuous stream of readers can starve
+/// the writers.
+///
+/// # Examples
+/// ```
+/// use spinlock::StampedLock;
+///
+/// let lock = StampedLock::new((0_u32, 0_u32));
+///
+/// *lock.write() = (1, 2);
+///
+/// let (x, y) = match lock.try_optimistic_read() {
+///     // SAFETY: The copy is discarded unless the stamp validates.
+///     Some(stamp) => match unsafe { lock.read_optimistic() } {
+///         point if lock.validate(stamp) => point,
+///         _ => *lock.read(),
+///     },
+///     None => *lock.read(),
+/// };
+/// assert_eq!(x + y, 3);
+/// ```
+#[derive(Debug)]
+pub struct StampedLock<T: ?Sized> {
+    // The state of the lock, from the upper to the lower bits:
+    // - the version, incremented by the release of each write access
+    // - the `WRITER` bit, set when the exclusive write access is held
+    // - the count of the shared read accesses held
+    state: AtomicU64,
+
+    // Inner data contained in the lock, last so that it can be unsized.
+    data: UnsafeCell<T>,
+}
+
+/// An optimistic stamp of a [`StampedLock`], recording its version.
+///
+/// It is returned by [`try_optimistic_read`](self::StampedLock::try_optimistic_read)
+/// and checked with [`validate`](self::StampedLock::validate). It doesn't hold
+/// any access to the lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Stamp(u64);
+
+impl<T> StampedLock<T> {
+    const_fn! {
+        /// Creates a new `StampedLock<T>` which is unlocked.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::StampedLock;
+        ///
+        /// let lock = StampedLock::new(0);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn new(data: T) -> Self {
+            Self {
+                state: AtomicU64::new(0),
+                data: UnsafeCell::new(data),
+            }
+        }
+    }
+
+    /// Consumes this `StampedLock`, returning the underlying data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::StampedLock;
+    ///
+    /// let lock = StampedLock::new(42);
+    /// assert_eq!(lock.into_inner(), 42);
+    /// ```
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+
+    /// Returns a copy of the data without acquiring any access, which may be
+    /// torn by a concurrent write.
+    ///
+    /// # Safety
+    /// The copy must be discarded without being used unless
+    /// [`validate`](self::StampedLock::validate) returns `true` after it, for
+    /// a stamp taken before it. `T` must also be valid for any mix of the
+    /// bytes of its values, since a concurrent write can leave a torn copy
+    /// which must be safe to discard, which is the case for plain data such as
+    /// integers.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::StampedLock;
+    ///
+    /// let lock = StampedLock::new(1);
+    ///
+    /// let stamp = lock.try_optimistic_read().unwrap();
+    /// // SAFETY: The copy is only used once the stamp is validated.
+    /// let value = unsafe { lock.read_optimistic() };
+    /// assert!(lock.validate(stamp));
+    /// assert_eq!(value, 1);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub unsafe fn read_optimistic(&self) -> T
+    where
+        T: Copy,
+    {
+        // SAFETY: The data is read with a volatile read, the caller discards
+        // it unless no write happened concurrently.
+        self.data.with(|data| unsafe { ptr::read_volatile(data) })
+    }
+}
+
+impl<T: ?Sized> StampedLock<T> {
+    /// Returns a stamp of the current version, or `None` if the exclusive
+    /// write access is held.
+    ///
+    /// No access is acquired: the data read after it may be concurrently
+    /// written and it must only be trusted once the stamp is validated with
+    /// [`validate`](self::StampedLock::validate).
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::StampedLock;
+    ///
+    /// let lock = StampedLock::new(1);
+    ///
+    /// assert!(lock.try_optimistic_read().is_some());
+    /// let writer = lock.write();
+    /// assert!(lock.try_optimistic_read().is_none());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn try_optimistic_read(&self) -> Option<Stamp> {
+        let state = self.state.load(Ordering::Acquire);
+        (state & WRITER == 0).then_some(Stamp(state & !MAX_READERS))
+    }
+
+    /// Returns `true` if no exclusive write access was acquired since the
+    /// stamp was taken, in which case the data read since is consistent.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::StampedLock;
+    ///
+    /// let lock = StampedLock::new(1);
+    ///
+    /// let stamp = lock.try_optimistic_read().unwrap();
+    /// assert!(lock.validate(stamp));
+    ///
+    /// *lock.write() = 2;
+    /// assert!(!lock.validate(stamp));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn validate(&self, stamp: Stamp) -> bool {
+        // Orders the reads of the data before the check of the version.
+        fence(Ordering::Acquire);
+        self.state.load(Ordering::Relaxed) & !MAX_READERS == stamp.0
+    }
+
+    /// Acquires the lock with shared read access, blocking the current thread
+    /// until it is available.
+    ///
+    /// If the maximum number of shared read accesses is already held, that is
+    /// `2^24 - 1` accesses, it waits for one of them to be released.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::StampedLock;
+    ///
+    /// let lock = StampedLock::new(1);
+    ///
+    /// let r1 = lock.read();
+    /// let r2 = lock.read();
+    /// assert_eq!(*r1 + *r2, 2);
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn read(&self) -> ReadStamp<'_, T> {
+        let mut backoff = Backoff::new();
+        loop {
+            if let Some(stamp) = self.try_read() {
+                return stamp;
+            }
+
+            backoff.spin();
+        }
+    }
+
+    /// Tries to acquire the lock with shared read access. If the lock is not
+    /// available returns `None`.
+    ///
+    /// It also returns `None` if the maximum number of shared read accesses is
+    /// already held. This function does not block the current thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::StampedLock;
+    ///
+    /// let lock = StampedLock::new(1);
+    ///
+    /// let reader = lock.try_read().unwrap();
+    /// assert!(lock.try_write().is_none());
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_read(&self) -> Option<ReadStamp<'_, T>> {
+        let mut state = self.state.load(Ordering::Relaxed);
+        while state & WRITER == 0 && state & MAX_READERS != MAX_READERS {
+            match self.state.compare_exchange_weak(
+                state,
+                state + READER,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(ReadStamp { lock: self }),
+                Err(current) => state = current,
+            }
+        }
+
+        None
+    }
+
+    /// Acquires the lock with exclusive write access, blocking the current
+    /// thread until it is available.
+    ///
+    /// Releasing the write access increments the version, which invalidates
+    /// the optimistic stamps taken before it.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::StampedLock;
+    ///
+    /// let lock = StampedLock::new(1);
+    ///
+    /// *lock.write() += 1;
+    /// assert_eq!(*lock.read(), 2);
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn write(&self) -> WriteStamp<'_, T> {
+        let mut backoff = Backoff::new();
+        loop {
+            if let Some(stamp) = self.try_write() {
+                return stamp;
+            }
+
+            backoff.spin();
+        }
+    }
+
+    /// Tries to acquire the lock with exclusive write access. If the lock is
+    /// not available returns `None`.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::StampedLock;
+    ///
+    /// let lock = StampedLock::new(1);
+    ///
+    /// let writer = lock.try_write().unwrap();
+    /// assert!(lock.try_read().is_none());
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_write(&self) -> Option<WriteStamp<'_, T>> {
+        let state = self.state.load(Ordering::Relaxed);
+        if state & (WRITER | MAX_READERS) != 0 {
+            return None;
+        }
+
+        self.state
+            .compare_exchange(state, state + WRITER, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| WriteStamp::new(self))
+    }
+
+    /// Tries to convert an optimistic stamp into a shared read access. If a
+    /// write access was acquired since the stamp was taken returns `None`.
+    ///
+    /// The data read since the stamp was taken stays consistent while the
+    /// returned read access is held.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::StampedLock;
+    ///
+    /// let lock = StampedLock::new(1);
+    ///
+    /// let stamp = lock.try_optimistic_read().unwrap();
+    /// let reader = lock.try_convert_to_read(stamp).unwrap();
+    /// drop(reader);
+    ///
+    /// *lock.write() = 2;
+    /// assert!(lock.try_convert_to_read(stamp).is_none());
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_convert_to_read(&self, stamp: Stamp) -> Option<ReadStamp<'_, T>> {
+        let mut state = self.state.load(Ordering::Relaxed);
+        while state & !MAX_READERS == stamp.0 && state & MAX_READERS != MAX_READERS {
+            match self.state.compare_exchange_weak(
+                state,
+                state + READER,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(ReadStamp { lock: self }),
+                Err(current) => state = current,
+            }
+        }
+
+        None
+    }
+
+    /// Tries to convert an optimistic stamp into an exclusive write access. If
+    /// a write access was acquired since the stamp was taken, or if any shared
+    /// read access is held, returns `None`.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::StampedLock;
+    ///
+    /// let lock = StampedLock::new(1);
+    ///
+    /// let stamp = lock.try_optimistic_read().unwrap();
+    /// *lock.try_convert_to_write(stamp).unwrap() = 2;
+    ///
+    /// // The write access invalidated the stamp.
+    /// assert!(lock.try_convert_to_write(stamp).is_none());
+    /// ```
+    #[must_use = "if unused the lock is immediately released"]
+    pub fn try_convert_to_write(&self, stamp: Stamp) -> Option<WriteStamp<'_, T>> {
+        self.state
+            .compare_exchange(
+                stamp.0,
+                stamp.0 + WRITER,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .ok()
+            .map(|_| WriteStamp::new(self))
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the lock mutably, no other reference to it can
+    /// exist so no locking is needed.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::StampedLock;
+    ///
+    /// let mut lock = StampedLock::new(1);
+    /// *lock.get_mut() = 42;
+    /// assert_eq!(lock.into_inner(), 42);
+    /// ```
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        // SAFETY: The lock is borrowed mutably so no access can be held and it
+        // is safe to get a mutable reference to the data.
+        self.data.with_mut(|data| unsafe { &mut *data })
+    }
+}
+
+impl<T: Default> Default for StampedLock<T> {
+    /// Creates a new `StampedLock<T>` containing the default value of `T`.
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+// SAFETY: The data is only accessed through the shared read accesses, which
+// exclude the writers, or through the exclusive write access. The optimistic
+// reads are unsafe and discard what they read concurrently with a writer.
+unsafe impl<T: ?Sized + Send + Sync> Sync for StampedLock<T> {}
+
+/// Guard structure holding a shared read access to a [`StampedLock`],
+/// released when dropped.
+///
+/// This structure is created by [`read`](self::StampedLock::read),
+/// [`try_read`](self::StampedLock::try_read) and
+/// [`try_convert_to_read`](self::StampedLock::try_convert_to_read) on
+/// [`StampedLock`](self::StampedLock).
+#[derive(Debug)]
+#[must_use = "if unused the lock is immediately released"]
+pub struct ReadStamp<'lock, T: ?Sized> {
+    lock: &'lock StampedLock<T>,
+}
+
+impl<'lock, T: ?Sized> ReadStamp<'lock, T> {
+    /// Releases the shared read access, returning an optimistic stamp of the
+    /// current version which validates until a write access is acquired.
+    ///
+    /// This is an associated function that needs to be used as `ReadStamp::into_optimistic(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{ReadStamp, StampedLock};
+    ///
+    /// let lock = StampedLock::new(1);
+    ///
+    /// let stamp = ReadStamp::into_optimistic(lock.read());
+    /// assert!(lock.validate(stamp));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn into_optimistic(guard: Self) -> Stamp {
+        let lock = guard.lock;
+        mem::forget(guard);
+
+        let state = lock.state.fetch_sub(READER, Ordering::Release);
+        Stamp(state & !MAX_READERS)
+    }
+
+    /// Tries to convert the shared read access into an exclusive write access,
+    /// which succeeds only if it is the only shared read access held.
+    /// Otherwise the read guard is returned back.
+    ///
+    /// This is an associated function that needs to be used as `ReadStamp::try_upgrade(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Errors
+    /// Returns the read guard if other shared read accesses are held.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{ReadStamp, StampedLock};
+    ///
+    /// let lock = StampedLock::new(1);
+    ///
+    /// let reader = lock.read();
+    /// let mut writer = ReadStamp::try_upgrade(reader).unwrap();
+    /// *writer = 2;
+    /// ```
+    #[inline]
+    pub fn try_upgrade(guard: Self) -> Result<WriteStamp<'lock, T>, Self> {
+        let state = guard.lock.state.load(Ordering::Relaxed);
+        if state & MAX_READERS != READER {
+            return Err(guard);
+        }
+
+        match guard.lock.state.compare_exchange(
+            state,
+            state - READER + WRITER,
+            Ordering::Acquire,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => {
+                let lock = guard.lock;
+                mem::forget(guard);
+                Ok(WriteStamp::new(lock))
+            }
+            Err(_) => Err(guard),
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for ReadStamp<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The shared read access excludes the writers so the data
+        // can't be modified while the guard is held.
+        self.lock.data.with(|data| unsafe { &*data })
+    }
+}
+
+impl<T: ?Sized> Drop for ReadStamp<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.state.fetch_sub(READER, Ordering::Release);
+    }
+}
+
+// Prevents the read guard from being moved to an other thread.
+#[cfg(not(feature = "send_guard"))]
+impl<T: ?Sized> !Send for ReadStamp<'_, T> {}
+
+/// Guard structure holding the exclusive write access to a [`StampedLock`],
+/// released when dropped.
+///
+/// This structure is created by [`write`](self::StampedLock::write),
+/// [`try_write`](self::StampedLock::try_write) and
+/// [`try_convert_to_write`](self::StampedLock::try_convert_to_write) on
+/// [`StampedLock`](self::StampedLock). Releasing it increments the version of
+/// the lock.
+#[derive(Debug)]
+#[must_use = "if unused the lock is immediately released"]
+pub struct WriteStamp<'lock, T: ?Sized> {
+    lock: &'lock StampedLock<T>,
+}
+
+impl<'lock, T: ?Sized> WriteStamp<'lock, T> {
+    /// Creates a new `WriteStamp<'lock, T>` once the write access is acquired.
+    #[inline]
+    fn new(lock: &'lock StampedLock<T>) -> Self {
+        // Orders the `WRITER` bit before the writes to the data, so that an
+        // optimistic read of these writes fails to validate.
+        fence(Ordering::Release);
+        Self { lock }
+    }
+
+    /// Converts the exclusive write access into a shared read access,
+    /// without letting any writer in.
+    ///
+    /// The version is incremented as when the write access is released.
+    ///
+    /// This is an associated function that needs to be used as `WriteStamp::downgrade(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{StampedLock, WriteStamp};
+    ///
+    /// let lock = StampedLock::new(1);
+    ///
+    /// let mut writer = lock.write();
+    /// *writer = 2;
+    /// let reader = WriteStamp::downgrade(writer);
+    /// assert_eq!(*reader, 2);
+    /// assert!(lock.try_write().is_none());
+    /// ```
+    #[inline]
+    pub fn downgrade(guard: Self) -> ReadStamp<'lock, T> {
+        let lock = guard.lock;
+        mem::forget(guard);
+
+        // Adding the write bit clears it and increments the version.
+        lock.state.fetch_add(WRITER + READER, Ordering::Release);
+        ReadStamp { lock }
+    }
+
+    /// Releases the exclusive write access, returning an optimistic stamp of
+    /// the new version.
+    ///
+    /// This is an associated function that needs to be used as `WriteStamp::into_optimistic(...)`
+    /// so that it does not conflict with a method of the same name on the inner data.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::{StampedLock, WriteStamp};
+    ///
+    /// let lock = StampedLock::new(1);
+    ///
+    /// let mut writer = lock.write();
+    /// *writer = 2;
+    /// let stamp = WriteStamp::into_optimistic(writer);
+    /// assert!(lock.validate(stamp));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn into_optimistic(guard: Self) -> Stamp {
+        let lock = guard.lock;
+        mem::forget(guard);
+
+        let state = lock.state.fetch_add(WRITER, Ordering::Release);
+        Stamp(state.wrapping_add(WRITER) & !MAX_READERS)
+    }
+}
+
+impl<T: ?Sized> Deref for WriteStamp<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The exclusive write access is held so no one else can access
+        // the data while the guard is held.
+        self.lock.data.with(|data| unsafe { &*data })
+    }
+}
+
+impl<T: ?Sized> DerefMut for WriteStamp<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: The exclusive write access is held so no one else can access
+        // the data while the guard is held.
+        self.lock.data.with_mut(|data| unsafe { &mut *data })
+    }
+}
+
+impl<T: ?Sized> Drop for WriteStamp<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // Adding the write bit clears it and increments the version.
+        self.lock.state.fetch_add(WRITER, Ordering::Release);
+    }
+}
+
+// Prevents the write guard from being moved to an other thread.
+#[cfg(not(feature = "send_guard"))]
+impl<T: ?Sized> !Send for WriteStamp<'_, T> {}
+
+#[cfg(all(test, not(feature = "loom")))]
+mod tests {
+    extern crate std;
+
+    use core::sync::atomic::Ordering;
+    use core::time::Duration;
+    use std::thread;
+
+    use super::{StampedLock, MAX_READERS, READER};
+
+    #[test]
+    fn read_waits_at_max_readers() {
+        let mut lock = StampedLock::new(0);
+
+        // Presets the count as if all the readers but one were held elsewhere.
+        *lock.state.get_mut() = MAX_READERS - READER;
+        let reader = lock.read();
+        assert!(lock.try_read().is_none());
+
+        // A reader spins while the count is full instead of panicking.
+        thread::scope(|s| {
+            let waiter = s.spawn(|| *lock.read());
+            thread::sleep(Duration::from_millis(10));
+            assert!(!waiter.is_finished());
+
+            drop(reader);
+            assert_eq!(waiter.join().unwrap(), 0);
+        });
+
+        // Drains the preset readers, the writer can then acquire the lock.
+        lock.state
+            .fetch_sub(MAX_READERS - READER, Ordering::Relaxed);
+        *lock.write() = 1;
+        assert_eq!(*lock.read(), 1);
+    }
+}
@@ -0,0 +1,10 @@
+/// Statistics of the spinning on a lock.
+///
+/// This structure is returned by [`RwLock::stats`](crate::RwLock::stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LockStats {
+    /// Number of spin iterations of the readers waiting for the lock.
+    pub read_spins: usize,
+    /// Number of spin iterations of the writers waiting for the lock.
+    pub write_spins: usize,
+}
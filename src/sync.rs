@@ -0,0 +1,105 @@
+//! Synchronization primitives the locks are built on.
+//!
+//! When the `loom` feature is enabled they are swapped for the [`loom`](https://docs.rs/loom)
+//! ones so that code using the locks can be model-checked. When the
+//! `portable-atomic` feature is enabled the atomics are swapped for the
+//! [`portable_atomic`](https://docs.rs/portable-atomic) ones so that the locks
+//! build on targets without atomic compare and swap.
+
+#[cfg(not(feature = "loom"))]
+pub use core::hint;
+#[cfg(all(
+    target_has_atomic = "64",
+    not(any(feature = "loom", feature = "portable-atomic"))
+))]
+pub use core::sync::atomic::AtomicU64;
+#[cfg(not(any(feature = "loom", feature = "portable-atomic")))]
+pub use core::sync::atomic::{fence, AtomicBool, AtomicIsize, AtomicU8, AtomicUsize, Ordering};
+#[cfg(all(feature = "portable-atomic", not(feature = "loom")))]
+pub use portable_atomic::AtomicU64;
+#[cfg(all(feature = "portable-atomic", not(feature = "loom")))]
+pub use portable_atomic::{fence, AtomicBool, AtomicIsize, AtomicU8, AtomicUsize, Ordering};
+#[cfg(all(feature = "std", not(feature = "loom")))]
+pub use std::thread::yield_now;
+#[cfg(all(feature = "std", not(feature = "loom")))]
+pub use std::thread_local;
+
+#[cfg(feature = "loom")]
+pub use loom::cell::UnsafeCell;
+#[cfg(feature = "loom")]
+pub use loom::hint;
+#[cfg(feature = "loom")]
+pub use loom::sync::atomic::{
+    fence, AtomicBool, AtomicIsize, AtomicU64, AtomicU8, AtomicUsize, Ordering,
+};
+#[cfg(all(feature = "std", feature = "loom"))]
+pub use loom::thread::yield_now;
+#[cfg(all(feature = "std", feature = "loom"))]
+pub use loom::thread_local;
+
+/// Declares a function which is `const` unless the `loom` feature is enabled,
+/// since the loom primitives can't be created in const contexts.
+macro_rules! const_fn {
+    ($(#[$attr:meta])* $vis:vis const fn $($rest:tt)*) => {
+        #[cfg(not(feature = "loom"))]
+        $(#[$attr])* $vis const fn $($rest)*
+
+        #[cfg(feature = "loom")]
+        $(#[$attr])* $vis fn $($rest)*
+    };
+}
+
+/// Stores a value in an atomic with release ordering to release a lock.
+///
+/// Loom does not model that a store eventually becomes visible to the other
+/// threads so a thread spinning on the lock may never observe its release.
+/// Under loom the value is swapped instead since a read-modify-write always
+/// operates on the latest value.
+macro_rules! store_release {
+    ($atomic:expr, $value:expr) => {{
+        #[cfg(not(feature = "loom"))]
+        $atomic.store($value, $crate::sync::Ordering::Release);
+
+        #[cfg(feature = "loom")]
+        $atomic.swap($value, $crate::sync::Ordering::Release);
+    }};
+}
+
+/// A wrapper around [`core::cell::UnsafeCell`] exposing the same API as the
+/// loom `UnsafeCell`, the accesses to the data go through closures so that
+/// loom can track them.
+#[cfg(not(feature = "loom"))]
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct UnsafeCell<T: ?Sized>(core::cell::UnsafeCell<T>);
+
+#[cfg(not(feature = "loom"))]
+impl<T> UnsafeCell<T> {
+    /// Creates a new `UnsafeCell<T>` containing the given data.
+    #[inline]
+    #[must_use]
+    pub const fn new(data: T) -> Self {
+        Self(core::cell::UnsafeCell::new(data))
+    }
+
+    /// Consumes the cell, returning the wrapped data.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0.into_inner()
+    }
+}
+
+#[cfg(not(feature = "loom"))]
+impl<T: ?Sized> UnsafeCell<T> {
+    /// Calls the given closure with an immutable pointer to the wrapped data.
+    #[inline]
+    pub fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+        f(self.0.get())
+    }
+
+    /// Calls the given closure with a mutable pointer to the wrapped data.
+    #[inline]
+    pub fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
@@ -0,0 +1,223 @@
+use core::ops::{Deref, DerefMut};
+
+use crate::backoff::Backoff;
+use crate::sync::{AtomicUsize, Ordering, UnsafeCell};
+
+/// A fair mutual exclusion synchronization primitive.
+///
+/// This primitive allows only one thread to access the data at a time.
+/// It is using a ticket lock as locking mechanism: each thread trying to
+/// acquire the lock takes a ticket and spins until its ticket is served,
+/// so the threads acquire the lock in the order they asked for it.
+///
+/// Compared to [`Mutex`](crate::Mutex) no thread can starve under contention,
+/// but an uncontended acquisition is slightly slower since it needs to update
+/// the ticket counter and to read the one being served. Under contention, the
+/// lock can also only be handed to the next thread in line even if an other
+/// thread is ready to take it.
+#[derive(Debug)]
+pub struct TicketMutex<T> {
+    // Inner data contained in the mutex.
+    data: UnsafeCell<T>,
+    // Ticket handed to the next thread trying to acquire the lock.
+    next_ticket: AtomicUsize,
+    // Ticket of the thread allowed to hold the lock.
+    now_serving: AtomicUsize,
+}
+
+impl<T> TicketMutex<T> {
+    const_fn! {
+        /// Creates a new `TicketMutex<T>` which is unlocked.
+        ///
+        /// # Examples
+        /// ```
+        /// use spinlock::TicketMutex;
+        ///
+        /// let mutex = TicketMutex::new(1);
+        /// ```
+        #[inline]
+        #[must_use]
+        pub const fn new(data: T) -> Self {
+            Self {
+                data: UnsafeCell::new(data),
+                next_ticket: AtomicUsize::new(0),
+                now_serving: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    /// Acquires the lock, blocking the current thread until the lock is available.
+    ///
+    /// This functions block the current thread until all the threads that
+    /// asked for the lock before have released it.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::TicketMutex;
+    /// use std::thread;
+    /// use std::sync::Arc;
+    ///
+    /// let mutex = Arc::new(TicketMutex::new(1));
+    /// let m = Arc::clone(&mutex);
+    ///
+    /// thread::spawn(move || {
+    ///     *m.lock() = 42;
+    /// }).join().expect("thread::spawn failed");
+    /// assert_eq!(*mutex.lock(), 42);
+    /// ```
+    pub fn lock(&self) -> TicketMutexGuard<'_, T> {
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+
+        let mut backoff = Backoff::new();
+        // Memory order acquire is used to make sure no reordering happens after it.
+        while self.now_serving.load(Ordering::Acquire) != ticket {
+            backoff.spin();
+        }
+
+        TicketMutexGuard::new(self)
+    }
+
+    /// Tries to acquire the lock. If the lock is not available returns `None`.
+    ///
+    /// This function does not block the current thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use spinlock::TicketMutex;
+    ///
+    /// let mutex = TicketMutex::new(1);
+    /// assert_eq!(*mutex.try_lock().unwrap(), 1);
+    /// ```
+    pub fn try_lock(&self) -> Option<TicketMutexGuard<'_, T>> {
+        // The lock is available only if no one is waiting for it
+        // ie the next ticket is the one being served.
+        let ticket = self.now_serving.load(Ordering::Relaxed);
+        self.next_ticket
+            .compare_exchange(
+                ticket,
+                ticket.wrapping_add(1),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .map_or(None, |_| Some(TicketMutexGuard::new(self)))
+    }
+
+    /// UNSAFE: forcing to unlock while a guard is still held may allow to have mutliple guards.
+    #[allow(clippy::inline_always)]
+    #[inline(always)]
+    unsafe fn unlock(&self) {
+        // Serves the next ticket, memory order release is used to make sure
+        // no reordering happens before it.
+        self.now_serving.fetch_add(1, Ordering::Release);
+    }
+}
+
+impl<T: Default> Default for TicketMutex<T> {
+    /// Creates a `TicketMutex<T>` which is unlocked containing the default of `T`.
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+// SAFETY: The locking mechanism ensures that only one thread accesses the data
+// at a time, which may move it out of the mutex so it must be `Send`.
+unsafe impl<T: Send> Sync for TicketMutex<T> {}
+
+/// This structure is created by calling [`lock`](self::TicketMutex::lock)
+/// or [`try_lock`](self::TicketMutex::try_lock) on [`TicketMutex`](self::TicketMutex).
+#[derive(Debug)]
+pub struct TicketMutexGuard<'mutex, T> {
+    mutex: &'mutex TicketMutex<T>,
+}
+
+impl<'mutex, T> TicketMutexGuard<'mutex, T> {
+    /// Creates a `TicketMutexGuard<'mutex, T>` of a given `TicketMutex`.
+    #[inline]
+    #[must_use]
+    const fn new(mutex: &'mutex TicketMutex<T>) -> Self {
+        Self { mutex }
+    }
+}
+
+impl<T> Deref for TicketMutexGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: A guard is only created when its ticket is served meaning that
+        // no one else can modify the data so it is safe to get reference to the
+        // data for the lifetime of the guard.
+        self.mutex.data.with(|data| unsafe { &*data })
+    }
+}
+
+impl<T> DerefMut for TicketMutexGuard<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: A guard is only created when its ticket is served meaning that
+        // no one else can modify the data so it is safe to get a mutable reference
+        // to the data for the lifetime of the guard.
+        self.mutex.data.with_mut(|data| unsafe { &mut *data })
+    }
+}
+
+impl<T> Drop for TicketMutexGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // SAFETY: Only the guard of the served ticket exists for a certain mutex
+        // which is the current one so it is safe to unlock the mutex when the
+        // guard gets dropped.
+        unsafe { self.mutex.unlock() }
+    }
+}
+
+/// Prevents the guard from being sent to another thread.
+impl<T> !Send for TicketMutexGuard<'_, T> {}
+
+unsafe impl<T: Sync> Sync for TicketMutexGuard<'_, T> {}
+
+#[cfg(all(test, not(feature = "loom")))]
+mod tests {
+    extern crate std;
+
+    use core::sync::atomic::Ordering;
+    use std::thread;
+    use std::vec::Vec;
+
+    use super::TicketMutex;
+
+    #[test]
+    fn try_lock_wraps_tickets() {
+        let mutex = TicketMutex::new(0);
+        mutex.next_ticket.store(usize::MAX, Ordering::Relaxed);
+        mutex.now_serving.store(usize::MAX, Ordering::Relaxed);
+
+        *mutex.try_lock().unwrap() += 1;
+        assert_eq!(mutex.next_ticket.load(Ordering::Relaxed), 0);
+        assert_eq!(mutex.now_serving.load(Ordering::Relaxed), 0);
+        assert_eq!(*mutex.try_lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn fifo_order() {
+        let order = TicketMutex::new(Vec::new());
+
+        let guard = order.lock();
+        thread::scope(|s| {
+            for i in 0..4 {
+                let order = &order;
+                s.spawn(move || order.lock().push(i));
+
+                // Waits for the thread to take its ticket before spawning the
+                // next one, the guard holds the first ticket.
+                while order.next_ticket.load(Ordering::Relaxed) != i + 2 {
+                    thread::yield_now();
+                }
+            }
+            drop(guard);
+        });
+
+        assert_eq!(*order.lock(), [0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,55 @@
+use core::fmt;
+
+/// An error returned when a lock could not be acquired without blocking.
+///
+/// This error is returned by the `*_result` variants of the non-blocking
+/// methods, such as [`Mutex::try_lock_result`](crate::Mutex::try_lock_result),
+/// for the callers expecting a `Result` rather than an `Option`.
+///
+/// # Examples
+/// ```
+/// use spinlock::{RwLock, WouldBlock};
+///
+/// let rwlock = RwLock::new(1);
+///
+/// let guard = rwlock.read();
+/// assert_eq!(rwlock.try_write_result().unwrap_err(), WouldBlock);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct WouldBlock;
+
+impl fmt::Display for WouldBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("lock could not be acquired without blocking")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WouldBlock {}
+
+/// An error returned when a lock could not be acquired within a deadline.
+///
+/// This error is returned by [`Mutex::lock_deadline`](crate::Mutex::lock_deadline)
+/// once its budget of spin iterations is exhausted, which bounds the time
+/// spent acquiring the lock without needing a clock.
+///
+/// # Examples
+/// ```
+/// use spinlock::{Mutex, Timeout};
+///
+/// let mutex = Mutex::new(1);
+///
+/// let guard = mutex.lock();
+/// assert_eq!(mutex.lock_deadline(100).unwrap_err(), Timeout);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Timeout;
+
+impl fmt::Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("lock could not be acquired before the deadline")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Timeout {}
@@ -0,0 +1,237 @@
+#![cfg(all(feature = "alloc", not(feature = "poison")))]
+
+use spinlock::{ArcMutexGuard, ArcRwLockWriteGuard, Mutex, RwLock};
+
+use std::sync::Arc;
+
+#[test]
+fn lock_arc_releases_on_drop() {
+    let mutex = Arc::new(Mutex::new(0));
+
+    let mut guard = mutex.lock_arc();
+    *guard += 1;
+    assert!(mutex.is_locked());
+    assert!(mutex.try_lock_arc().is_none());
+
+    drop(guard);
+    assert!(!mutex.is_locked());
+    assert_eq!(*mutex.lock(), 1);
+}
+
+#[test]
+fn guard_keeps_mutex_alive() {
+    let mutex = Arc::new(Mutex::new(vec![1]));
+
+    let mut guard = mutex.try_lock_arc().unwrap();
+    drop(mutex);
+
+    guard.push(2);
+    assert_eq!(Arc::strong_count(ArcMutexGuard::mutex(&guard)), 1);
+    assert_eq!(*guard, [1, 2]);
+}
+
+#[test]
+fn try_lock_arc_on_locked() {
+    let mutex = Arc::new(Mutex::new(0));
+
+    let guard = mutex.lock();
+    assert!(mutex.try_lock_arc().is_none());
+
+    drop(guard);
+    assert!(mutex.try_lock_arc().is_some());
+}
+
+#[test]
+fn try_lock_arc_stored_in_struct() {
+    // Holds the lock for as long as it lives, without borrowing the mutex.
+    struct Builder {
+        entries: ArcMutexGuard<Vec<i32>>,
+    }
+
+    impl Builder {
+        fn add(mut self, entry: i32) -> Self {
+            self.entries.push(entry);
+            self
+        }
+    }
+
+    let mutex = Arc::new(Mutex::new(Vec::new()));
+
+    let builder = Builder {
+        entries: mutex.try_lock_arc().unwrap(),
+    };
+    let builder = builder.add(1).add(2);
+    assert!(mutex.is_locked());
+    assert!(Arc::ptr_eq(ArcMutexGuard::mutex(&builder.entries), &mutex));
+
+    drop(builder);
+    assert_eq!(*mutex.lock(), [1, 2]);
+}
+
+#[test]
+fn lock_owned_static_guard() {
+    use std::ops::DerefMut;
+
+    let mutex = Arc::new(Mutex::new(0));
+
+    // Boxed trait objects are `'static` unless stated otherwise.
+    let mut guard: Box<dyn DerefMut<Target = i32>> = Box::new(mutex.lock_owned());
+    **guard = 42;
+    assert!(mutex.try_lock_owned().is_none());
+
+    drop(guard);
+    assert_eq!(*mutex.try_lock_owned().unwrap(), 42);
+}
+
+#[cfg(not(feature = "critical-section"))]
+#[test]
+fn guard_moved_to_thread() {
+    use std::sync::mpsc;
+    use std::thread;
+
+    let mutex = Arc::new(Mutex::new(0));
+    let (tx, rx) = mpsc::channel();
+
+    let mut guard = mutex.lock_arc();
+    let thread = thread::spawn(move || {
+        *guard = 42;
+        // The lock is still held by the guard in this thread.
+        tx.send(()).unwrap();
+        thread::park();
+    });
+
+    rx.recv().unwrap();
+    assert!(mutex.is_locked());
+
+    thread.thread().unpark();
+    thread.join().unwrap();
+    assert!(!mutex.is_locked());
+    assert_eq!(*mutex.lock(), 42);
+}
+
+#[test]
+fn read_arc_restores_reader_count() {
+    let rwlock = Arc::new(RwLock::new(1));
+
+    let r1 = rwlock.read_arc();
+    let r2 = rwlock.read_arc();
+    assert_eq!(rwlock.reader_count(), 2);
+    assert!(rwlock.try_write().is_none());
+
+    drop(r1);
+    drop(r2);
+    assert_eq!(rwlock.reader_count(), 0);
+    assert!(rwlock.try_write().is_some());
+}
+
+#[test]
+fn read_arc_recursive_drop_orders() {
+    let rwlock = Arc::new(RwLock::new(1));
+
+    let r1 = rwlock.read_arc_recursive();
+    let r2 = rwlock.read_arc_recursive();
+    assert_eq!(rwlock.reader_count(), 2);
+    drop(r1);
+    assert_eq!(rwlock.reader_count(), 1);
+    drop(r2);
+    assert_eq!(rwlock.reader_count(), 0);
+
+    let r1 = rwlock.read_arc_recursive();
+    let r2 = rwlock.read_arc_recursive();
+    drop(r2);
+    assert_eq!(rwlock.reader_count(), 1);
+    drop(r1);
+    assert_eq!(rwlock.reader_count(), 0);
+    assert!(rwlock.try_write().is_some());
+
+    // The guards hold the last references to the rwlock once it is dropped.
+    let weak = Arc::downgrade(&rwlock);
+    let r1 = rwlock.read_arc_recursive();
+    let r2 = rwlock.read_arc_recursive();
+    drop(rwlock);
+    assert_eq!(weak.upgrade().unwrap().reader_count(), 2);
+    drop(r1);
+    assert_eq!(weak.upgrade().unwrap().reader_count(), 1);
+    drop(r2);
+    assert!(weak.upgrade().is_none());
+}
+
+#[test]
+fn read_arc_recursive_with_biased_writer() {
+    use std::thread;
+    use std::time::Duration;
+
+    let rwlock = Arc::new(RwLock::new(1));
+    let r1 = rwlock.read_arc();
+
+    let writer = {
+        let rwlock = Arc::clone(&rwlock);
+        thread::spawn(move || *rwlock.write_biased() = 2)
+    };
+    thread::sleep(Duration::from_millis(50));
+
+    // Does not wait for the biased writer although it may be waiting.
+    let r2 = rwlock.read_arc_recursive();
+    assert_eq!(*r1 + *r2, 2);
+    drop((r1, r2));
+
+    writer.join().unwrap();
+    assert_eq!(*rwlock.read(), 2);
+}
+
+#[test]
+fn write_arc_restores_write_state() {
+    let rwlock = Arc::new(RwLock::new(1));
+
+    let mut guard = rwlock.write_arc();
+    *guard = 2;
+    assert!(rwlock.is_write_locked());
+    assert!(rwlock.try_read().is_none());
+    assert!(Arc::ptr_eq(ArcRwLockWriteGuard::rwlock(&guard), &rwlock));
+
+    drop(guard);
+    assert!(!rwlock.is_write_locked());
+    assert_eq!(*rwlock.read(), 2);
+}
+
+#[test]
+fn upgradeable_read_arc_with_readers() {
+    let rwlock = Arc::new(RwLock::new(1));
+
+    let upgradeable = rwlock.upgradeable_read_arc();
+    let reader = rwlock.read_arc();
+    assert!(rwlock.try_upgradeable_read().is_none());
+    assert!(rwlock.try_write().is_none());
+    assert_eq!(rwlock.reader_count(), 1);
+
+    drop(reader);
+    drop(upgradeable);
+    assert!(rwlock.try_write().is_some());
+}
+
+#[test]
+fn read_arc_moved_to_thread() {
+    use std::sync::mpsc;
+    use std::thread;
+
+    let rwlock = Arc::new(RwLock::new(42));
+    let (tx, rx) = mpsc::channel();
+
+    let guard = rwlock.read_arc();
+    let other = rwlock.read_arc();
+    let thread = thread::spawn(move || {
+        assert_eq!(*guard, 42);
+        tx.send(()).unwrap();
+        thread::park();
+        // Both guards are released once the thread ends.
+        drop(other);
+    });
+
+    rx.recv().unwrap();
+    assert_eq!(rwlock.reader_count(), 2);
+
+    thread.thread().unpark();
+    thread.join().unwrap();
+    assert_eq!(rwlock.reader_count(), 0);
+    assert!(rwlock.try_write().is_some());
+}
@@ -0,0 +1,79 @@
+use spinlock::Barrier;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn single_thread_never_blocks() {
+    let barrier = Barrier::new(1);
+
+    assert!(barrier.wait().is_leader());
+    assert!(barrier.wait().is_leader());
+}
+
+#[test]
+fn no_thread_races_ahead() {
+    const THREADS: usize = 8;
+    const PHASES: usize = 100;
+
+    let barrier = Arc::new(Barrier::new(THREADS));
+    let arrived: Arc<Vec<_>> = Arc::new((0..PHASES).map(|_| AtomicUsize::new(0)).collect());
+    let leaders: Arc<Vec<_>> = Arc::new((0..PHASES).map(|_| AtomicUsize::new(0)).collect());
+
+    let threads: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let barrier = Arc::clone(&barrier);
+            let arrived = Arc::clone(&arrived);
+            let leaders = Arc::clone(&leaders);
+            thread::spawn(move || {
+                for phase in 0..PHASES {
+                    arrived[phase].fetch_add(1, Ordering::SeqCst);
+                    if barrier.wait().is_leader() {
+                        leaders[phase].fetch_add(1, Ordering::SeqCst);
+                    }
+                    // Every thread reached this phase before any left it, and
+                    // none can have reached the next one yet.
+                    assert_eq!(arrived[phase].load(Ordering::SeqCst), THREADS);
+                    if phase + 1 < PHASES {
+                        assert!(arrived[phase + 1].load(Ordering::SeqCst) < THREADS);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    for leader in leaders.iter() {
+        assert_eq!(leader.load(Ordering::SeqCst), 1);
+    }
+}
+
+#[test]
+fn more_threads_than_n() {
+    const N: usize = 4;
+    const THREADS: usize = 3 * N;
+    const ROUNDS: usize = 20;
+
+    let barrier = Arc::new(Barrier::new(N));
+
+    for _ in 0..ROUNDS {
+        let threads: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || barrier.wait().is_leader())
+            })
+            .collect();
+
+        // The threads are released in groups of `N`, each with a single leader.
+        let leaders = threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .filter(|&is_leader| is_leader)
+            .count();
+        assert_eq!(leaders, THREADS / N);
+    }
+}
@@ -0,0 +1,57 @@
+#![cfg(not(feature = "poison"))]
+
+use spinlock::{Condvar, Mutex};
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn wait_until_notified() {
+    let pair = Arc::new((Mutex::new(false), Condvar::new()));
+
+    let p = Arc::clone(&pair);
+    let thread = thread::spawn(move || {
+        let (mutex, condvar) = &*p;
+        *mutex.lock() = true;
+        condvar.notify_one();
+    });
+
+    let (mutex, condvar) = &*pair;
+    let mut ready = mutex.lock();
+    while !*ready {
+        ready = condvar.wait(ready);
+    }
+    assert!(mutex.is_locked());
+    drop(ready);
+
+    thread.join().unwrap();
+}
+
+#[test]
+fn producer_consumer() {
+    let pair = Arc::new((Mutex::new(VecDeque::new()), Condvar::new()));
+
+    let p = Arc::clone(&pair);
+    let producer = thread::spawn(move || {
+        let (mutex, condvar) = &*p;
+        for i in 0..1_000 {
+            mutex.lock().push_back(i);
+            condvar.notify_all();
+        }
+    });
+
+    let (mutex, condvar) = &*pair;
+    let mut received = Vec::new();
+    let mut queue = mutex.lock();
+    while received.len() < 1_000 {
+        match queue.pop_front() {
+            Some(i) => received.push(i),
+            None => queue = condvar.wait(queue),
+        }
+    }
+    drop(queue);
+
+    producer.join().unwrap();
+    assert!(received.into_iter().eq(0..1_000));
+}
@@ -0,0 +1,36 @@
+#![cfg(all(feature = "critical-section", not(feature = "poison")))]
+
+use spinlock::Mutex;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn lock_unlock() {
+    let mutex = Mutex::new(0);
+
+    *mutex.lock() += 1;
+    assert_eq!(*mutex.lock(), 1);
+    assert!(!mutex.is_locked());
+}
+
+#[test]
+fn guard_holds_critical_section() {
+    let mutex = Mutex::new(0);
+    let entered = Arc::new(AtomicBool::new(false));
+
+    let guard = mutex.lock();
+    let thread = {
+        let entered = Arc::clone(&entered);
+        thread::spawn(move || critical_section::with(|_| entered.store(true, Ordering::SeqCst)))
+    };
+
+    thread::sleep(Duration::from_millis(50));
+    assert!(!entered.load(Ordering::SeqCst));
+
+    drop(guard);
+    thread.join().unwrap();
+    assert!(entered.load(Ordering::SeqCst));
+}
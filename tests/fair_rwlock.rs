@@ -0,0 +1,147 @@
+use spinlock::FairRwLock;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn try_read_on_read_locked() {
+    let rwlock = FairRwLock::new(0);
+
+    let _guard = rwlock.read();
+
+    assert!(rwlock.try_read().is_some());
+}
+
+#[test]
+fn try_read_on_write_locked() {
+    let rwlock = FairRwLock::new(0);
+
+    let _guard = rwlock.write();
+
+    assert!(rwlock.try_read().is_none());
+}
+
+#[test]
+fn try_write_on_read_locked() {
+    let rwlock = FairRwLock::new(0);
+
+    let _guard = rwlock.read();
+
+    assert!(rwlock.try_write().is_none());
+}
+
+#[test]
+fn try_write_after_unlock() {
+    let rwlock = FairRwLock::new(0);
+
+    drop(rwlock.read());
+    drop(rwlock.write());
+    drop(rwlock.try_read().unwrap());
+
+    assert!(rwlock.try_write().is_some());
+}
+
+#[test]
+fn try_read_with_writer_waiting() {
+    let rwlock = Arc::new(FairRwLock::new(0));
+
+    let guard = rwlock.read();
+
+    let writer = {
+        let rwlock = Arc::clone(&rwlock);
+        thread::spawn(move || {
+            *rwlock.write() += 1;
+        })
+    };
+
+    // Gives time to the writer to take its ticket.
+    thread::sleep(Duration::from_millis(50));
+    assert!(rwlock.try_read().is_none());
+
+    drop(guard);
+    writer.join().unwrap();
+
+    assert_eq!(*rwlock.read(), 1);
+}
+
+#[test]
+fn readers_and_writers_count() {
+    let rwlock = Arc::new(FairRwLock::new(0));
+
+    let writers: Vec<_> = (0..2)
+        .map(|_| {
+            let rwlock = Arc::clone(&rwlock);
+            thread::spawn(move || {
+                for _ in 0..1_000 {
+                    *rwlock.write() += 1;
+                }
+            })
+        })
+        .collect();
+
+    let readers: Vec<_> = (0..2)
+        .map(|_| {
+            let rwlock = Arc::clone(&rwlock);
+            thread::spawn(move || {
+                let mut last = 0;
+                for _ in 0..1_000 {
+                    let count = *rwlock.read();
+                    assert!(count >= last);
+                    last = count;
+                }
+            })
+        })
+        .collect();
+
+    for thread in writers.into_iter().chain(readers) {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(*rwlock.read(), 2_000);
+}
+
+#[test]
+fn writer_bounded_waiting() {
+    const READERS: usize = 4;
+
+    let rwlock = Arc::new(FairRwLock::new(()));
+    let reads = Arc::new(AtomicUsize::new(0));
+    let done = Arc::new(AtomicUsize::new(0));
+
+    let readers: Vec<_> = (0..READERS)
+        .map(|_| {
+            let rwlock = Arc::clone(&rwlock);
+            let reads = Arc::clone(&reads);
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                while done.load(Ordering::Relaxed) == 0 {
+                    let _guard = rwlock.read();
+                    reads.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+        })
+        .collect();
+
+    // Waits for the readers to continuously hold the lock.
+    while reads.load(Ordering::Relaxed) < 1_000 {
+        thread::yield_now();
+    }
+
+    for _ in 0..100 {
+        let before = reads.load(Ordering::Relaxed);
+        let _guard = rwlock.write();
+        let after = reads.load(Ordering::Relaxed);
+
+        // Only the readers ahead of the writer in line may enter before it,
+        // plus the ones which entered before `before` but counted themselves
+        // after it.
+        assert!(after - before <= 2 * READERS);
+    }
+
+    done.store(1, Ordering::Relaxed);
+    for thread in readers {
+        thread.join().unwrap();
+    }
+}
@@ -0,0 +1,54 @@
+#![cfg(not(feature = "loom"))]
+
+use spinlock::Lazy;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+#[test]
+fn deref_initializes_once() {
+    let mut count = 0;
+    let lazy = Lazy::new(|| {
+        count += 1;
+        42
+    });
+
+    assert_eq!(*lazy, 42);
+    assert_eq!(*lazy, 42);
+    drop(lazy);
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn static_lazy() {
+    static VALUES: Lazy<Vec<u32>> = Lazy::new(|| vec![1, 2, 3]);
+
+    assert_eq!(*VALUES, [1, 2, 3]);
+}
+
+#[test]
+fn concurrent_deref() {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    static LAZY: Lazy<usize> = Lazy::new(|| {
+        thread::yield_now();
+        COUNT.fetch_add(1, Ordering::Relaxed) + 42
+    });
+
+    let barrier = Arc::new(Barrier::new(8));
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                assert_eq!(*LAZY, 42);
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(COUNT.load(Ordering::Relaxed), 1);
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "lock_api")]
+
+use spinlock::lock_api::{Mutex, RwLock};
+
+use std::sync::Arc;
+use std::thread;
+
+static STATIC_MUTEX: Mutex<i32> = Mutex::new(0);
+
+#[test]
+fn const_mutex() {
+    *STATIC_MUTEX.lock() += 1;
+
+    assert!(*STATIC_MUTEX.lock() >= 1);
+}
+
+#[test]
+fn mutex_try_lock() {
+    let mutex = Mutex::new(0);
+
+    let guard = mutex.lock();
+    assert!(mutex.is_locked());
+    assert!(mutex.try_lock().is_none());
+
+    drop(guard);
+    assert!(!mutex.is_locked());
+    assert!(mutex.try_lock().is_some());
+}
+
+#[test]
+fn mutex_two_threads_count() {
+    let count = Arc::new(Mutex::new(0));
+
+    let count1 = Arc::clone(&count);
+    let thread1 = thread::spawn(move || {
+        for _ in 0..100_000 {
+            *count1.lock() += 1;
+        }
+    });
+
+    let count2 = Arc::clone(&count);
+    let thread2 = thread::spawn(move || {
+        for _ in 0..100_000 {
+            *count2.lock() += 1;
+        }
+    });
+
+    thread1.join().unwrap();
+    thread2.join().unwrap();
+
+    assert_eq!(*count.lock(), 200_000);
+}
+
+#[test]
+fn rwlock_read_write() {
+    let rwlock = RwLock::new(0);
+
+    let rguard1 = rwlock.read();
+    let rguard2 = rwlock.try_read().unwrap();
+    assert!(rwlock.is_locked());
+    assert!(!rwlock.is_locked_exclusive());
+    assert!(rwlock.try_write().is_none());
+
+    drop(rguard1);
+    drop(rguard2);
+    assert!(!rwlock.is_locked());
+
+    *rwlock.write() = 42;
+    assert_eq!(*rwlock.read(), 42);
+
+    let _wguard = rwlock.write();
+    assert!(rwlock.is_locked_exclusive());
+    assert!(rwlock.try_read().is_none());
+}
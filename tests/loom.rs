@@ -0,0 +1,65 @@
+#![cfg(feature = "loom")]
+
+use spinlock::{Mutex, RwLock};
+
+use loom::sync::Arc;
+use loom::thread;
+
+#[test]
+fn mutex_two_threads_count() {
+    loom::model(|| {
+        let count = Arc::new(Mutex::new(0));
+
+        let count1 = Arc::clone(&count);
+        let thread = thread::spawn(move || {
+            *count1.lock() += 1;
+        });
+
+        *count.lock() += 1;
+        thread.join().unwrap();
+
+        assert_eq!(*count.lock(), 2);
+    });
+}
+
+#[test]
+fn rwlock_reader_and_writer() {
+    loom::model(|| {
+        let rwlock = Arc::new(RwLock::new(0));
+
+        let rwlock1 = Arc::clone(&rwlock);
+        let thread = thread::spawn(move || {
+            *rwlock1.write() += 1;
+        });
+
+        let value = *rwlock.read();
+        assert!(value == 0 || value == 1);
+        thread.join().unwrap();
+
+        assert_eq!(*rwlock.read(), 1);
+    });
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn reentrant_mutex_nested_count() {
+    use spinlock::ReentrantMutex;
+    use std::cell::Cell;
+
+    loom::model(|| {
+        let count = Arc::new(ReentrantMutex::new(Cell::new(0)));
+
+        let count1 = Arc::clone(&count);
+        let thread = thread::spawn(move || {
+            let outer = count1.lock();
+            let inner = count1.lock();
+            inner.set(inner.get() + 1);
+            drop(outer);
+        });
+
+        count.lock().set(count.lock().get() + 1);
+        thread.join().unwrap();
+
+        assert_eq!(count.lock().get(), 2);
+    });
+}
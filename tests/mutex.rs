@@ -1,5 +1,9 @@
-use spinlock::Mutex;
+#![cfg(not(feature = "poison"))]
 
+use spinlock::{Mutex, MutexGuard, Timeout, WouldBlock};
+
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::thread;
 
@@ -44,6 +48,10 @@ fn two_threads_count() {
 }
 
 #[test]
+#[cfg_attr(
+    feature = "critical-section",
+    ignore = "the critical section excludes the other thread so try_lock never fails"
+)]
 fn two_threads_try_count() {
     let count = Arc::new(Mutex::new(0));
 
@@ -83,3 +91,636 @@ fn two_threads_try_count() {
 
     assert_eq!(*count.lock(), 2_000_000);
 }
+
+#[test]
+fn lock_deadline_on_long_held() {
+    let mutex = Mutex::new(0);
+
+    let guard = mutex.lock();
+    assert_eq!(mutex.lock_deadline(0).unwrap_err(), Timeout);
+    assert_eq!(mutex.lock_deadline(1_000).unwrap_err(), Timeout);
+    drop(guard);
+
+    assert!(mutex.lock_deadline(0).is_ok());
+}
+
+#[test]
+#[cfg_attr(
+    feature = "critical-section",
+    ignore = "the critical section excludes the other thread until the lock is released"
+)]
+fn lock_deadline_on_quickly_released() {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let mutex = Arc::new(Mutex::new(0));
+    let (tx, rx) = mpsc::channel();
+
+    let holder = {
+        let mutex = Arc::clone(&mutex);
+        thread::spawn(move || {
+            let mut guard = mutex.lock();
+            tx.send(()).unwrap();
+            thread::sleep(Duration::from_millis(10));
+            *guard += 1;
+        })
+    };
+
+    rx.recv().unwrap();
+    // Far more spins than needed for the lock to be released.
+    let guard = mutex.lock_deadline(u64::MAX).unwrap();
+    assert_eq!(*guard, 1);
+    drop(guard);
+    holder.join().unwrap();
+}
+
+#[test]
+fn guard_display() {
+    let number = Mutex::new(42);
+    let name = Mutex::new(String::from("spinlock"));
+
+    assert_eq!(number.lock().to_string(), "42");
+    assert_eq!(format!("{:>4}", number.lock()), "  42");
+    assert_eq!(format!("[{}]", name.lock()), "[spinlock]");
+    assert_eq!(
+        MutexGuard::map(name.lock(), |name| &mut name[..4]).to_string(),
+        "spin"
+    );
+}
+
+#[test]
+fn guard_hash() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of<K: Hash>(key: K) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let names = [Mutex::new("spin"), Mutex::new("lock"), Mutex::new("spin")];
+
+    let hashes: HashSet<_> = names.iter().map(|name| hash_of(name.lock())).collect();
+    assert_eq!(hashes.len(), 2);
+    assert!(hashes.contains(&hash_of("spin")));
+    assert!(hashes.contains(&hash_of("lock")));
+    assert!(names.iter().all(|name| !name.is_locked()));
+}
+
+#[test]
+fn guard_comparison() {
+    let a = Mutex::new(1);
+    let b = Mutex::new(2);
+    let c = Mutex::new(1);
+
+    // The guards compare the locked data, not the identity of the mutexes.
+    assert!(a.lock() < b.lock());
+    assert!(a.lock() == c.lock());
+    assert!(b.lock() != c.lock());
+    assert_eq!(a.lock().cmp(&b.lock()), std::cmp::Ordering::Less);
+
+    let mutexes = [Mutex::new(3), Mutex::new(1), Mutex::new(2)];
+    let mut guards: Vec<_> = mutexes.iter().map(Mutex::lock).collect();
+    guards.sort();
+    let sorted: Vec<_> = guards.iter().map(|guard| **guard).collect();
+    assert_eq!(sorted, [1, 2, 3]);
+    assert_eq!(guards.iter().max().map(|guard| **guard), Some(3));
+}
+
+#[test]
+fn guard_borrow() {
+    use std::borrow::{Borrow, BorrowMut};
+
+    fn len<B: Borrow<String>>(value: B) -> usize {
+        value.borrow().len()
+    }
+
+    fn push<B: BorrowMut<String>>(mut value: B) {
+        value.borrow_mut().push_str("lock");
+    }
+
+    let mutex = Mutex::new(String::from("spin"));
+
+    push(mutex.lock());
+    assert_eq!(len(mutex.lock()), 8);
+    assert_eq!(len(String::from("spin")), 4);
+
+    let mapped = MutexGuard::map(mutex.lock(), |data| data);
+    assert_eq!(len(mapped), 8);
+    assert!(!mutex.is_locked());
+}
+
+#[test]
+fn try_lock_hot_on_long_held() {
+    let mutex = Mutex::new(0);
+
+    // The lock is held for longer than the few reloads of each attempt.
+    let guard = mutex.lock();
+    for _ in 0..100 {
+        assert!(mutex.try_lock_hot().is_none());
+    }
+
+    drop(guard);
+    *mutex.try_lock_hot().unwrap() += 1;
+    assert_eq!(*mutex.lock(), 1);
+}
+
+#[test]
+fn into_inner_after_lock() {
+    let mutex = Mutex::new(vec![1, 2]);
+
+    let mut guard = mutex.lock();
+    guard.push(3);
+    drop(guard);
+
+    assert_eq!(mutex.into_inner(), vec![1, 2, 3]);
+}
+
+#[test]
+fn is_locked() {
+    let mutex = Mutex::new(0);
+
+    assert!(!mutex.is_locked());
+
+    let guard = mutex.lock();
+    assert!(mutex.is_locked());
+
+    drop(guard);
+    assert!(!mutex.is_locked());
+}
+
+#[test]
+fn map_guard() {
+    let mutex = Mutex::new((0, String::from("spin")));
+
+    let mut name = MutexGuard::map(mutex.lock(), |data| &mut data.1);
+    name.push_str("lock");
+    assert!(mutex.try_lock().is_none());
+
+    drop(name);
+    assert_eq!(*mutex.lock(), (0, String::from("spinlock")));
+}
+
+#[test]
+fn try_map_guard_some() {
+    let mutex = Mutex::new(Some(0));
+
+    let mut inner = MutexGuard::try_map(mutex.lock(), Option::as_mut).unwrap();
+    *inner += 1;
+    assert!(mutex.is_locked());
+
+    drop(inner);
+    assert_eq!(*mutex.lock(), Some(1));
+}
+
+#[test]
+fn try_map_guard_none() {
+    let mutex = Mutex::new(None::<i32>);
+
+    let guard = MutexGuard::try_map(mutex.lock(), Option::as_mut).unwrap_err();
+    assert!(mutex.is_locked());
+
+    drop(guard);
+    assert!(!mutex.is_locked());
+}
+
+#[test]
+fn with_lock_releases_lock() {
+    let mutex = Mutex::new(vec![1, 2, 3]);
+
+    let found = mutex.with_lock(|data| {
+        for x in data.iter_mut() {
+            if *x == 2 {
+                *x = 42;
+                return true;
+            }
+        }
+        false
+    });
+    assert!(found);
+    assert!(!mutex.is_locked());
+    assert_eq!(*mutex.lock(), [1, 42, 3]);
+}
+
+#[test]
+fn replace() {
+    let mutex = Mutex::new(String::from("spin"));
+
+    assert_eq!(mutex.replace(String::from("lock")), "spin");
+    assert!(!mutex.is_locked());
+    assert_eq!(*mutex.lock(), "lock");
+}
+
+#[test]
+fn take() {
+    let mutex = Mutex::new(Some(1));
+
+    assert_eq!(mutex.take(), Some(1));
+    assert!(!mutex.is_locked());
+    assert_eq!(*mutex.lock(), None);
+}
+
+#[test]
+fn swap() {
+    let a = Mutex::new(1);
+    let b = Mutex::new(2);
+
+    a.swap(&b);
+    assert!(!a.is_locked());
+    assert!(!b.is_locked());
+    assert_eq!(*a.lock(), 2);
+    assert_eq!(*b.lock(), 1);
+
+    a.swap(&a);
+    assert!(!a.is_locked());
+    assert_eq!(*a.lock(), 2);
+}
+
+#[test]
+fn swap_opposite_order_no_deadlock() {
+    let a = Arc::new(Mutex::new(1));
+    let b = Arc::new(Mutex::new(2));
+
+    let threads: Vec<_> = (0..2)
+        .map(|i| {
+            let a = Arc::clone(&a);
+            let b = Arc::clone(&b);
+            thread::spawn(move || {
+                for _ in 0..10_000 {
+                    if i == 0 {
+                        a.swap(&b);
+                    } else {
+                        b.swap(&a);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(*a.lock() + *b.lock(), 3);
+}
+
+#[test]
+fn try_lock_spin_exhausted() {
+    let mutex = Mutex::new(0);
+
+    let guard = mutex.lock();
+    assert!(mutex.try_lock_spin(0).is_none());
+    assert!(mutex.try_lock_spin(1_000).is_none());
+
+    drop(guard);
+    assert!(mutex.try_lock_spin(0).is_none());
+    assert!(mutex.try_lock_spin(1).is_some());
+}
+
+#[test]
+#[cfg_attr(
+    feature = "critical-section",
+    ignore = "the critical section of the forgotten guard is never exited"
+)]
+fn force_unlock_forgotten_guard() {
+    let mutex = Mutex::new(0);
+
+    let mut guard = mutex.lock();
+    *guard += 1;
+    mem::forget(guard);
+    assert!(mutex.is_locked());
+
+    // SAFETY: The guard was forgotten.
+    unsafe { mutex.force_unlock() };
+    assert!(!mutex.is_locked());
+    assert_eq!(*mutex.lock(), 1);
+}
+
+#[test]
+fn from_value() {
+    let mutex: Mutex<i32> = 5.into();
+    assert!(!mutex.is_locked());
+    assert_eq!(*mutex.lock(), 5);
+}
+
+#[test]
+#[cfg(not(feature = "loom"))]
+fn static_array() {
+    static TABLE: [Mutex<u8>; 256] = Mutex::new_array(1);
+
+    let first = TABLE[0].lock();
+    let last = TABLE[255].lock();
+    *TABLE[42].lock() += 1;
+    assert!(TABLE[0].is_locked());
+    assert!(!TABLE[1].is_locked());
+    assert_eq!(*first + *last, 2);
+
+    drop(first);
+    drop(last);
+    assert_eq!(*TABLE[42].lock(), 2);
+    assert!(TABLE.iter().all(|mutex| !mutex.is_locked()));
+}
+
+#[test]
+fn data_ptr_points_to_data() {
+    let mutex = Mutex::new(0);
+
+    let guard = mutex.lock();
+    assert_eq!(mutex.data_ptr().cast_const(), &*guard as *const i32);
+    drop(guard);
+
+    // SAFETY: No guard is held and the mutex is not shared.
+    unsafe { *mutex.data_ptr() += 1 };
+    assert_eq!(*mutex.lock(), 1);
+}
+
+#[test]
+fn eq_compares_data() {
+    let a = Mutex::new(1);
+    let b = Mutex::new(1);
+    let c = Mutex::new(2);
+
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert_ne!(c, a);
+    assert!(!a.is_locked() && !b.is_locked() && !c.is_locked());
+}
+
+#[test]
+fn eq_with_itself() {
+    let mutex = Mutex::new(1);
+    assert_eq!(mutex, mutex);
+
+    // The data is compared with itself so `NaN` is not equal to itself.
+    let nan = Mutex::new(f64::NAN);
+    assert_ne!(nan, nan);
+    assert!(!nan.is_locked());
+}
+
+#[test]
+fn clone_is_independent() {
+    let mutex = Mutex::new(vec![1, 2]);
+
+    let clone = mutex.clone();
+    assert!(!mutex.is_locked());
+    assert!(!clone.is_locked());
+
+    clone.lock().push(3);
+    assert_eq!(*mutex.lock(), [1, 2]);
+    assert_eq!(*clone.lock(), [1, 2, 3]);
+}
+
+#[test]
+fn debug_shows_data() {
+    let mutex = Mutex::new(42);
+    assert_eq!(format!("{mutex:?}"), "Mutex { data: 42 }");
+
+    let guard = mutex.lock();
+    assert_eq!(format!("{mutex:?}"), "Mutex { data: <locked> }");
+    drop(guard);
+}
+
+#[test]
+fn guard_mutex_relock() {
+    let mutex = Mutex::new(1);
+
+    let guard = mutex.lock();
+    let locked = MutexGuard::mutex(&guard);
+    assert!(std::ptr::eq(locked, &mutex));
+    drop(guard);
+
+    *locked.lock() += 1;
+    assert_eq!(*mutex.lock(), 2);
+}
+
+#[test]
+fn unlocked_lets_another_thread_lock() {
+    let mutex = Arc::new(Mutex::new(1));
+
+    let mut guard = mutex.lock();
+    MutexGuard::unlocked(&mut guard, || {
+        let m = Arc::clone(&mutex);
+        thread::spawn(move || *m.lock() = 42).join().unwrap();
+    });
+
+    assert!(mutex.is_locked());
+    assert_eq!(*guard, 42);
+    drop(guard);
+    assert!(!mutex.is_locked());
+}
+
+#[test]
+fn unlocked_relocks_on_panic() {
+    let mutex = Mutex::new(1);
+
+    let mut guard = mutex.lock();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        MutexGuard::unlocked(&mut guard, || panic!("unlocked closure panicked"));
+    }));
+
+    assert!(result.is_err());
+    assert!(mutex.is_locked());
+    drop(guard);
+    assert!(!mutex.is_locked());
+}
+
+#[test]
+fn map_locked_extracts_cloned_field() {
+    struct Config {
+        name: String,
+        retries: u32,
+    }
+
+    let mutex = Mutex::new(Config {
+        name: String::from("spinlock"),
+        retries: 3,
+    });
+
+    let name = mutex.map_locked(|config| config.name.clone());
+    let retries = mutex.map_locked(|config| config.retries);
+    assert!(!mutex.is_locked());
+
+    mutex.lock().name.push_str("-rs");
+    assert_eq!(name, "spinlock");
+    assert_eq!(retries, 3);
+    assert_eq!(
+        mutex.map_locked(|config| config.name.clone()),
+        "spinlock-rs"
+    );
+}
+
+#[test]
+fn with_mut_matches_with_lock() {
+    let push = |data: &mut Vec<u32>| {
+        data.push(data.len().try_into().unwrap());
+        data.iter().sum::<u32>()
+    };
+
+    let mut exclusive = Mutex::new(vec![0, 1]);
+    let shared = Mutex::new(vec![0, 1]);
+
+    for _ in 0..3 {
+        assert_eq!(exclusive.with_mut(push), shared.with_lock(push));
+    }
+    assert!(!exclusive.is_locked());
+    assert_eq!(exclusive.into_inner(), shared.into_inner());
+}
+
+#[test]
+fn map_locked_mut_increments_and_returns() {
+    let mutex = Mutex::new(0_u32);
+
+    let increment = || {
+        mutex.map_locked_mut(|counter| {
+            *counter += 1;
+            *counter
+        })
+    };
+    assert_eq!(increment(), 1);
+    assert_eq!(increment(), 2);
+    assert!(!mutex.is_locked());
+    assert_eq!(*mutex.lock(), 2);
+}
+
+#[test]
+fn try_lock_result_ok_and_would_block() {
+    let mutex = Mutex::new(0);
+
+    let guard = mutex.try_lock_result().unwrap();
+    assert_eq!(mutex.try_lock_result().unwrap_err(), WouldBlock);
+    drop(guard);
+
+    assert!(mutex.try_lock_result().is_ok());
+}
+
+#[test]
+#[cfg_attr(
+    feature = "critical-section",
+    ignore = "the critical section of the leaked guard is never exited"
+)]
+fn leak_keeps_mutex_locked() {
+    let mutex = Box::leak(Box::new(Mutex::new(vec![1])));
+
+    let data: &'static mut Vec<i32> = MutexGuard::leak(mutex.lock());
+    data.push(2);
+
+    assert!(mutex.is_locked());
+    assert!(mutex.try_lock().is_none());
+    assert_eq!(*data, [1, 2]);
+}
+
+#[test]
+fn unsized_slice() {
+    let mutex = Mutex::new([1, 2, 3]);
+    let slice: &Mutex<[i32]> = &mutex;
+
+    slice.lock()[1] = 42;
+    assert_eq!(slice.lock().len(), 3);
+    assert_eq!(
+        *MutexGuard::map(slice.lock(), |data| &mut data[1..]),
+        [42, 3]
+    );
+
+    assert_eq!(mutex.into_inner(), [1, 42, 3]);
+}
+
+#[test]
+fn unsized_trait_object() {
+    struct Counter {
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Counter {
+        fn call(&mut self) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let mut counter = Counter {
+        count: Arc::clone(&count),
+    };
+
+    let callback: Box<Mutex<dyn FnMut() + Send>> = Box::new(Mutex::new(move || counter.call()));
+    (callback.lock())();
+    (callback.lock())();
+    assert_eq!(count.load(Ordering::Relaxed), 2);
+
+    let callbacks: [&Mutex<dyn FnMut() + Send>; 2] = [&*callback, &Mutex::new(|| {})];
+    for callback in callbacks {
+        (callback.lock())();
+    }
+    assert_eq!(count.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn guard_explicit_borrows() {
+    fn push_default<T: Default>(guard: &mut MutexGuard<'_, Vec<T>>) {
+        MutexGuard::get_mut(guard).push(T::default());
+    }
+
+    let mutex = Mutex::new(vec![1]);
+
+    let mut guard = mutex.lock();
+    push_default(&mut guard);
+    assert_eq!(MutexGuard::get(&guard), &[1, 0]);
+}
+
+#[cfg(feature = "stats")]
+#[test]
+#[cfg_attr(
+    feature = "critical-section",
+    ignore = "the critical section excludes the other thread before it reaches the lock"
+)]
+fn contention_count_two_threads() {
+    let mutex = Arc::new(Mutex::new(0));
+
+    *mutex.lock() += 1;
+    assert!(mutex.try_lock().is_some());
+    assert_eq!(mutex.contention_count(), 0);
+
+    let guard = mutex.lock();
+    let m = Arc::clone(&mutex);
+    let thread = thread::spawn(move || *m.lock() += 1);
+
+    // The other thread waits as long as the lock is held.
+    while mutex.contention_count() == 0 {
+        thread::yield_now();
+    }
+    drop(guard);
+    thread.join().unwrap();
+
+    assert_eq!(mutex.contention_count(), 1);
+    assert_eq!(*mutex.lock(), 2);
+}
+
+#[test]
+fn ptr_eq_identity() {
+    let a = Mutex::new(0);
+    let b = Mutex::new(0);
+
+    assert!(Mutex::ptr_eq(&a, &a));
+    assert!(!Mutex::ptr_eq(&a, &b));
+    // Equal data does not make the same lock.
+    assert!(a == b);
+
+    let shared = Arc::new(Mutex::new(0));
+    let clone = Arc::clone(&shared);
+    assert!(Mutex::ptr_eq(&shared, &clone));
+    assert!(!Mutex::ptr_eq(&shared, &a));
+}
+
+#[test]
+fn id_stable_and_distinct() {
+    let a = Mutex::new(0);
+    let b = Mutex::new(0);
+
+    assert_eq!(a.id(), a.id());
+    assert_ne!(a.id(), b.id());
+
+    let _guard = a.lock();
+    assert_eq!(a.id(), std::ptr::from_ref(&a).addr());
+    assert_eq!(format!("{a:p}"), format!("{:p}", &raw const a));
+}
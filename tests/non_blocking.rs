@@ -0,0 +1,52 @@
+#![cfg(not(feature = "poison"))]
+
+use spinlock::{Mutex, NonBlockingMutex};
+
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn try_lock_like_mutex() {
+    let non_blocking = NonBlockingMutex::new(0);
+    let mutex = Mutex::new(0);
+
+    let guard = non_blocking.try_lock();
+    let mutex_guard = mutex.try_lock();
+    assert_eq!(guard.is_some(), mutex_guard.is_some());
+    assert_eq!(
+        non_blocking.try_lock().is_some(),
+        mutex.try_lock().is_some()
+    );
+
+    drop((guard, mutex_guard));
+    *non_blocking.try_lock().unwrap() += 1;
+    *mutex.try_lock().unwrap() += 1;
+    assert_eq!(
+        *non_blocking.try_lock().unwrap(),
+        *mutex.try_lock().unwrap()
+    );
+}
+
+#[test]
+fn try_lock_from_threads() {
+    let mutex = Arc::new(NonBlockingMutex::new(0));
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let mutex = Arc::clone(&mutex);
+            thread::spawn(move || {
+                let mut acquired = 0;
+                for _ in 0..10_000 {
+                    if let Some(mut count) = mutex.try_lock() {
+                        *count += 1;
+                        acquired += 1;
+                    }
+                }
+                acquired
+            })
+        })
+        .collect();
+
+    let acquired: i32 = threads.into_iter().map(|t| t.join().unwrap()).sum();
+    assert_eq!(*mutex.try_lock().unwrap(), acquired);
+}
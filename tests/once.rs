@@ -0,0 +1,59 @@
+use spinlock::Once;
+
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn call_once_runs_once() {
+    let once = Once::new();
+    let mut count = 0;
+
+    once.call_once(|| count += 1);
+    assert!(once.is_completed());
+    once.call_once(|| count += 1);
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn concurrent_call_once() {
+    let once = Arc::new(Once::new());
+    let count = Arc::new(AtomicUsize::new(0));
+
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let once = Arc::clone(&once);
+            let count = Arc::clone(&count);
+            thread::spawn(move || {
+                once.call_once(|| {
+                    thread::yield_now();
+                    count.fetch_add(1, Ordering::Relaxed);
+                });
+                // The initialization is complete once `call_once` returns.
+                assert!(once.is_completed());
+                assert_eq!(count.load(Ordering::Relaxed), 1);
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(count.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn panicking_initializer_resets() {
+    let once = Once::new();
+
+    let result = panic::catch_unwind(|| once.call_once(|| panic!("initializer panicked")));
+    assert!(result.is_err());
+    assert!(!once.is_completed());
+
+    let mut count = 0;
+    once.call_once(|| count += 1);
+    assert_eq!(count, 1);
+    assert!(once.is_completed());
+}
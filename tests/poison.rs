@@ -0,0 +1,70 @@
+#![cfg(feature = "poison")]
+
+use spinlock::{Mutex, MutexGuard};
+
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn lock_not_poisoned() {
+    let mutex = Mutex::new(0);
+
+    *mutex.lock().unwrap() += 1;
+    assert!(!mutex.is_poisoned());
+    assert_eq!(*mutex.try_lock().unwrap().unwrap(), 1);
+}
+
+#[test]
+fn panic_poisons_lock() {
+    let mutex = Arc::new(Mutex::new(0));
+
+    let m = Arc::clone(&mutex);
+    let result = thread::spawn(move || {
+        let mut guard = m.lock().unwrap();
+        *guard = 42;
+        panic!("panic while holding the lock");
+    })
+    .join();
+    assert!(result.is_err());
+
+    assert!(mutex.is_poisoned());
+    assert!(!mutex.is_locked());
+
+    let guard = mutex.lock().unwrap_err().into_inner();
+    assert_eq!(*guard, 42);
+    drop(guard);
+    assert!(mutex.try_lock().unwrap().is_err());
+    assert!(mutex.try_lock_result().unwrap().is_err());
+}
+
+#[test]
+fn panic_in_mapped_guard_poisons_lock() {
+    let mutex = Arc::new(Mutex::new((0, 0)));
+
+    let m = Arc::clone(&mutex);
+    let result = thread::spawn(move || {
+        let _guard = MutexGuard::map(m.lock().unwrap(), |data| &mut data.0);
+        panic!("panic while holding the lock");
+    })
+    .join();
+    assert!(result.is_err());
+
+    assert!(mutex.is_poisoned());
+}
+
+#[test]
+fn clear_poison() {
+    let mutex = Arc::new(Mutex::new(0));
+
+    let m = Arc::clone(&mutex);
+    let _ = thread::spawn(move || {
+        let _guard = m.lock().unwrap();
+        panic!("panic while holding the lock");
+    })
+    .join();
+    assert!(mutex.is_poisoned());
+
+    mutex.clear_poison();
+    assert!(!mutex.is_poisoned());
+    assert!(mutex.lock().is_ok());
+}
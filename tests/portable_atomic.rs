@@ -0,0 +1,24 @@
+#![cfg(all(feature = "portable-atomic", not(feature = "poison")))]
+
+use spinlock::{Mutex, RwLock};
+
+#[test]
+fn mutex_lock_unlock() {
+    static MUTEX: Mutex<u32> = Mutex::new(0);
+
+    *MUTEX.lock() += 1;
+    assert!(MUTEX.try_lock().is_some());
+    assert_eq!(*MUTEX.lock(), 1);
+    assert!(!MUTEX.is_locked());
+}
+
+#[test]
+fn rwlock_read_write() {
+    let rwlock = RwLock::new(0);
+
+    *rwlock.write() += 1;
+    let r1 = rwlock.read();
+    let r2 = rwlock.read();
+    assert_eq!(*r1 + *r2, 2);
+    assert!(rwlock.try_write().is_none());
+}
@@ -0,0 +1,129 @@
+#![cfg(not(feature = "loom"))]
+
+use spinlock::RawMutex;
+
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn try_lock_on_locked() {
+    let raw = RawMutex::new();
+
+    assert!(raw.try_lock());
+    assert!(raw.is_locked());
+    assert!(!raw.try_lock());
+
+    // SAFETY: The lock was acquired by `try_lock`.
+    unsafe { raw.unlock() };
+    assert!(!raw.is_locked());
+    assert!(raw.try_lock());
+}
+
+#[test]
+fn try_lock_contended() {
+    static LOCK: RawMutex = RawMutex::new();
+    static ACQUIRED: AtomicUsize = AtomicUsize::new(0);
+    static mut COUNT: usize = 0;
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            thread::spawn(|| {
+                for _ in 0..10_000 {
+                    if LOCK.try_lock() {
+                        // SAFETY: The count is only accessed while holding the lock.
+                        unsafe { *ptr::addr_of_mut!(COUNT) += 1 };
+                        ACQUIRED.fetch_add(1, Ordering::Relaxed);
+                        // SAFETY: The lock was acquired by `try_lock`.
+                        unsafe { LOCK.unlock() };
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    // Each successful attempt had an exclusive access to the count.
+    assert!(LOCK.try_lock());
+    // SAFETY: The count is only accessed while holding the lock.
+    let count = unsafe { *ptr::addr_of!(COUNT) };
+    // SAFETY: The lock was acquired by `try_lock`.
+    unsafe { LOCK.unlock() };
+    assert_eq!(count, ACQUIRED.load(Ordering::Relaxed));
+    assert!(count > 0);
+}
+
+#[test]
+fn lock_contended() {
+    static LOCK: RawMutex = RawMutex::new();
+    static mut COUNT: usize = 0;
+
+    // The threads find the lock held so they all start in the contended path.
+    LOCK.lock();
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            thread::spawn(|| {
+                for _ in 0..1_000 {
+                    LOCK.lock();
+                    // SAFETY: The count is only accessed while holding the lock.
+                    unsafe { *ptr::addr_of_mut!(COUNT) += 1 };
+                    // SAFETY: The lock was acquired by `lock`.
+                    unsafe { LOCK.unlock() };
+                }
+            })
+        })
+        .collect();
+
+    thread::sleep(Duration::from_millis(10));
+    // SAFETY: The lock was acquired by `lock`.
+    unsafe { LOCK.unlock() };
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    LOCK.lock();
+    // SAFETY: The count is only accessed while holding the lock.
+    assert_eq!(unsafe { *ptr::addr_of!(COUNT) }, 4_000);
+    // SAFETY: The lock was acquired by `lock`.
+    unsafe { LOCK.unlock() };
+}
+
+#[test]
+fn guard_separate_region() {
+    static LOCK: RawMutex = RawMutex::new();
+    static mut REGION: [u64; 4] = [0; 4];
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            thread::spawn(|| {
+                for _ in 0..10_000 {
+                    LOCK.lock();
+                    // SAFETY: The region is only accessed while holding the lock.
+                    let region = unsafe { &mut *ptr::addr_of_mut!(REGION) };
+                    for value in region.iter_mut() {
+                        *value += 1;
+                    }
+                    // SAFETY: The lock is held by this thread and the reference
+                    // to the region is no longer used.
+                    unsafe { LOCK.unlock() };
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    LOCK.lock();
+    // SAFETY: The region is only accessed while holding the lock.
+    let region = unsafe { *ptr::addr_of!(REGION) };
+    // SAFETY: The lock is held by this thread.
+    unsafe { LOCK.unlock() };
+    assert_eq!(region, [40_000; 4]);
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "std")]
+
+use spinlock::ReentrantMutex;
+
+use std::cell::{Cell, RefCell};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn nested_lock() {
+    let mutex = ReentrantMutex::new(Cell::new(0));
+
+    let guard1 = mutex.lock();
+    let guard2 = mutex.lock();
+    let guard3 = mutex.try_lock().unwrap();
+    guard3.set(guard1.get() + guard2.get() + 1);
+
+    assert_eq!(guard1.get(), 1);
+}
+
+#[test]
+fn released_after_last_guard() {
+    let mutex = ReentrantMutex::new(0);
+
+    let guard1 = mutex.lock();
+    let guard2 = mutex.lock();
+
+    drop(guard1);
+    assert!(mutex.is_locked());
+
+    drop(guard2);
+    assert!(!mutex.is_locked());
+}
+
+#[test]
+fn recursive_calls() {
+    fn push(mutex: &ReentrantMutex<RefCell<Vec<u32>>>, depth: u32) {
+        let guard = mutex.lock();
+        guard.borrow_mut().push(depth);
+        if depth > 0 {
+            push(mutex, depth - 1);
+        }
+    }
+
+    let mutex = ReentrantMutex::new(RefCell::new(Vec::new()));
+    push(&mutex, 3);
+
+    assert_eq!(*mutex.lock().borrow(), vec![3, 2, 1, 0]);
+    assert!(!mutex.is_locked());
+}
+
+#[test]
+fn try_lock_held_by_other_thread() {
+    let mutex = Arc::new(ReentrantMutex::new(0));
+
+    let _guard = mutex.lock();
+    let _nested = mutex.lock();
+
+    let m = Arc::clone(&mutex);
+    let locked = thread::spawn(move || m.try_lock().is_none())
+        .join()
+        .unwrap();
+    assert!(locked);
+}
+
+#[test]
+fn two_threads_nested_count() {
+    let count = Arc::new(ReentrantMutex::new(Cell::new(0)));
+
+    let threads: Vec<_> = (0..2)
+        .map(|_| {
+            let count = Arc::clone(&count);
+            thread::spawn(move || {
+                for _ in 0..10_000 {
+                    let outer = count.lock();
+                    let inner = count.lock();
+                    inner.set(inner.get() + 1);
+                    outer.set(outer.get() + 1);
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(count.lock().get(), 40_000);
+}
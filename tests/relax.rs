@@ -0,0 +1,122 @@
+#![cfg(all(feature = "std", not(feature = "poison")))]
+
+use spinlock::{Contention, Mutex, PoliteSpin, Relax, RwLock, WaitForEvent, Yield};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+/// Increments a counter behind a `Mutex` relaxing with `R` from 4 threads, 1000
+/// times each, checks that no increment was lost and returns the mutex.
+fn count_with<R: Relax + 'static>() -> Arc<Mutex<i32, R>> {
+    let mutex = Arc::new(Mutex::<i32, R>::with_relax(0));
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let mutex = Arc::clone(&mutex);
+            thread::spawn(move || {
+                for _ in 0..1_000 {
+                    *mutex.lock() += 1;
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(*mutex.lock(), 4_000);
+
+    mutex
+}
+
+#[test]
+fn yield_mutex() {
+    count_with::<Yield>();
+}
+
+#[test]
+fn yield_rwlock() {
+    let rwlock: RwLock<i32, Yield> = RwLock::default();
+
+    *rwlock.write() = 42;
+    assert_eq!(*rwlock.read(), 42);
+    assert!(rwlock.try_write_spin(10).is_some());
+}
+
+#[test]
+fn polite_spin_mutex() {
+    let mutex = count_with::<PoliteSpin>();
+    assert!(mutex.try_lock_spin(10).is_some());
+}
+
+#[test]
+fn contention_spin_only_mutex() {
+    let mutex = Mutex::<i32, Contention<true>>::with_relax(0);
+
+    let guard = mutex.lock();
+    assert!(mutex.try_lock_spin(10).is_none());
+    drop(guard);
+
+    *mutex.lock() += 1;
+    assert_eq!(*mutex.lock(), 1);
+    assert!(!mutex.is_locked());
+}
+
+#[test]
+fn contention_yielding_mutex() {
+    let mutex = count_with::<Contention<false>>();
+    assert!(mutex.try_lock_spin(10).is_some());
+}
+
+#[test]
+fn wait_for_event_mutex() {
+    let mutex = Mutex::<i32, WaitForEvent>::with_relax(0);
+
+    *mutex.lock() += 1;
+    assert_eq!(*mutex.lock(), 1);
+    assert!(!mutex.is_locked());
+}
+
+#[test]
+fn wait_for_event_contended() {
+    count_with::<WaitForEvent>();
+}
+
+#[test]
+fn counting_relax() {
+    static SPINS: AtomicUsize = AtomicUsize::new(0);
+
+    // A strategy of its own, no other lock relaxes with it.
+    struct CountSpins;
+
+    impl Relax for CountSpins {
+        fn relax() {
+            SPINS.fetch_add(1, Ordering::Relaxed);
+            thread::yield_now();
+        }
+    }
+
+    let mutex = Arc::new(Mutex::<i32, CountSpins>::with_relax(0));
+
+    // An uncontended lock never spins.
+    *mutex.lock() += 1;
+    assert_eq!(SPINS.load(Ordering::Relaxed), 0);
+
+    let guard = mutex.lock();
+    assert!(mutex.try_lock_spin(10).is_none());
+    assert_eq!(SPINS.load(Ordering::Relaxed), 10);
+
+    let m = Arc::clone(&mutex);
+    let thread = thread::spawn(move || *m.lock() += 1);
+
+    // The other thread spins through the strategy until the lock is released.
+    while SPINS.load(Ordering::Relaxed) <= 10 {
+        thread::yield_now();
+    }
+    drop(guard);
+    thread.join().unwrap();
+
+    assert_eq!(*mutex.lock(), 2);
+}
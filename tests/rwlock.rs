@@ -1,7 +1,12 @@
-use spinlock::RwLock;
+use spinlock::{
+    Policy, RwLock, RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard, WouldBlock,
+};
 
-use std::sync::Arc;
+use std::mem;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Barrier};
 use std::thread;
+use std::time::Duration;
 
 #[test]
 fn multiple_read_guard() {
@@ -72,3 +77,1374 @@ fn two_threads_count() {
 
     assert_eq!(*count.read(), 2_000_000);
 }
+
+#[test]
+fn reader_count() {
+    let rwlock = RwLock::new(0);
+
+    assert_eq!(rwlock.reader_count(), 0);
+
+    let rguard1 = rwlock.read();
+    let rguard2 = rwlock.read();
+    assert_eq!(rwlock.reader_count(), 2);
+
+    drop(rguard1);
+    drop(rguard2);
+    let _wguard = rwlock.write();
+    assert_eq!(rwlock.reader_count(), 0);
+}
+
+#[test]
+fn is_write_locked() {
+    let rwlock = RwLock::new(0);
+
+    assert!(!rwlock.is_write_locked());
+
+    let rguard = rwlock.read();
+    assert!(!rwlock.is_write_locked());
+    drop(rguard);
+
+    let _wguard = rwlock.write();
+    assert!(rwlock.is_write_locked());
+}
+
+#[test]
+fn map_read_guard() {
+    let rwlock = RwLock::new((0, 1));
+
+    let second = RwLockReadGuard::map(rwlock.read(), |data| &data.1);
+    assert_eq!(*second, 1);
+    assert_eq!(rwlock.reader_count(), 1);
+    assert!(rwlock.try_write().is_none());
+
+    drop(second);
+    assert_eq!(rwlock.reader_count(), 0);
+    assert!(rwlock.try_write().is_some());
+}
+
+#[test]
+fn map_write_guard() {
+    let rwlock = RwLock::new((0, 1));
+
+    let mut second = RwLockWriteGuard::map(rwlock.write(), |data| &mut data.1);
+    *second = 42;
+    assert!(rwlock.is_write_locked());
+    assert!(rwlock.try_read().is_none());
+
+    drop(second);
+    assert!(!rwlock.is_write_locked());
+    assert_eq!(*rwlock.read(), (0, 42));
+}
+
+#[test]
+fn try_map_read_guard_some() {
+    let rwlock = RwLock::new(Some(1));
+
+    let inner = RwLockReadGuard::try_map(rwlock.read(), Option::as_ref).unwrap();
+    assert_eq!(*inner, 1);
+    assert_eq!(rwlock.reader_count(), 1);
+
+    drop(inner);
+    assert_eq!(rwlock.reader_count(), 0);
+}
+
+#[test]
+fn try_map_read_guard_none() {
+    let rwlock = RwLock::new(None::<i32>);
+
+    let guard = RwLockReadGuard::try_map(rwlock.read(), Option::as_ref).unwrap_err();
+    assert_eq!(rwlock.reader_count(), 1);
+
+    drop(guard);
+    assert_eq!(rwlock.reader_count(), 0);
+}
+
+#[test]
+fn try_map_write_guard_some() {
+    let rwlock = RwLock::new(Some(1));
+
+    let mut inner = RwLockWriteGuard::try_map(rwlock.write(), Option::as_mut).unwrap();
+    *inner = 42;
+    assert!(rwlock.is_write_locked());
+
+    drop(inner);
+    assert_eq!(*rwlock.read(), Some(42));
+}
+
+#[test]
+fn try_map_write_guard_none() {
+    let rwlock = RwLock::new(None::<i32>);
+
+    let guard = RwLockWriteGuard::try_map(rwlock.write(), Option::as_mut).unwrap_err();
+    assert!(rwlock.is_write_locked());
+
+    drop(guard);
+    assert!(!rwlock.is_write_locked());
+}
+
+#[test]
+fn upgradeable_read_with_readers() {
+    let rwlock = RwLock::new(0);
+
+    let _rguard = rwlock.read();
+    let uguard = rwlock.upgradeable_read();
+
+    assert!(rwlock.try_read().is_some());
+    assert!(rwlock.try_upgradeable_read().is_none());
+    assert!(rwlock.try_write().is_none());
+    assert_eq!(rwlock.reader_count(), 1);
+
+    drop(uguard);
+    assert!(rwlock.try_upgradeable_read().is_some());
+}
+
+#[test]
+fn try_upgradeable_read_on_upgradeable_locked() {
+    let rwlock = RwLock::new(0);
+
+    let uguard = rwlock.try_upgradeable_read().unwrap();
+    assert!(rwlock.try_upgradeable_read().is_none());
+
+    let rguard = rwlock.try_read();
+    assert!(rguard.is_some());
+    assert!(rwlock.try_upgradeable_read().is_none());
+
+    drop(rguard);
+    drop(uguard);
+    assert!(rwlock.try_upgradeable_read().is_some());
+}
+
+#[test]
+fn try_upgradeable_read_on_write_locked() {
+    let rwlock = RwLock::new(0);
+
+    let wguard = rwlock.write();
+    assert!(rwlock.try_upgradeable_read().is_none());
+
+    drop(wguard);
+    assert!(rwlock.try_upgradeable_read().is_some());
+}
+
+#[test]
+fn try_upgrade_with_readers() {
+    let rwlock = RwLock::new(0);
+
+    let uguard = rwlock.upgradeable_read();
+    let rguard = rwlock.read();
+
+    let uguard = RwLockUpgradableReadGuard::try_upgrade(uguard).unwrap_err();
+    drop(rguard);
+
+    let mut wguard = RwLockUpgradableReadGuard::try_upgrade(uguard).unwrap();
+    *wguard = 1;
+    assert!(rwlock.is_write_locked());
+
+    drop(wguard);
+    assert_eq!(*rwlock.read(), 1);
+    assert!(rwlock.try_upgradeable_read().is_some());
+}
+
+#[test]
+fn with_upgraded_conditionally_mutates() {
+    let rwlock = RwLock::new(vec![1, 5, 2, 8]);
+
+    let mut uguard = rwlock.upgradeable_read();
+    for i in 0..uguard.len() {
+        if uguard[i] > 4 {
+            let previous = RwLockUpgradableReadGuard::with_upgraded(&mut uguard, |data| {
+                mem::replace(&mut data[i], 0)
+            });
+            assert!(previous > 4);
+        }
+        // The upgradeable read access is held again once the closure returns.
+        assert!(!rwlock.is_write_locked());
+        assert!(rwlock.try_upgradeable_read().is_none());
+        assert!(rwlock.try_read().is_some());
+    }
+
+    assert_eq!(*uguard, [1, 0, 2, 0]);
+    drop(uguard);
+    assert!(rwlock.try_write().is_some());
+}
+
+#[test]
+fn with_upgraded_downgrades_on_panic() {
+    let rwlock = RwLock::new(0);
+
+    let mut uguard = rwlock.upgradeable_read();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        RwLockUpgradableReadGuard::with_upgraded(&mut uguard, |data| {
+            *data = 1;
+            panic!("panic while upgraded");
+        });
+    }));
+
+    assert!(result.is_err());
+    assert_eq!(*uguard, 1);
+    assert!(!rwlock.is_write_locked());
+    assert!(rwlock.try_upgradeable_read().is_none());
+    drop(uguard);
+    assert!(rwlock.try_write().is_some());
+}
+
+#[test]
+fn upgrade_waits_for_readers() {
+    let rwlock = Arc::new(RwLock::new(0));
+
+    let uguard = rwlock.upgradeable_read();
+
+    let (tx, rx) = mpsc::channel();
+    let r = Arc::clone(&rwlock);
+    let reader = thread::spawn(move || {
+        let rguard = r.read();
+        tx.send(()).unwrap();
+
+        // The upgrade must not be able to modify the data while we are reading.
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(*rguard, 0);
+    });
+
+    rx.recv().unwrap();
+    let mut wguard = RwLockUpgradableReadGuard::upgrade(uguard);
+    assert_eq!(rwlock.reader_count(), 0);
+    *wguard = 42;
+    drop(wguard);
+
+    reader.join().unwrap();
+    assert_eq!(*rwlock.read(), 42);
+}
+
+#[test]
+fn downgrade_keeps_writers_out() {
+    let rwlock = Arc::new(RwLock::new(0));
+
+    let mut wguard = rwlock.write();
+
+    let r = Arc::clone(&rwlock);
+    let writer = thread::spawn(move || {
+        *r.write() = 2;
+    });
+
+    *wguard = 1;
+    let rguard = RwLockWriteGuard::downgrade(wguard);
+    assert_eq!(rwlock.reader_count(), 1);
+
+    // Gives time to the other writer to try to acquire the lock.
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(*rguard, 1);
+
+    drop(rguard);
+    writer.join().unwrap();
+    assert_eq!(*rwlock.read(), 2);
+}
+
+#[test]
+fn downgrade_to_upgradeable_then_upgrade() {
+    let rwlock = Arc::new(RwLock::new(0));
+
+    let mut wguard = rwlock.write();
+    *wguard = 1;
+    let uguard = RwLockWriteGuard::downgrade_to_upgradeable(wguard);
+    assert!(!rwlock.is_write_locked());
+    assert!(rwlock.try_upgradeable_read().is_none());
+    assert!(rwlock.try_write().is_none());
+
+    // A reader joins while the upgradeable read access is held.
+    let (tx, rx) = mpsc::channel();
+    let r = Arc::clone(&rwlock);
+    let reader = thread::spawn(move || {
+        let rguard = r.read();
+        tx.send(*rguard).unwrap();
+        thread::sleep(Duration::from_millis(20));
+    });
+    assert_eq!(rx.recv().unwrap(), 1);
+
+    // Upgrading waits for the reader to leave.
+    let mut wguard = RwLockUpgradableReadGuard::upgrade(uguard);
+    assert_eq!(rwlock.reader_count(), 0);
+    *wguard = 2;
+    drop(wguard);
+
+    reader.join().unwrap();
+    assert_eq!(*rwlock.read(), 2);
+}
+
+#[test]
+fn write_then_downgrade_keeps_writers_out() {
+    let rwlock = Arc::new(RwLock::new(0));
+
+    let threads: Vec<_> = (1..=4)
+        .map(|id| {
+            let rwlock = Arc::clone(&rwlock);
+            thread::spawn(move || {
+                for _ in 0..200 {
+                    let rguard = rwlock.write_then_downgrade(|data| *data = id);
+                    thread::yield_now();
+                    assert_eq!(*rguard, id);
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(rwlock.reader_count(), 0);
+}
+
+#[test]
+fn concurrent_try_read_on_write_locked() {
+    let rwlock = Arc::new(RwLock::new(0));
+
+    let wguard = rwlock.write();
+
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let r = Arc::clone(&rwlock);
+            thread::spawn(move || {
+                for _ in 0..10_000 {
+                    assert!(r.try_read().is_none());
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert!(rwlock.is_write_locked());
+    drop(wguard);
+    assert!(rwlock.try_read().is_some());
+}
+
+#[test]
+fn with_read_releases_lock() {
+    let rwlock = RwLock::new(vec![1, 2, 3]);
+
+    let position = rwlock.with_read(|data| {
+        assert_eq!(rwlock.reader_count(), 1);
+        data.iter().position(|&x| x == 2)
+    });
+    assert_eq!(position, Some(1));
+    assert_eq!(rwlock.reader_count(), 0);
+}
+
+#[test]
+fn with_write_releases_lock() {
+    let rwlock = RwLock::new(vec![1, 2, 3]);
+
+    let found = rwlock.with_write(|data| {
+        for x in data.iter_mut() {
+            if *x == 2 {
+                *x = 42;
+                return true;
+            }
+        }
+        false
+    });
+    assert!(found);
+    assert!(!rwlock.is_write_locked());
+    assert_eq!(*rwlock.read(), [1, 42, 3]);
+}
+
+#[test]
+fn scoped_read_released_on_panic() {
+    let rwlock = RwLock::new(1);
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        rwlock.scoped_read(|_| panic!("read failed"))
+    }));
+    assert!(result.is_err());
+
+    assert_eq!(rwlock.reader_count(), 0);
+    *rwlock.try_write().unwrap() = 2;
+    assert_eq!(rwlock.scoped_read(|data| *data), 2);
+}
+
+#[test]
+fn scoped_write_released_on_panic() {
+    let rwlock = RwLock::new(1);
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        rwlock.scoped_write(|data| {
+            *data = 2;
+            panic!("write failed");
+        })
+    }));
+    assert!(result.is_err());
+
+    assert!(!rwlock.is_write_locked());
+    rwlock.scoped_write(|data| *data += 1);
+    assert_eq!(*rwlock.try_read().unwrap(), 3);
+}
+
+#[test]
+fn try_read_result_ok_and_would_block() {
+    let rwlock = RwLock::new(0);
+
+    let rguard = rwlock.try_read_result().unwrap();
+    assert!(rwlock.try_read_result().is_ok());
+    drop(rguard);
+
+    let _wguard = rwlock.write();
+    assert_eq!(rwlock.try_read_result().unwrap_err(), WouldBlock);
+}
+
+#[test]
+fn try_write_result_ok_and_would_block() {
+    let rwlock = RwLock::new(0);
+
+    let wguard = rwlock.try_write_result().unwrap();
+    assert_eq!(rwlock.try_write_result().unwrap_err(), WouldBlock);
+    drop(wguard);
+
+    let _rguard = rwlock.read();
+    assert_eq!(rwlock.try_write_result().unwrap_err(), WouldBlock);
+}
+
+#[test]
+fn try_read_spin_exhausted() {
+    let rwlock = RwLock::new(0);
+
+    let wguard = rwlock.write();
+    assert!(rwlock.try_read_spin(1_000).is_none());
+
+    drop(wguard);
+    let rguard = rwlock.try_read_spin(1).unwrap();
+    assert!(rwlock.try_read_spin(1).is_some());
+    drop(rguard);
+}
+
+#[test]
+fn try_write_spin_exhausted() {
+    let rwlock = RwLock::new(0);
+
+    let rguard = rwlock.read();
+    assert!(rwlock.try_write_spin(1_000).is_none());
+
+    drop(rguard);
+    let wguard = rwlock.try_write_spin(1).unwrap();
+    assert!(rwlock.try_write_spin(1_000).is_none());
+    drop(wguard);
+}
+
+#[test]
+fn try_write_patient_reader_leaving() {
+    let rwlock = Arc::new(RwLock::new(0));
+    let barrier = Arc::new(Barrier::new(2));
+
+    let r = Arc::clone(&rwlock);
+    let b = Arc::clone(&barrier);
+    let reader = thread::spawn(move || {
+        let rguard = r.read();
+        b.wait();
+        thread::sleep(Duration::from_millis(1));
+        drop(rguard);
+    });
+
+    barrier.wait();
+    assert!(rwlock.try_write().is_none());
+    // The budget covers the sleep of the reader, it is left as soon as the
+    // reader drops its guard.
+    *rwlock.try_write_patient(usize::MAX).unwrap() = 1;
+
+    reader.join().unwrap();
+    assert_eq!(*rwlock.read(), 1);
+}
+
+#[test]
+fn try_write_patient_gives_up_on_writer() {
+    let rwlock = RwLock::new(0);
+
+    let wguard = rwlock.write();
+    assert!(rwlock.try_write_patient(usize::MAX).is_none());
+    drop(wguard);
+
+    let uguard = rwlock.upgradeable_read();
+    assert!(rwlock.try_write_patient(usize::MAX).is_none());
+    drop(uguard);
+
+    let rguard = rwlock.read();
+    assert!(rwlock.try_write_patient(10).is_none());
+    drop(rguard);
+    assert!(rwlock.try_write_patient(0).is_some());
+}
+
+#[test]
+fn try_write_spin_with_reader_threads() {
+    let rwlock = Arc::new(RwLock::new(0));
+    let barrier = Arc::new(Barrier::new(5));
+
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let r = Arc::clone(&rwlock);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                let rguard = r.read();
+                // Holds the read access until the bounded write gave up.
+                barrier.wait();
+                barrier.wait();
+                drop(rguard);
+            })
+        })
+        .collect();
+
+    barrier.wait();
+    assert!(rwlock.try_write_spin(10_000).is_none());
+    assert_eq!(rwlock.reader_count(), 4);
+    barrier.wait();
+
+    for reader in readers {
+        reader.join().unwrap();
+    }
+
+    *rwlock.try_write_spin(0).unwrap() += 1;
+    assert_eq!(*rwlock.read(), 1);
+}
+
+#[test]
+fn write_biased_and_upgrade_count() {
+    let rwlock = Arc::new(RwLock::new(0));
+
+    let threads: Vec<_> = (0..3)
+        .map(|kind| {
+            let rwlock = Arc::clone(&rwlock);
+            thread::spawn(move || {
+                for _ in 0..500 {
+                    match kind {
+                        0 => *rwlock.write() += 1,
+                        1 => *rwlock.write_biased() += 1,
+                        _ => *RwLockUpgradableReadGuard::upgrade(rwlock.upgradeable_read()) += 1,
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(*rwlock.read(), 1_500);
+}
+
+#[test]
+fn write_biased_not_starved_by_readers() {
+    let rwlock = Arc::new(RwLock::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let r = Arc::clone(&rwlock);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let guard = r.read();
+                    thread::sleep(Duration::from_millis(1));
+                    drop(guard);
+                }
+            })
+        })
+        .collect();
+
+    thread::sleep(Duration::from_millis(20));
+    *rwlock.write_biased() = 42;
+    stop.store(true, Ordering::Relaxed);
+
+    for reader in readers {
+        reader.join().unwrap();
+    }
+
+    assert_eq!(*rwlock.read(), 42);
+}
+
+#[test]
+fn read_waits_for_every_biased_writer() {
+    let rwlock = Arc::new(RwLock::new(Vec::new()));
+    let (acquired_tx, acquired_rx) = mpsc::channel();
+
+    // Both biased writers wait for the reader to leave.
+    let reader = rwlock.read();
+    let (release_txs, writers): (Vec<_>, Vec<_>) = (0..2)
+        .map(|id| {
+            let w = Arc::clone(&rwlock);
+            let acquired_tx = acquired_tx.clone();
+            let (release_tx, release_rx) = mpsc::channel::<()>();
+            let writer = thread::spawn(move || {
+                let mut guard = w.write_biased();
+                guard.push(id);
+                // Lets the new readers in as far as this writer is concerned.
+                let guard = RwLockWriteGuard::downgrade(guard);
+                acquired_tx.send(id).unwrap();
+                release_rx.recv().unwrap();
+                drop(guard);
+            });
+            (release_tx, writer)
+        })
+        .unzip();
+    thread::sleep(Duration::from_millis(50));
+    drop(reader);
+
+    // The second writer is still waiting after the first one acquired the
+    // lock, so a new reader keeps waiting for it.
+    let first = acquired_rx.recv().unwrap();
+    let r = Arc::clone(&rwlock);
+    let reader = thread::spawn(move || r.read().len());
+    thread::sleep(Duration::from_millis(50));
+    assert!(!reader.is_finished());
+    release_txs[first].send(()).unwrap();
+
+    let second = acquired_rx.recv().unwrap();
+    assert_ne!(first, second);
+    release_txs[second].send(()).unwrap();
+    assert_eq!(reader.join().unwrap(), 2);
+
+    for writer in writers {
+        writer.join().unwrap();
+    }
+}
+
+#[test]
+fn write_with_churning_readers() {
+    const WRITES: usize = 100;
+
+    let rwlock = Arc::new(RwLock::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let r = Arc::clone(&rwlock);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                // Readers overlap so the reader count keeps moving between 1 and 2
+                // and is only briefly 0.
+                while !stop.load(Ordering::Relaxed) {
+                    let guard = r.read();
+                    assert!(*guard <= WRITES);
+                    drop(guard);
+                }
+            })
+        })
+        .collect();
+
+    for _ in 0..WRITES {
+        *rwlock.write() += 1;
+    }
+    stop.store(true, Ordering::Relaxed);
+
+    for reader in readers {
+        reader.join().unwrap();
+    }
+
+    assert_eq!(*rwlock.read(), WRITES);
+}
+
+#[test]
+fn heavy_read_with_writers() {
+    const READERS: usize = 4;
+    const WRITERS: usize = 2;
+    const WRITES: usize = 100;
+
+    let rwlock = Arc::new(RwLock::new((0, 0)));
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let readers: Vec<_> = (0..READERS)
+        .map(|_| {
+            let r = Arc::clone(&rwlock);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    // A writer updates both halves at once, so a reader
+                    // overlapping a writer would observe them differ.
+                    let guard = r.read();
+                    assert_eq!(guard.0, guard.1);
+                    drop(guard);
+
+                    let guard = r.read_recursive();
+                    assert_eq!(guard.0, guard.1);
+                }
+            })
+        })
+        .collect();
+
+    let writers: Vec<_> = (0..WRITERS)
+        .map(|_| {
+            let w = Arc::clone(&rwlock);
+            thread::spawn(move || {
+                for _ in 0..WRITES {
+                    let mut guard = w.write();
+                    guard.0 += 1;
+                    guard.1 += 1;
+                }
+            })
+        })
+        .collect();
+
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    stop.store(true, Ordering::Relaxed);
+    for reader in readers {
+        reader.join().unwrap();
+    }
+
+    // The transient increments of the readers were all rolled back.
+    assert_eq!(rwlock.reader_count(), 0);
+    assert!(!rwlock.is_write_locked());
+    assert_eq!(
+        *rwlock.try_write().unwrap(),
+        (WRITERS * WRITES, WRITERS * WRITES)
+    );
+}
+
+#[test]
+fn force_read_unlock_forgotten_guard() {
+    let rwlock = RwLock::new(0);
+
+    mem::forget(rwlock.read());
+    mem::forget(rwlock.read());
+    assert_eq!(rwlock.reader_count(), 2);
+
+    // SAFETY: Both guards were forgotten.
+    unsafe {
+        rwlock.force_read_unlock();
+        rwlock.force_read_unlock();
+    }
+    assert_eq!(rwlock.reader_count(), 0);
+    assert!(rwlock.try_write().is_some());
+}
+
+#[test]
+fn force_write_unlock_forgotten_guard() {
+    let rwlock = RwLock::new(0);
+
+    let mut guard = rwlock.write();
+    *guard += 1;
+    mem::forget(guard);
+    assert!(rwlock.is_write_locked());
+
+    // SAFETY: The guard was forgotten.
+    unsafe { rwlock.force_write_unlock() };
+    assert!(!rwlock.is_write_locked());
+    assert_eq!(*rwlock.read(), 1);
+}
+
+#[test]
+fn try_read_burst_on_write_locked() {
+    let rwlock = Arc::new(RwLock::new((0_u64, 0_u64)));
+    let mut writer = rwlock.write();
+
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let rwlock = Arc::clone(&rwlock);
+            thread::spawn(move || {
+                for _ in 0..10_000 {
+                    assert!(rwlock.try_read().is_none());
+                    assert!(rwlock.try_read_recursive().is_none());
+                }
+            })
+        })
+        .collect();
+
+    // The failed attempts never show up in the lock while the writer works.
+    for i in 1..=1_000 {
+        *writer = (i, i);
+        assert_eq!(rwlock.reader_count(), 0);
+        assert!(rwlock.is_write_locked());
+    }
+
+    for reader in readers {
+        reader.join().unwrap();
+    }
+
+    assert_eq!(rwlock.reader_count(), 0);
+    assert_eq!(*writer, (1_000, 1_000));
+    drop(writer);
+    assert!(!rwlock.is_write_locked());
+    assert_eq!(*rwlock.try_read().unwrap(), (1_000, 1_000));
+}
+
+#[test]
+fn guard_display() {
+    let number = RwLock::new(42);
+    let name = RwLock::new(String::from("spinlock"));
+
+    assert_eq!(number.read().to_string(), "42");
+    assert_eq!(format!("{:>4}", number.write()), "  42");
+    assert_eq!(number.upgradeable_read().to_string(), "42");
+    assert_eq!(format!("[{}]", name.read()), "[spinlock]");
+    assert_eq!(format!("[{}]", name.write()), "[spinlock]");
+    assert_eq!(
+        RwLockReadGuard::map(name.read(), |name| &name[4..]).to_string(),
+        "lock"
+    );
+}
+
+#[test]
+fn guard_hash() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of<K: Hash>(key: K) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let rwlock = RwLock::new((1, "spinlock"));
+
+    let mut hashes = HashSet::new();
+    hashes.insert(hash_of(rwlock.read()));
+    hashes.insert(hash_of(rwlock.write()));
+    hashes.insert(hash_of(rwlock.upgradeable_read()));
+    assert_eq!(hashes.len(), 1);
+    assert!(hashes.contains(&hash_of((1, "spinlock"))));
+
+    hashes.insert(hash_of(RwLockReadGuard::map(rwlock.read(), |data| &data.1)));
+    assert!(hashes.contains(&hash_of("spinlock")));
+    assert_eq!(rwlock.reader_count(), 0);
+}
+
+#[test]
+fn guard_comparison() {
+    let a = RwLock::new(1);
+    let b = RwLock::new(2);
+
+    // The guards compare the locked data, not the identity of the rwlocks.
+    assert!(a.read() < b.read());
+    assert!(a.write() < b.write());
+    assert!(a.read() == a.read());
+    assert!(a.upgradeable_read() != b.upgradeable_read());
+
+    let rwlocks = [RwLock::new(3), RwLock::new(1), RwLock::new(2)];
+    let mut guards: Vec<_> = rwlocks.iter().map(RwLock::read).collect();
+    guards.sort_by(|a, b| b.cmp(a));
+    let sorted: Vec<_> = guards.iter().map(|guard| **guard).collect();
+    assert_eq!(sorted, [3, 2, 1]);
+    drop(guards);
+
+    let mut guards: Vec<_> = rwlocks.iter().map(RwLock::write).collect();
+    guards.sort();
+    *guards[0] = 0;
+    drop(guards);
+    assert_eq!(*rwlocks[1].read(), 0);
+}
+
+#[test]
+fn guard_borrow() {
+    use std::borrow::{Borrow, BorrowMut};
+
+    fn sum<B: Borrow<Vec<u32>>>(value: B) -> u32 {
+        value.borrow().iter().sum()
+    }
+
+    fn push<B: BorrowMut<Vec<u32>>>(mut value: B) {
+        value.borrow_mut().push(3);
+    }
+
+    let rwlock = RwLock::new(vec![1, 2]);
+
+    push(rwlock.write());
+    assert_eq!(sum(rwlock.read()), 6);
+    assert_eq!(sum(rwlock.upgradeable_read()), 6);
+    push(RwLockWriteGuard::map(rwlock.write(), |data| data));
+    assert_eq!(sum(RwLockReadGuard::map(rwlock.read(), |data| data)), 9);
+    assert_eq!(rwlock.reader_count(), 0);
+    assert!(!rwlock.is_write_locked());
+}
+
+#[test]
+fn optimistic_read_invalidated_by_writer() {
+    let rwlock = RwLock::new(1_u64);
+
+    let token = rwlock.optimistic_read();
+    // SAFETY: The copy is only trusted once the token is validated.
+    let value = unsafe { token.read() };
+    assert!(token.validate());
+    assert_eq!(value, 1);
+
+    // Readers don't invalidate the token.
+    drop(rwlock.read());
+    assert!(token.validate());
+
+    *rwlock.write() = 2;
+    assert!(!token.validate());
+
+    // A token started during a write never validates.
+    let guard = rwlock.write();
+    let token = rwlock.optimistic_read();
+    drop(guard);
+    assert!(!token.validate());
+
+    let token = rwlock.optimistic_read();
+    // SAFETY: The copy is only trusted once the token is validated.
+    assert_eq!(unsafe { token.read() }, 2);
+    assert!(token.validate());
+}
+
+#[test]
+fn optimistic_read_invalidated_by_upgrade() {
+    let rwlock = RwLock::new(1);
+
+    let upgradeable = rwlock.upgradeable_read();
+    let token = rwlock.optimistic_read();
+    assert!(token.validate());
+
+    let writer = RwLockUpgradableReadGuard::upgrade(upgradeable);
+    assert!(!token.validate());
+    drop(writer);
+}
+
+#[test]
+fn optimistic_read_invalidated_by_with_upgraded() {
+    let rwlock = RwLock::new(1);
+
+    let mut upgradeable = rwlock.upgradeable_read();
+    let token = rwlock.optimistic_read();
+    assert!(token.validate());
+
+    RwLockUpgradableReadGuard::with_upgraded(&mut upgradeable, |data| *data = 2);
+    assert!(!token.validate());
+    assert_eq!(*upgradeable, 2);
+    drop(upgradeable);
+
+    let token = rwlock.optimistic_read();
+    // SAFETY: The copy is only trusted once the token is validated.
+    assert_eq!(unsafe { token.read() }, 2);
+    assert!(token.validate());
+}
+
+#[test]
+fn optimistic_readers_never_trust_torn_values() {
+    let rwlock = Arc::new(RwLock::new((0_u64, 0_u64)));
+    let done = Arc::new(AtomicBool::new(false));
+
+    let readers: Vec<_> = (0..2)
+        .map(|_| {
+            let rwlock = Arc::clone(&rwlock);
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                while !done.load(Ordering::Relaxed) {
+                    let token = rwlock.optimistic_read();
+                    // SAFETY: The copy is only trusted once the token is validated.
+                    let (a, b) = unsafe { token.read() };
+                    if token.validate() {
+                        assert_eq!(a, b);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    // Every way to write the data publishes it through the version.
+    for i in 1..=3_000 {
+        match i % 3 {
+            0 => *rwlock.write() = (i, i),
+            1 => *RwLockWriteGuard::map(rwlock.write(), |data| data) = (i, i),
+            _ => {
+                let mut upgradeable = rwlock.upgradeable_read();
+                RwLockUpgradableReadGuard::with_upgraded(&mut upgradeable, |data| {
+                    *data = (i, i);
+                });
+            }
+        }
+    }
+    done.store(true, Ordering::Relaxed);
+
+    for reader in readers {
+        reader.join().unwrap();
+    }
+
+    let token = rwlock.optimistic_read();
+    // SAFETY: The copy is only trusted once the token is validated.
+    assert_eq!(unsafe { token.read() }, (3_000, 3_000));
+    assert!(token.validate());
+}
+
+#[test]
+fn optimistic_read_of_new_locked() {
+    let rwlock = RwLock::new_locked(0);
+
+    let token = rwlock.optimistic_read();
+    // SAFETY: The write access is held by no guard, it was created locked.
+    unsafe { rwlock.force_write_unlock() };
+    assert!(!token.validate());
+    assert!(rwlock.optimistic_read().validate());
+}
+
+#[test]
+fn new_locked_blocks_readers_until_unlocked() {
+    let rwlock = Arc::new(RwLock::new_locked(0));
+    assert!(rwlock.is_write_locked());
+    assert!(rwlock.try_read().is_none());
+    assert!(rwlock.try_write().is_none());
+
+    let (tx, rx) = mpsc::channel();
+    let reader = {
+        let rwlock = Arc::clone(&rwlock);
+        thread::spawn(move || {
+            let value = *rwlock.read();
+            tx.send(value).unwrap();
+        })
+    };
+
+    thread::sleep(Duration::from_millis(50));
+    assert!(rx.try_recv().is_err());
+
+    // SAFETY: The rwlock was created locked and no guard holds the write
+    // access.
+    unsafe {
+        *rwlock.data_ptr() = 42;
+        rwlock.force_write_unlock();
+    }
+
+    assert_eq!(rx.recv().unwrap(), 42);
+    reader.join().unwrap();
+    assert!(!rwlock.is_write_locked());
+}
+
+#[test]
+fn from_value() {
+    let rwlock: RwLock<i32> = 5.into();
+    assert!(!rwlock.is_write_locked());
+    assert_eq!(*rwlock.read(), 5);
+}
+
+#[test]
+fn eq_compares_data() {
+    let a = RwLock::new(1);
+    let b = RwLock::new(1);
+    let c = RwLock::new(2);
+
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert_eq!(a, a);
+    assert_eq!(a.reader_count(), 0);
+
+    // The comparison only needs shared read accesses.
+    let r = a.read();
+    assert_eq!(a, b);
+    drop(r);
+}
+
+#[test]
+fn clone_is_independent() {
+    let rwlock = RwLock::new(vec![1, 2]);
+
+    let r = rwlock.read();
+    let clone = rwlock.clone();
+    assert_eq!(clone.reader_count(), 0);
+    assert!(!clone.is_write_locked());
+    drop(r);
+
+    clone.write().push(3);
+    assert_eq!(*rwlock.read(), [1, 2]);
+    assert_eq!(*clone.read(), [1, 2, 3]);
+}
+
+#[test]
+fn debug_shows_data() {
+    let rwlock = RwLock::new(42);
+    assert_eq!(format!("{rwlock:?}"), "RwLock { data: 42 }");
+
+    let r = rwlock.read();
+    assert_eq!(format!("{rwlock:?}"), "RwLock { data: 42 }");
+    drop(r);
+
+    let w = rwlock.write();
+    assert_eq!(format!("{rwlock:?}"), "RwLock { data: <locked> }");
+    drop(w);
+}
+
+#[test]
+fn guard_rwlock_relock() {
+    let rwlock = RwLock::new(1);
+
+    let r = rwlock.read();
+    let locked = RwLockReadGuard::rwlock(&r);
+    assert!(std::ptr::eq(locked, &rwlock));
+    drop(r);
+
+    let w = locked.write();
+    let locked = RwLockWriteGuard::rwlock(&w);
+    assert!(locked.is_write_locked());
+    drop(w);
+
+    *locked.write() += 1;
+    assert_eq!(*rwlock.read(), 2);
+}
+
+#[test]
+fn read_unlocked_lets_writer_lock() {
+    let rwlock = Arc::new(RwLock::new(1));
+
+    let mut guard = rwlock.read();
+    let value = RwLockReadGuard::unlocked(&mut guard, || {
+        let w = Arc::clone(&rwlock);
+        thread::spawn(move || *w.write() = 42).join().unwrap();
+        rwlock.reader_count()
+    });
+
+    assert_eq!(value, 0);
+    assert_eq!(rwlock.reader_count(), 1);
+    assert_eq!(*guard, 42);
+    drop(guard);
+    assert_eq!(rwlock.reader_count(), 0);
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn stats_count_writer_spins() {
+    let rwlock = Arc::new(RwLock::new(0));
+
+    *rwlock.write() += 1;
+    drop(rwlock.read());
+    assert_eq!(rwlock.stats(), spinlock::LockStats::default());
+
+    let rguard = rwlock.read();
+    let w = Arc::clone(&rwlock);
+    let writer = thread::spawn(move || *w.write() += 1);
+
+    // The writer spins as long as the read access is held.
+    while rwlock.stats().write_spins == 0 {
+        thread::yield_now();
+    }
+    drop(rguard);
+    writer.join().unwrap();
+
+    let stats = rwlock.stats();
+    assert!(stats.write_spins > 0);
+    assert_eq!(stats.read_spins, 0);
+    assert_eq!(*rwlock.read(), 2);
+}
+
+#[test]
+fn read_recursive_nested() {
+    let rwlock = RwLock::new(1);
+
+    let r1 = rwlock.read_recursive();
+    let r2 = rwlock.read_recursive();
+    let r3 = rwlock.read_recursive();
+    assert_eq!(rwlock.reader_count(), 3);
+    assert_eq!(*r1 + *r2 + *r3, 3);
+
+    drop((r1, r2, r3));
+    assert_eq!(rwlock.reader_count(), 0);
+    assert!(rwlock.try_write().is_some());
+}
+
+#[test]
+fn read_recursive_with_biased_writer_waiting() {
+    let rwlock = Arc::new(RwLock::new(0));
+
+    let r1 = rwlock.read();
+    let w = Arc::clone(&rwlock);
+    let writer = thread::spawn(move || *w.write_biased() = 42);
+
+    // Let the writer wait, `read` would now deadlock.
+    thread::sleep(Duration::from_millis(20));
+    let r2 = rwlock.read_recursive();
+    assert_eq!(*r1 + *r2, 0);
+
+    drop((r1, r2));
+    writer.join().unwrap();
+    assert_eq!(*rwlock.read(), 42);
+}
+
+#[test]
+fn try_read_recursive_nested() {
+    let rwlock = RwLock::new(1);
+
+    let r1 = rwlock.read_recursive();
+    let r2 = rwlock.try_read_recursive().unwrap();
+    let r3 = rwlock.try_read_recursive().unwrap();
+    assert_eq!(rwlock.reader_count(), 3);
+    assert_eq!(*r1 + *r2 + *r3, 3);
+
+    drop((r1, r2, r3));
+    assert_eq!(rwlock.reader_count(), 0);
+    assert!(rwlock.try_write().is_some());
+}
+
+#[test]
+fn try_read_recursive_on_write_locked() {
+    let rwlock = RwLock::new(0);
+
+    let wguard = rwlock.write();
+    assert!(rwlock.try_read_recursive().is_none());
+
+    drop(wguard);
+    assert!(rwlock.try_read_recursive().is_some());
+}
+
+#[test]
+fn unsized_slice() {
+    let rwlock = RwLock::new([1, 2, 3]);
+    let slice: &RwLock<[i32]> = &rwlock;
+
+    slice.write()[1] = 42;
+    let r1 = slice.read();
+    let r2 = slice.read();
+    assert_eq!(*r1, [1, 42, 3]);
+    assert_eq!(r2.len(), 3);
+}
+
+#[test]
+fn guard_explicit_borrows() {
+    fn push_default<T: Default>(guard: &mut RwLockWriteGuard<'_, Vec<T>>) {
+        RwLockWriteGuard::get_mut(guard).push(T::default());
+    }
+
+    let rwlock = RwLock::new(vec![1]);
+
+    let mut wguard = rwlock.write();
+    push_default(&mut wguard);
+    assert_eq!(RwLockWriteGuard::get(&wguard), &[1, 0]);
+    drop(wguard);
+
+    let rguard = rwlock.read();
+    assert_eq!(RwLockReadGuard::get(&rguard).len(), 2);
+}
+
+#[test]
+fn ptr_eq_while_write_locked() {
+    let a = RwLock::new([1, 2, 3]);
+    let b = RwLock::new([1, 2, 3]);
+
+    // Comparing the identities never takes the lock, even through unsized
+    // views of it.
+    let _wguard = a.write();
+    let slice: &RwLock<[i32]> = &a;
+    assert!(RwLock::ptr_eq(slice, &a));
+    assert!(!RwLock::ptr_eq(slice, &b));
+}
+
+#[test]
+fn id_stable_across_guards() {
+    let rwlock = RwLock::new(0);
+    let id = rwlock.id();
+
+    let rguard = rwlock.read();
+    assert_eq!(rwlock.id(), id);
+    drop(rguard);
+
+    let uguard = rwlock.upgradeable_read();
+    assert_eq!(rwlock.id(), id);
+    let _wguard = RwLockUpgradableReadGuard::upgrade(uguard);
+    assert_eq!(rwlock.id(), id);
+    assert_eq!(format!("{rwlock:p}"), format!("{:p}", &raw const rwlock));
+}
+
+#[test]
+fn get_mut_without_locking() {
+    let mut rwlock = RwLock::new(vec![1]);
+
+    rwlock.get_mut().push(2);
+    assert_eq!(*rwlock.read(), [1, 2]);
+}
+
+#[test]
+fn map_in_place_string() {
+    let mut rwlock = RwLock::new(String::from("spin"));
+
+    rwlock.map_in_place(|mut data| {
+        data.push_str("lock");
+        data
+    });
+    rwlock.map_in_place(|data| data.to_uppercase());
+    assert_eq!(*rwlock.read(), "SPINLOCK");
+    assert_eq!(rwlock.reader_count(), 0);
+    assert!(!rwlock.is_write_locked());
+}
+
+#[test]
+fn map_in_place_panic_aborts() {
+    // Set in the child process running this same test.
+    const CHILD: &str = "SPINLOCK_MAP_IN_PLACE_CHILD";
+
+    if std::env::var_os(CHILD).is_some() {
+        let mut rwlock = RwLock::new(String::from("data"));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            rwlock.map_in_place(|_| panic!("transform failed"));
+        }));
+        // Unreachable, catching the panic would drop the moved out string
+        // again along with the rwlock.
+        drop((result, rwlock));
+        std::process::exit(0);
+    }
+
+    let status = std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "map_in_place_panic_aborts", "--test-threads=1"])
+        .env(CHILD, "1")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .unwrap();
+    assert!(!status.success());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        // SIGABRT
+        assert_eq!(status.signal(), Some(6));
+    }
+}
+
+// Relaxes by counting the relaxations in `SPINS[ID]`, one counter per test.
+struct CountSpins<const ID: usize>;
+
+static SPINS: [AtomicUsize; 2] = [AtomicUsize::new(0), AtomicUsize::new(0)];
+
+impl<const ID: usize> spinlock::Relax for CountSpins<ID> {
+    fn relax() {
+        SPINS[ID].fetch_add(1, Ordering::Relaxed);
+        thread::yield_now();
+    }
+}
+
+/// Holds a shared read access while a writer waits for it, then returns the
+/// value read by another reader arriving while the writer waits.
+fn read_with_writer_waiting<const ID: usize>(policy: Policy) -> i32 {
+    let rwlock = Arc::new(RwLock::<_, CountSpins<ID>>::with_policy(0, policy));
+
+    let rguard = rwlock.read();
+    let w = Arc::clone(&rwlock);
+    let writer = thread::spawn(move || *w.write() = 42);
+
+    // The writer spins once it found the read access held.
+    while SPINS[ID].load(Ordering::Relaxed) == 0 {
+        thread::yield_now();
+    }
+
+    let r = Arc::clone(&rwlock);
+    let reader = thread::spawn(move || *r.read());
+
+    let value = if policy == Policy::ReaderPreferring {
+        // The reader does not wait for the writer, which still waits for
+        // both read accesses to be released.
+        let value = reader.join().unwrap();
+        assert!(!rwlock.is_write_locked());
+        drop(rguard);
+        value
+    } else {
+        // The reader waits for the writer, which waits for the read access.
+        drop(rguard);
+        reader.join().unwrap()
+    };
+
+    writer.join().unwrap();
+    assert_eq!(*rwlock.read(), 42);
+    value
+}
+
+#[test]
+fn reader_preferring_policy() {
+    assert_eq!(read_with_writer_waiting::<0>(Policy::ReaderPreferring), 0);
+}
+
+#[test]
+fn writer_preferring_policy() {
+    assert_eq!(read_with_writer_waiting::<1>(Policy::WriterPreferring), 42);
+}
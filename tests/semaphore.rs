@@ -0,0 +1,62 @@
+use spinlock::Semaphore;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn try_acquire_exhausted() {
+    let semaphore = Semaphore::new(2);
+
+    let a = semaphore.try_acquire().unwrap();
+    let b = semaphore.try_acquire().unwrap();
+    assert_eq!(semaphore.available_permits(), 0);
+    assert!(semaphore.try_acquire().is_none());
+
+    drop(a);
+    assert_eq!(semaphore.available_permits(), 1);
+    assert!(semaphore.try_acquire().is_some());
+
+    drop(b);
+    assert_eq!(semaphore.available_permits(), 2);
+}
+
+#[test]
+fn no_permits() {
+    let semaphore = Semaphore::new(0);
+    assert!(semaphore.try_acquire().is_none());
+}
+
+#[test]
+fn max_concurrency_never_exceeded() {
+    const PERMITS: usize = 3;
+
+    let semaphore = Arc::new(Semaphore::new(PERMITS));
+    let current = Arc::new(AtomicUsize::new(0));
+    let high_water_mark = Arc::new(AtomicUsize::new(0));
+
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let semaphore = Arc::clone(&semaphore);
+            let current = Arc::clone(&current);
+            let high_water_mark = Arc::clone(&high_water_mark);
+            thread::spawn(move || {
+                for _ in 0..1_000 {
+                    let permit = semaphore.acquire();
+                    let now = current.fetch_add(1, Ordering::SeqCst) + 1;
+                    high_water_mark.fetch_max(now, Ordering::SeqCst);
+                    thread::yield_now();
+                    current.fetch_sub(1, Ordering::SeqCst);
+                    drop(permit);
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert!(high_water_mark.load(Ordering::SeqCst) <= PERMITS);
+    assert_eq!(semaphore.available_permits(), PERMITS);
+}
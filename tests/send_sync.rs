@@ -0,0 +1,60 @@
+use spinlock::{Mutex, RwLock, TicketMutex};
+
+use std::cell::Cell;
+
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+
+#[test]
+fn mutex_send_sync() {
+    assert_send::<Mutex<i32>>();
+    assert_sync::<Mutex<i32>>();
+
+    // A value which is only `Send` can still be sent within a mutex, and
+    // shared between threads since a single thread accesses it at a time.
+    assert_send::<Mutex<Cell<i32>>>();
+    assert_sync::<Mutex<Cell<i32>>>();
+}
+
+#[test]
+fn ticket_mutex_send_sync() {
+    assert_send::<TicketMutex<i32>>();
+    assert_sync::<TicketMutex<i32>>();
+
+    // A value which is only `Send` can be shared within a mutex.
+    assert_sync::<TicketMutex<Cell<i32>>>();
+}
+
+#[test]
+fn rwlock_send_sync() {
+    assert_send::<RwLock<i32>>();
+    assert_sync::<RwLock<i32>>();
+
+    assert_send::<RwLock<Cell<i32>>>();
+}
+
+#[cfg(all(feature = "send_guard", not(feature = "poison")))]
+#[test]
+fn guards_send() {
+    use spinlock::{
+        MappedMutexGuard, MappedRwLockReadGuard, MappedRwLockWriteGuard, MutexGuard, ReadStamp,
+        RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard, WriteStamp,
+    };
+    use std::thread;
+
+    assert_send::<MutexGuard<'static, i32>>();
+    assert_send::<MappedMutexGuard<'static, i32>>();
+    assert_send::<RwLockReadGuard<'static, i32>>();
+    assert_send::<RwLockWriteGuard<'static, i32>>();
+    assert_send::<RwLockUpgradableReadGuard<'static, i32>>();
+    assert_send::<MappedRwLockReadGuard<'static, i32>>();
+    assert_send::<MappedRwLockWriteGuard<'static, i32>>();
+    assert_send::<ReadStamp<'static, i32>>();
+    assert_send::<WriteStamp<'static, i32>>();
+
+    // The lock is released by the thread the guard was sent to.
+    static MUTEX: Mutex<i32> = Mutex::new(0);
+    let mut guard = MUTEX.lock();
+    thread::spawn(move || *guard = 42).join().unwrap();
+    assert_eq!(*MUTEX.lock(), 42);
+}
@@ -0,0 +1,49 @@
+use spinlock::SeqLock;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn write_then_read() {
+    let seqlock = SeqLock::new(0);
+
+    assert_eq!(seqlock.read(), 0);
+    seqlock.write(42);
+    assert_eq!(seqlock.read(), 42);
+    assert_eq!(seqlock.into_inner(), 42);
+}
+
+#[test]
+fn readers_never_observe_torn_values() {
+    let seqlock = Arc::new(SeqLock::new((0_u64, 0_u64)));
+    let done = Arc::new(AtomicBool::new(false));
+
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let seqlock = Arc::clone(&seqlock);
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                let mut last = 0;
+                while !done.load(Ordering::Relaxed) {
+                    let (a, b) = seqlock.read();
+                    assert_eq!(a, b);
+                    // The writes are observed in order.
+                    assert!(a >= last);
+                    last = a;
+                }
+            })
+        })
+        .collect();
+
+    for i in 1..=100_000 {
+        seqlock.write((i, i));
+    }
+    done.store(true, Ordering::Relaxed);
+
+    for reader in readers {
+        reader.join().unwrap();
+    }
+
+    assert_eq!(seqlock.read(), (100_000, 100_000));
+}
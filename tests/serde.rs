@@ -0,0 +1,29 @@
+#![cfg(feature = "serde")]
+
+use spinlock::{Mutex, RwLock};
+
+#[test]
+fn mutex_round_trip() {
+    let mutex = Mutex::new(vec![1, 2, 3]);
+
+    let json = serde_json::to_string(&mutex).unwrap();
+    assert_eq!(json, "[1,2,3]");
+    assert!(!mutex.is_locked());
+
+    let mutex: Mutex<Vec<i32>> = serde_json::from_str(&json).unwrap();
+    assert!(!mutex.is_locked());
+    assert_eq!(mutex.into_inner(), vec![1, 2, 3]);
+}
+
+#[test]
+fn rwlock_round_trip() {
+    let rwlock = RwLock::new(String::from("spinlock"));
+
+    let json = serde_json::to_string(&rwlock).unwrap();
+    assert_eq!(json, "\"spinlock\"");
+    assert_eq!(rwlock.reader_count(), 0);
+
+    let rwlock: RwLock<String> = serde_json::from_str(&json).unwrap();
+    assert!(!rwlock.is_write_locked());
+    assert_eq!(*rwlock.read(), "spinlock");
+}
@@ -0,0 +1,87 @@
+#![cfg(not(any(feature = "loom", feature = "poison")))]
+
+use spinlock::{Mutex, MutexGuard, Relax, RwLock};
+
+use std::cell::Cell;
+use std::mem;
+
+// Relaxes by counting the relaxations of the current thread, releasing the lock
+// waited for by the test on the fifth one.
+struct ReleaseOnFifth;
+
+thread_local! {
+    static RELAXES: Cell<u32> = const { Cell::new(0) };
+    static RELEASE: Cell<fn()> = const { Cell::new(|| {}) };
+}
+
+impl Relax for ReleaseOnFifth {
+    fn relax() {
+        RELAXES.set(RELAXES.get() + 1);
+        if RELAXES.get() == 5 {
+            RELEASE.get()();
+        }
+    }
+}
+
+/// Returns the number of relaxations of `wait`, which waits for a lock
+/// released by `release`.
+fn count_relaxes(release: fn(), wait: impl FnOnce()) -> u32 {
+    RELAXES.set(0);
+    RELEASE.set(release);
+    wait();
+    RELAXES.get()
+}
+
+static MUTEX_SPIN_HARD: Mutex<(), ReleaseOnFifth> = Mutex::with_spin_budget((), 8);
+static MUTEX_BACK_OFF: Mutex<(), ReleaseOnFifth> = Mutex::with_spin_budget((), 0);
+
+static RWLOCK_SPIN_HARD: RwLock<(), ReleaseOnFifth> = RwLock::with_spin_budget((), 8);
+static RWLOCK_BACK_OFF: RwLock<(), ReleaseOnFifth> = RwLock::with_spin_budget((), 0);
+
+#[test]
+#[cfg_attr(
+    feature = "critical-section",
+    ignore = "the critical section of the leaked guard is never exited"
+)]
+fn mutex_relax_count() {
+    // Within the budget the lock is checked after each relaxation.
+    let _ = MutexGuard::leak(MUTEX_SPIN_HARD.lock());
+    // SAFETY: The lock was leaked above.
+    let relaxes = count_relaxes(
+        || unsafe { MUTEX_SPIN_HARD.force_unlock() },
+        || drop(MUTEX_SPIN_HARD.lock()),
+    );
+    assert_eq!(relaxes, 5);
+
+    // Without a budget it backs off right away, relaxing 1 + 2 + 4 times
+    // before observing the release.
+    let _ = MutexGuard::leak(MUTEX_BACK_OFF.lock());
+    // SAFETY: The lock was leaked above.
+    let relaxes = count_relaxes(
+        || unsafe { MUTEX_BACK_OFF.force_unlock() },
+        || drop(MUTEX_BACK_OFF.lock()),
+    );
+    assert_eq!(relaxes, 7);
+}
+
+#[test]
+fn rwlock_relax_count() {
+    // Within the budget the lock is checked after each relaxation.
+    mem::forget(RWLOCK_SPIN_HARD.write());
+    // SAFETY: The write guard was forgotten above.
+    let relaxes = count_relaxes(
+        || unsafe { RWLOCK_SPIN_HARD.force_write_unlock() },
+        || drop(RWLOCK_SPIN_HARD.read()),
+    );
+    assert_eq!(relaxes, 5);
+
+    // Without a budget it backs off right away, relaxing 1 + 2 + 4 times
+    // before observing the release.
+    mem::forget(RWLOCK_BACK_OFF.write());
+    // SAFETY: The write guard was forgotten above.
+    let relaxes = count_relaxes(
+        || unsafe { RWLOCK_BACK_OFF.force_write_unlock() },
+        || drop(RWLOCK_BACK_OFF.read()),
+    );
+    assert_eq!(relaxes, 7);
+}
@@ -0,0 +1,127 @@
+use spinlock::{ReadStamp, StampedLock, WriteStamp};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn optimistic_read_validates() {
+    let lock = StampedLock::new((1_u64, 1_u64));
+
+    let stamp = lock.try_optimistic_read().unwrap();
+    // SAFETY: The copy is only used once the stamp is validated.
+    let value = unsafe { lock.read_optimistic() };
+    assert!(lock.validate(stamp));
+    assert_eq!(value, (1, 1));
+
+    // The shared read accesses don't invalidate the stamp.
+    let reader = lock.read();
+    assert!(lock.validate(stamp));
+    drop(reader);
+    assert!(lock.validate(stamp));
+}
+
+#[test]
+fn optimistic_read_invalidated_by_writer() {
+    let lock = StampedLock::new(1);
+
+    let stamp = lock.try_optimistic_read().unwrap();
+    let writer = lock.write();
+    assert!(!lock.validate(stamp));
+    assert!(lock.try_optimistic_read().is_none());
+    drop(writer);
+    assert!(!lock.validate(stamp));
+
+    let stamp = lock.try_optimistic_read().unwrap();
+    assert!(lock.validate(stamp));
+    assert!(lock.try_convert_to_read(stamp).is_some());
+}
+
+#[test]
+fn read_write_exclusion() {
+    let lock = StampedLock::new(0);
+
+    let reader = lock.read();
+    assert!(lock.try_write().is_none());
+    let other = lock.try_read().unwrap();
+    drop(reader);
+    assert!(lock.try_write().is_none());
+    drop(other);
+
+    let writer = lock.try_write().unwrap();
+    assert!(lock.try_read().is_none());
+    assert!(lock.try_write().is_none());
+    drop(writer);
+    assert!(lock.try_read().is_some());
+}
+
+#[test]
+fn stamp_conversions() {
+    let lock = StampedLock::new(1);
+
+    let stamp = lock.try_optimistic_read().unwrap();
+    let reader = lock.try_convert_to_read(stamp).unwrap();
+    // Other readers prevent the conversion to a write access.
+    assert!(lock.try_convert_to_write(stamp).is_none());
+
+    let mut writer = ReadStamp::try_upgrade(reader).unwrap();
+    *writer = 2;
+    let reader = WriteStamp::downgrade(writer);
+    assert!(!lock.validate(stamp));
+    assert_eq!(*reader, 2);
+    assert!(lock.try_write().is_none());
+
+    let stamp = ReadStamp::into_optimistic(reader);
+    let mut writer = lock.try_convert_to_write(stamp).unwrap();
+    *writer = 3;
+    let stamp = WriteStamp::into_optimistic(writer);
+    assert!(lock.validate(stamp));
+    assert_eq!(lock.into_inner(), 3);
+}
+
+#[test]
+fn try_upgrade_with_other_readers() {
+    let lock = StampedLock::new(1);
+
+    let reader = lock.read();
+    let other = lock.read();
+    let reader = ReadStamp::try_upgrade(reader).unwrap_err();
+    drop(other);
+    assert!(ReadStamp::try_upgrade(reader).is_ok());
+}
+
+#[test]
+fn optimistic_readers_never_trust_torn_values() {
+    let lock = Arc::new(StampedLock::new((0_u64, 0_u64)));
+    let done = Arc::new(AtomicBool::new(false));
+
+    let readers: Vec<_> = (0..2)
+        .map(|_| {
+            let lock = Arc::clone(&lock);
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                while !done.load(Ordering::Relaxed) {
+                    let Some(stamp) = lock.try_optimistic_read() else {
+                        continue;
+                    };
+                    // SAFETY: The copy is only used once the stamp is validated.
+                    let (a, b) = unsafe { lock.read_optimistic() };
+                    if lock.validate(stamp) {
+                        assert_eq!(a, b);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for i in 1..=10_000 {
+        *lock.write() = (i, i);
+    }
+    done.store(true, Ordering::Relaxed);
+
+    for reader in readers {
+        reader.join().unwrap();
+    }
+
+    assert_eq!(*lock.read(), (10_000, 10_000));
+}
@@ -0,0 +1,52 @@
+use spinlock::TicketMutex;
+
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn try_lock_on_unlocked() {
+    let mutex = TicketMutex::new(0);
+
+    assert!(mutex.try_lock().is_some());
+}
+
+#[test]
+fn try_lock_on_locked() {
+    let mutex = TicketMutex::new(0);
+
+    let _guard = mutex.lock();
+
+    assert!(mutex.try_lock().is_none());
+}
+
+#[test]
+fn try_lock_after_unlock() {
+    let mutex = TicketMutex::new(0);
+
+    drop(mutex.lock());
+    drop(mutex.try_lock().unwrap());
+
+    assert!(mutex.try_lock().is_some());
+}
+
+#[test]
+fn four_threads_count() {
+    let count = Arc::new(TicketMutex::new(0));
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let count = Arc::clone(&count);
+            thread::spawn(move || {
+                for _ in 0..10_000 {
+                    *count.lock() += 1;
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(*count.lock(), 40_000);
+}